@@ -0,0 +1,339 @@
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use crate::ssh_target::SshTarget;
+
+/// Serializes appends from concurrent sessions and transfers
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    TerminalSession,
+    SftpList,
+    SftpUpload,
+    SftpDownload,
+    SftpMkdir,
+    SftpDelete,
+    SftpRename,
+    SftpCopy,
+    SftpChmod,
+    SftpChown,
+    SftpSymlink,
+    SftpTouch,
+    DesktopConnect,
+    VncConnect,
+    KeyDeploy,
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::TerminalSession => "terminal_session",
+            Self::SftpList => "sftp_list",
+            Self::SftpUpload => "sftp_upload",
+            Self::SftpDownload => "sftp_download",
+            Self::SftpMkdir => "sftp_mkdir",
+            Self::SftpDelete => "sftp_delete",
+            Self::SftpRename => "sftp_rename",
+            Self::SftpCopy => "sftp_copy",
+            Self::SftpChmod => "sftp_chmod",
+            Self::SftpChown => "sftp_chown",
+            Self::SftpSymlink => "sftp_symlink",
+            Self::SftpTouch => "sftp_touch",
+            Self::DesktopConnect => "desktop_connect",
+            Self::VncConnect => "vnc_connect",
+            Self::KeyDeploy => "key_deploy",
+        }
+    }
+
+    /// Actions that count as "connecting" for the recent connections list
+    pub fn is_connection(&self) -> bool {
+        matches!(
+            self,
+            Self::TerminalSession | Self::SftpList | Self::DesktopConnect | Self::VncConnect
+        )
+    }
+}
+
+/// One line of the audit log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Unix time in milliseconds when the action started
+    pub timestamp_ms: u64,
+    pub action: AuditAction,
+    /// Local account that performed the action
+    pub local_user: String,
+    pub device_id: Option<String>,
+    pub host: String,
+    /// Remote account
+    pub user: String,
+    pub port: Option<u16>,
+    /// Path, session id or other action-specific detail
+    pub detail: Option<String>,
+    pub duration_ms: Option<u64>,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+impl AuditEntry {
+    pub fn new(action: AuditAction, target: &SshTarget) -> Self {
+        Self {
+            timestamp_ms: now_ms(),
+            action,
+            local_user: local_user(),
+            device_id: target.device_id.clone(),
+            host: target.host.clone(),
+            user: target.user.clone(),
+            port: target.port,
+            detail: None,
+            duration_ms: None,
+            success: true,
+            error: None,
+        }
+    }
+
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// Fill in duration and outcome, then append to the log
+    pub fn finish<T>(mut self, started: Instant, result: &Result<T, String>) {
+        self.duration_ms = Some(started.elapsed().as_millis() as u64);
+        if let Err(e) = result {
+            self.success = false;
+            self.error = Some(e.clone());
+        }
+        record(&self);
+    }
+}
+
+/// Run `f` and log it as `action` against `target`, whatever the outcome
+pub fn track<T>(
+    action: AuditAction,
+    target: &SshTarget,
+    detail: Option<&str>,
+    f: impl FnOnce() -> Result<T, String>,
+) -> Result<T, String> {
+    let mut entry = AuditEntry::new(action, target);
+    entry.detail = detail.map(str::to_string);
+    let started = Instant::now();
+    let result = f();
+    entry.finish(started, &result);
+    result
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AuditQuery {
+    #[serde(default)]
+    pub device_id: Option<String>,
+    #[serde(default)]
+    pub host: Option<String>,
+    #[serde(default)]
+    pub actions: Option<Vec<AuditAction>>,
+    #[serde(default)]
+    pub since_ms: Option<u64>,
+    #[serde(default)]
+    pub until_ms: Option<u64>,
+    /// Only failures (true) or only successes (false)
+    #[serde(default)]
+    pub failed: Option<bool>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+impl AuditQuery {
+    fn matches(&self, entry: &AuditEntry) -> bool {
+        self.device_id.as_ref().is_none_or(|id| entry.device_id.as_ref() == Some(id))
+            && self.host.as_ref().is_none_or(|h| entry.host.eq_ignore_ascii_case(h))
+            && self.actions.as_ref().is_none_or(|a| a.contains(&entry.action))
+            && self.since_ms.is_none_or(|t| entry.timestamp_ms >= t)
+            && self.until_ms.is_none_or(|t| entry.timestamp_ms <= t)
+            && self.failed.is_none_or(|f| entry.success != f)
+    }
+}
+
+/// A distinct place the user connected to, for quick reconnect
+#[derive(Debug, Clone, Serialize)]
+pub struct RecentConnection {
+    pub device_id: Option<String>,
+    pub host: String,
+    pub user: String,
+    pub port: Option<u16>,
+    pub action: AuditAction,
+    pub last_ms: u64,
+}
+
+pub fn log_path() -> PathBuf {
+    crate::config::config_path()
+        .parent()
+        .map(|p| p.join("audit.log"))
+        .unwrap_or_else(|| PathBuf::from("audit.log"))
+}
+
+/// Append an entry to the audit log. Failures are logged, never surfaced:
+/// auditing must not break the action being audited.
+pub fn record(entry: &AuditEntry) {
+    if let Err(e) = append(&log_path(), entry) {
+        log::warn!("Failed to write audit log: {}", e);
+    }
+}
+
+pub fn append(path: &Path, entry: &AuditEntry) -> Result<(), String> {
+    let line = serde_json::to_string(entry).map_err(|e| e.to_string())?;
+    let _guard = WRITE_LOCK.lock().unwrap();
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    writeln!(file, "{}", line).map_err(|e| format!("Failed to append: {}", e))
+}
+
+/// Entries matching `query`, newest first. Unreadable lines are skipped.
+pub fn query(path: &Path, query: &AuditQuery) -> Result<Vec<AuditEntry>, String> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let file = fs::File::open(path).map_err(|e| format!("Failed to open audit log: {}", e))?;
+    let mut entries: Vec<AuditEntry> = BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .filter(|entry| query.matches(entry))
+        .collect();
+    entries.reverse();
+    if let Some(limit) = query.limit {
+        entries.truncate(limit);
+    }
+    Ok(entries)
+}
+
+/// Most recent distinct connection targets, newest first
+pub fn recent_connections(path: &Path, limit: usize) -> Result<Vec<RecentConnection>, String> {
+    let mut recent: Vec<RecentConnection> = Vec::new();
+    let connections = query(
+        path,
+        &AuditQuery {
+            failed: Some(false),
+            ..Default::default()
+        },
+    )?;
+    for entry in connections.into_iter().filter(|e| e.action.is_connection()) {
+        let seen = recent.iter().any(|r| {
+            r.host == entry.host
+                && r.user == entry.user
+                && r.port == entry.port
+                && r.action == entry.action
+        });
+        if !seen {
+            recent.push(RecentConnection {
+                device_id: entry.device_id,
+                host: entry.host,
+                user: entry.user,
+                port: entry.port,
+                action: entry.action,
+                last_ms: entry.timestamp_ms,
+            });
+        }
+        if recent.len() >= limit {
+            break;
+        }
+    }
+    Ok(recent)
+}
+
+pub fn to_csv(entries: &[AuditEntry]) -> String {
+    let mut out = String::from(
+        "timestamp_ms,action,local_user,device_id,host,user,port,detail,duration_ms,success,error\n",
+    );
+    for e in entries {
+        let fields = [
+            e.timestamp_ms.to_string(),
+            e.action.as_str().to_string(),
+            e.local_user.clone(),
+            e.device_id.clone().unwrap_or_default(),
+            e.host.clone(),
+            e.user.clone(),
+            e.port.map(|p| p.to_string()).unwrap_or_default(),
+            e.detail.clone().unwrap_or_default(),
+            e.duration_ms.map(|d| d.to_string()).unwrap_or_default(),
+            e.success.to_string(),
+            e.error.clone().unwrap_or_default(),
+        ];
+        let row: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        out.push_str(&row.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+pub fn local_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(action: AuditAction, host: &str, ts: u64, success: bool) -> AuditEntry {
+        let mut e = AuditEntry::new(action, &SshTarget::new(host, "lab", None));
+        e.timestamp_ms = ts;
+        e.success = success;
+        e
+    }
+
+    #[test]
+    fn test_query_and_recent_connections() {
+        let path = std::env::temp_dir().join(format!("remotelab-audit-{}.log", std::process::id()));
+        fs::remove_file(&path).ok();
+        append(&path, &entry(AuditAction::TerminalSession, "gpu1", 1, true)).unwrap();
+        append(&path, &entry(AuditAction::SftpUpload, "gpu1", 2, false)).unwrap();
+        append(&path, &entry(AuditAction::TerminalSession, "gpu2", 3, true)).unwrap();
+        append(&path, &entry(AuditAction::TerminalSession, "gpu1", 4, true)).unwrap();
+
+        let failed = query(&path, &AuditQuery { failed: Some(true), ..Default::default() }).unwrap();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].action, AuditAction::SftpUpload);
+
+        let since = query(&path, &AuditQuery { since_ms: Some(3), ..Default::default() }).unwrap();
+        assert_eq!(since.iter().map(|e| e.timestamp_ms).collect::<Vec<_>>(), vec![4, 3]);
+
+        let recent = recent_connections(&path, 10).unwrap();
+        let hosts: Vec<_> = recent.iter().map(|r| (r.host.as_str(), r.last_ms)).collect();
+        assert_eq!(hosts, vec![("gpu1", 4), ("gpu2", 3)]);
+        fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_csv_escaping() {
+        let mut e = entry(AuditAction::SftpDelete, "gpu1", 5, false);
+        e.detail = Some("/data/a,b \"c\"".into());
+        let csv = to_csv(&[e]);
+        let row = csv.lines().nth(1).unwrap();
+        assert!(row.starts_with("5,sftp_delete,"));
+        assert!(row.contains("\"/data/a,b \"\"c\"\"\""));
+    }
+}
@@ -0,0 +1,40 @@
+use crate::audit::{self, AuditEntry, AuditQuery, RecentConnection};
+
+#[tauri::command]
+pub async fn audit_query(query: Option<AuditQuery>) -> Result<Vec<AuditEntry>, String> {
+    let query = query.unwrap_or_default();
+    tokio::task::spawn_blocking(move || audit::query(&audit::log_path(), &query))
+        .await
+        .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Write matching entries to `path` as "json" or "csv". Returns how many
+/// entries were exported.
+#[tauri::command]
+pub async fn audit_export(
+    format: String,
+    path: String,
+    query: Option<AuditQuery>,
+) -> Result<usize, String> {
+    let query = query.unwrap_or_default();
+    tokio::task::spawn_blocking(move || {
+        let entries = audit::query(&audit::log_path(), &query)?;
+        let body = match format.as_str() {
+            "json" => serde_json::to_string_pretty(&entries).map_err(|e| e.to_string())?,
+            "csv" => audit::to_csv(&entries),
+            other => return Err(format!("Unsupported export format: {}", other)),
+        };
+        std::fs::write(&path, body).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+        Ok(entries.len())
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+#[tauri::command]
+pub async fn recent_connections(limit: Option<usize>) -> Result<Vec<RecentConnection>, String> {
+    let limit = limit.unwrap_or(10);
+    tokio::task::spawn_blocking(move || audit::recent_connections(&audit::log_path(), limit))
+        .await
+        .map_err(|e| format!("Task failed: {}", e))?
+}
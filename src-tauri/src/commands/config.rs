@@ -0,0 +1,42 @@
+use crate::config::{self, store, ConfigState};
+use tauri::State;
+
+#[tauri::command]
+pub async fn config_backups_list() -> Result<Vec<store::BackupInfo>, String> {
+    store::list_backups(&config::backups_dir())
+}
+
+/// Replace the live config with a backup. Encrypted backups are opened with
+/// `password`, falling back to the currently unlocked one. The config being
+/// replaced goes into the rotation first, so a restore can itself be undone.
+#[tauri::command]
+pub async fn config_backup_restore(
+    name: String,
+    password: Option<String>,
+    config: State<'_, ConfigState>,
+) -> Result<(), String> {
+    let path = store::backup_path(&config::backups_dir(), &name)?;
+    let data = std::fs::read(&path).map_err(|e| format!("Read failed: {}", e))?;
+
+    let (json, used_password) = if crate::crypto::is_encrypted(&data) {
+        let pw = password
+            .or_else(|| config.1.lock().unwrap().clone())
+            .ok_or("Backup is encrypted; a password is required")?;
+        (crate::crypto::decrypt_config(&data, &pw)?, Some(pw))
+    } else {
+        let json =
+            String::from_utf8(data).map_err(|_| "Backup is not valid UTF-8".to_string())?;
+        (json, None)
+    };
+
+    let (restored, _) =
+        config::parse_config(&json).map_err(|e| format!("Invalid backup: {}", e))?;
+    if used_password.is_some() {
+        *config.1.lock().unwrap() = used_password;
+    }
+    let mut cfg = config.0.lock().unwrap();
+    *cfg = restored;
+    config.persist(&cfg)?;
+    log::info!("Restored config from backup {}", name);
+    Ok(())
+}
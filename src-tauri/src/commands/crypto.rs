@@ -19,8 +19,13 @@ pub async fn unlock_config(
     let path = config::config_path();
     let data = std::fs::read(&path).map_err(|e| format!("Read failed: {}", e))?;
     let json = crate::crypto::decrypt_config(&data, &password)?;
-    let app_config: config::AppConfig =
-        serde_json::from_str(&json).map_err(|e| format!("Invalid config: {}", e))?;
+    let (app_config, from) =
+        config::parse_config(&json).map_err(|e| format!("Invalid config: {}", e))?;
+    if from < config::migrations::CURRENT_SCHEMA_VERSION {
+        config::backup_before_migration(from)?;
+        config::save_config_with_password(&app_config, &Some(password.clone()))
+            .map_err(|e| format!("Save failed: {}", e))?;
+    }
     *config.0.lock().unwrap() = app_config;
     *config.1.lock().unwrap() = Some(password);
     Ok(())
@@ -31,17 +36,29 @@ pub async fn set_config_password(
     password: String,
     config: State<'_, ConfigState>,
 ) -> Result<(), String> {
-    *config.1.lock().unwrap() = Some(password.clone());
-    let cfg = config.0.lock().unwrap().clone();
-    config::save_config_with_password(&cfg, &Some(password))
-        .map_err(|e| format!("Save failed: {}", e))
+    // Held across the write so no other save interleaves with this one
+    let cfg = config.0.lock().unwrap();
+    let previous = config.1.lock().unwrap().replace(password);
+    if let Err(e) = config.persist(&cfg) {
+        // The file still has the old encryption (or none); keep saving that way
+        *config.1.lock().unwrap() = previous;
+        return Err(format!("Save failed: {}", e));
+    }
+    // Older backups would otherwise keep the devices in plaintext
+    let removed = config::store::purge_plaintext_backups(&config::backups_dir())?
+        + config::purge_plaintext_migration_backups()?;
+    log::info!("Removed {} plaintext config backups", removed);
+    Ok(())
 }
 
 #[tauri::command]
 pub async fn remove_config_password(
     config: State<'_, ConfigState>,
 ) -> Result<(), String> {
-    *config.1.lock().unwrap() = None;
-    let cfg = config.0.lock().unwrap().clone();
-    config::save_config_with_password(&cfg, &None).map_err(|e| format!("Save failed: {}", e))
+    let cfg = config.0.lock().unwrap();
+    let previous = config.1.lock().unwrap().take();
+    config.persist(&cfg).map_err(|e| {
+        *config.1.lock().unwrap() = previous;
+        format!("Save failed: {}", e)
+    })
 }
@@ -1,5 +1,9 @@
+use crate::audit::{AuditAction, AuditEntry};
+use crate::config::ConfigState;
 use crate::desktop::{detect, launcher, sunshine, turbovnc, VncProxy};
+use crate::ssh_target::SshTarget;
 use serde::Serialize;
+use std::time::Instant;
 use tauri::{AppHandle, Emitter, State};
 
 /// Connection result returned to frontend
@@ -33,21 +37,33 @@ pub async fn desktop_connect(
     host: String,
     user: String,
     port: Option<u16>,
+    device_id: Option<String>,
     proxy: State<'_, VncProxy>,
+    config: State<'_, ConfigState>,
+) -> Result<DesktopConnection, String> {
+    let target = config.resolve_target(&host, &user, port, device_id.as_deref())?;
+    let entry = AuditEntry::new(AuditAction::DesktopConnect, &target);
+    let started = Instant::now();
+    let result = smart_connect(&app, target, &proxy).await;
+    entry.finish(started, &result);
+    result
+}
+
+async fn smart_connect(
+    app: &AppHandle,
+    target: SshTarget,
+    proxy: &VncProxy,
 ) -> Result<DesktopConnection, String> {
     log::info!(
-        "Desktop connect to {}@{}:{}, detecting GPU...",
-        user,
-        host,
-        port.unwrap_or(22)
+        "Desktop connect to {}, detecting GPU...",
+        target.destination()
     );
 
     // Step 1: Detect GPU
-    emit_progress(&app, "gpu_detect", 0, "Detecting GPU...");
+    emit_progress(app, "gpu_detect", 0, "Detecting GPU...");
 
-    let h = host.clone();
-    let u = user.clone();
-    let gpu = tokio::task::spawn_blocking(move || detect::detect_remote_gpu(&h, &u, port))
+    let t = target.clone();
+    let gpu = tokio::task::spawn_blocking(move || detect::detect_remote_gpu(&t))
         .await
         .map_err(|e| format!("GPU detect task failed: {}", e))?
         .unwrap_or_else(|e| {
@@ -69,7 +85,7 @@ pub async fn desktop_connect(
 
     let nvenc_label = if gpu.has_nvenc { "NVENC" } else { "No NVENC" };
     emit_progress(
-        &app,
+        app,
         "gpu_detect",
         5,
         &format!("GPU: {} ({})", gpu.gpu_name, nvenc_label),
@@ -77,24 +93,23 @@ pub async fn desktop_connect(
 
     // Step 2: Choose method based on GPU capabilities
     if gpu.has_nvenc {
-        log::info!("GPU has NVENC, setting up Sunshine on {}", host);
-        emit_progress(&app, "sunshine_setup", 5, "Starting Sunshine setup...");
+        log::info!("GPU has NVENC, setting up Sunshine on {}", target.host);
+        emit_progress(app, "sunshine_setup", 5, "Starting Sunshine setup...");
 
-        let h = host.clone();
-        let u = user.clone();
+        let t = target.clone();
         let app2 = app.clone();
         let sunshine_result = tokio::task::spawn_blocking(move || {
-            sunshine::setup_sunshine(&h, &u, port, &app2)
+            sunshine::setup_sunshine(&t, &app2)
         })
         .await
         .map_err(|e| format!("Sunshine task failed: {}", e))?;
 
         match sunshine_result {
             Ok(remote_port) => {
-                emit_progress(&app, "tunnel", 95, "Creating secure tunnel...");
-                let local_port = proxy.start_tunnel(&host, &user, remote_port, port)?;
+                emit_progress(app, "tunnel", 95, "Creating secure tunnel...");
+                let local_port = proxy.start_tunnel(&target, remote_port)?;
 
-                emit_progress(&app, "done", 100, "Connected!");
+                emit_progress(app, "done", 100, "Connected!");
                 return Ok(DesktopConnection {
                     mode: "sunshine".to_string(),
                     url: format!("https://127.0.0.1:{}", local_port),
@@ -105,7 +120,7 @@ pub async fn desktop_connect(
             Err(e) => {
                 log::warn!("Sunshine failed: {}, falling back to VNC", e);
                 emit_progress(
-                    &app,
+                    app,
                     "vnc_fallback",
                     40,
                     &format!("Sunshine unavailable ({}), using VNC...", e),
@@ -115,28 +130,27 @@ pub async fn desktop_connect(
     }
 
     // VNC path: x11vnc with auto-install
-    log::info!("Setting up VNC for {}", host);
-    emit_progress(&app, "vnc_setup", 50, "Setting up VNC server...");
+    log::info!("Setting up VNC for {}", target.host);
+    emit_progress(app, "vnc_setup", 50, "Setting up VNC server...");
 
-    let h = host.clone();
-    let u = user.clone();
+    let t = target.clone();
     let app3 = app.clone();
     let vnc_port = tokio::task::spawn_blocking(move || {
-        turbovnc::setup_turbovnc(&h, &u, port, &app3)
+        turbovnc::setup_turbovnc(&t, &app3)
     })
     .await
     .map_err(|e| format!("VNC setup task failed: {}", e))??;
 
     log::info!("VNC on remote port {}, starting tunnel", vnc_port);
-    emit_progress(&app, "tunnel", 85, "Creating SSH tunnel...");
+    emit_progress(app, "tunnel", 85, "Creating SSH tunnel...");
 
-    proxy.start_tunnel(&host, &user, vnc_port, port)?;
+    proxy.start_tunnel(&target, vnc_port)?;
     tokio::time::sleep(std::time::Duration::from_millis(500)).await;
 
-    emit_progress(&app, "proxy", 90, "Starting WebSocket proxy...");
+    emit_progress(app, "proxy", 90, "Starting WebSocket proxy...");
     let ws_port = proxy.start_ws_proxy().await?;
 
-    emit_progress(&app, "done", 100, "Connected!");
+    emit_progress(app, "done", 100, "Connected!");
     log::info!("WebSocket proxy ready on ws://127.0.0.1:{}", ws_port);
 
     Ok(DesktopConnection {
@@ -153,8 +167,11 @@ pub async fn detect_gpu(
     host: String,
     user: String,
     port: Option<u16>,
+    device_id: Option<String>,
+    config: State<'_, ConfigState>,
 ) -> Result<detect::GpuInfo, String> {
-    detect::detect_remote_gpu(&host, &user, port)
+    let target = config.resolve_target(&host, &user, port, device_id.as_deref())?;
+    detect::detect_remote_gpu(&target)
 }
 
 /// Legacy VNC connect (direct, skip auto-detect)
@@ -164,9 +181,16 @@ pub async fn vnc_connect(
     user: String,
     vnc_port: Option<u16>,
     port: Option<u16>,
+    device_id: Option<String>,
     proxy: State<'_, VncProxy>,
+    config: State<'_, ConfigState>,
 ) -> Result<u16, String> {
-    proxy.connect(&host, &user, vnc_port, port).await
+    let target = config.resolve_target(&host, &user, port, device_id.as_deref())?;
+    let entry = AuditEntry::new(AuditAction::VncConnect, &target);
+    let started = Instant::now();
+    let result = proxy.connect(&target, vnc_port).await;
+    entry.finish(started, &result);
+    result
 }
 
 /// Stop desktop connection
@@ -1,41 +1,88 @@
-use crate::config::{ConfigState, Device, save_config};
+use crate::config::merge::{self, MergeReport, MergeSelection, Resolutions};
+use crate::config::{groups, validate, AppConfig, ConfigState, Device, WakeOnLan};
+use crate::health::{probe, DeviceHealth, HealthMonitor};
+use std::collections::HashMap;
+use crate::sshconfig::convert::{self, ImportCandidate};
+use crate::sshconfig::parser;
 use serde::Serialize;
-use tauri::State;
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::{AppHandle, State};
 
 #[derive(Debug, Clone, Serialize)]
 pub struct DeviceWithStatus {
+    /// Effective settings, with group defaults filled in
     #[serde(flatten)]
     pub device: Device,
     pub online: bool,
+    /// Last probe result, None until the monitor has checked the device
+    pub health: Option<DeviceHealth>,
+    /// Fields taken from group defaults rather than set on the device
+    pub inherited: Vec<String>,
 }
 
+/// List devices, optionally only those carrying `tag` and/or filed under
+/// `group` (including its subgroups). Status comes from the health
+/// monitor's cache, so this never blocks on the network.
 #[tauri::command]
 pub async fn list_devices(
+    tag: Option<String>,
+    group: Option<String>,
     config: State<'_, ConfigState>,
+    health: State<'_, HealthMonitor>,
 ) -> Result<Vec<DeviceWithStatus>, String> {
     let cfg = config.0.lock().unwrap().clone();
-    log::info!("list_devices: checking {} devices", cfg.devices.len());
-    let mut results = Vec::new();
-
-    for device in &cfg.devices {
-        let check_host = device.ssh_host.as_deref().unwrap_or(&device.vpn_ip);
-        let online = if device.ssh_port.is_some() || device.ssh_host.is_some() {
-            // For non-VPN devices, check TCP connectivity instead of ping
-            check_tcp(check_host, device.ssh_port.unwrap_or(22))
-        } else {
-            check_online(&device.vpn_ip)
-        };
-        log::info!("  {} ({}) -> online={}", device.name, check_host, online);
-        results.push(DeviceWithStatus {
-            device: device.clone(),
-            online,
-        });
-    }
+    let tag = tag.map(|t| t.trim().to_lowercase());
+    let devices: Vec<&Device> = cfg
+        .devices
+        .iter()
+        .filter(|d| tag.as_ref().is_none_or(|t| d.tags.contains(t)))
+        .filter(|d| {
+            group.as_deref().is_none_or(|g| {
+                d.group.as_deref().is_some_and(|dg| cfg.group_is_within(dg, g))
+            })
+        })
+        .collect();
+
+    Ok(devices
+        .into_iter()
+        .map(|device| {
+            let (device, inherited) = cfg.effective_device(device);
+            let status = health.get(&device.id);
+            DeviceWithStatus {
+                online: status.as_ref().is_some_and(|h| h.online),
+                health: status,
+                device,
+                inherited,
+            }
+        })
+        .collect())
+}
 
-    Ok(results)
+/// Probe every device now instead of waiting for the next scheduled round
+#[tauri::command]
+pub async fn refresh_device_status(
+    app: AppHandle,
+    health: State<'_, HealthMonitor>,
+) -> Result<Vec<DeviceHealth>, String> {
+    Ok(health.probe_all(&app).await)
+}
+
+/// Change how often the health monitor probes devices
+#[tauri::command]
+pub async fn set_health_interval(
+    seconds: u64,
+    health: State<'_, HealthMonitor>,
+) -> Result<(), String> {
+    if seconds < 5 {
+        return Err("Interval must be at least 5 seconds".to_string());
+    }
+    health.set_interval(Duration::from_secs(seconds));
+    Ok(())
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn add_device(
     name: String,
     vpn_ip: String,
@@ -43,30 +90,72 @@ pub async fn add_device(
     rustdesk_id: Option<String>,
     ssh_host: Option<String>,
     ssh_port: Option<u16>,
+    jump_hosts: Option<Vec<String>>,
+    identity_file: Option<String>,
+    group: Option<String>,
+    tags: Option<Vec<String>>,
+    wol: Option<WakeOnLan>,
+    record_sessions: Option<bool>,
     config: State<'_, ConfigState>,
+    health: State<'_, HealthMonitor>,
 ) -> Result<Device, String> {
     let mut cfg = config.0.lock().unwrap();
 
-    // Generate ID from name
-    let id = name.to_lowercase().replace(' ', "-");
-
-    // Check for duplicates
-    if cfg.devices.iter().any(|d| d.id == id) {
-        return Err(format!("Device '{}' already exists", id));
-    }
-
     let device = Device {
-        id,
-        name,
-        vpn_ip,
-        ssh_user,
+        id: Device::new_id(),
+        name: name.trim().to_string(),
+        vpn_ip: vpn_ip.trim().to_string(),
+        ssh_user: ssh_user.trim().to_string(),
         rustdesk_id,
         ssh_host,
         ssh_port,
+        jump_hosts,
+        identity_file,
+        group,
+        tags: groups::normalize_tags(tags.unwrap_or_default()),
+        wol,
+        record_sessions: record_sessions.unwrap_or(false),
+        ..Default::default()
     };
+    validate::validate_device(&device, &cfg)?;
 
     cfg.devices.push(device.clone());
-    save_config(&cfg).map_err(|e| format!("Failed to save config: {}", e))?;
+    config.persist(&cfg).map_err(|e| format!("Failed to save config: {}", e))?;
+    health.refresh();
+
+    Ok(device)
+}
+
+/// Replace every field of the device with the same id. The id itself is
+/// fixed at creation, so renames keep jump host references intact. `device`
+/// may be the effective one from `list_devices`: inherited fields left
+/// unchanged stay inherited.
+#[tauri::command]
+pub async fn update_device(
+    device: Device,
+    config: State<'_, ConfigState>,
+    health: State<'_, HealthMonitor>,
+) -> Result<Device, String> {
+    let mut cfg = config.0.lock().unwrap();
+    if cfg.find_device(&device.id).is_none() {
+        return Err(format!("Device '{}' not found", device.id));
+    }
+    let device = cfg.strip_inherited(device);
+
+    let device = Device {
+        name: device.name.trim().to_string(),
+        vpn_ip: device.vpn_ip.trim().to_string(),
+        ssh_user: device.ssh_user.trim().to_string(),
+        tags: groups::normalize_tags(device.tags),
+        ..device
+    };
+    validate::validate_device(&device, &cfg)?;
+
+    if let Some(slot) = cfg.devices.iter_mut().find(|d| d.id == device.id) {
+        *slot = device.clone();
+    }
+    config.persist(&cfg).map_err(|e| format!("Failed to save config: {}", e))?;
+    health.refresh();
 
     Ok(device)
 }
@@ -77,14 +166,38 @@ pub async fn remove_device(
     config: State<'_, ConfigState>,
 ) -> Result<(), String> {
     let mut cfg = config.0.lock().unwrap();
+    if let Some(dependent) = cfg.devices.iter().find(|d| d.jumps().contains(&id)) {
+        return Err(format!(
+            "Device '{}' is used as a jump host by '{}'",
+            id, dependent.id
+        ));
+    }
+    if let Some(dependent) = cfg
+        .devices
+        .iter()
+        .find(|d| d.wol.as_ref().and_then(|w| w.relay.as_deref()) == Some(id.as_str()))
+    {
+        return Err(format!(
+            "Device '{}' relays Wake-on-LAN for '{}'",
+            id, dependent.id
+        ));
+    }
+    if let Some(group) = cfg.groups.iter().find(|g| {
+        g.defaults.jump_hosts.as_ref().is_some_and(|j| j.contains(&id))
+    }) {
+        return Err(format!(
+            "Device '{}' is the default jump host of group '{}'",
+            id, group.name
+        ));
+    }
     cfg.devices.retain(|d| d.id != id);
-    save_config(&cfg).map_err(|e| format!("Failed to save config: {}", e))?;
+    config.persist(&cfg).map_err(|e| format!("Failed to save config: {}", e))?;
     Ok(())
 }
 
 #[tauri::command]
 pub async fn ping_device(ip: String) -> Result<bool, String> {
-    Ok(check_online(&ip))
+    Ok(probe::probe_ping(&ip).await.online)
 }
 
 #[tauri::command]
@@ -100,34 +213,191 @@ pub async fn export_config(
 pub async fn import_config(
     json_str: String,
     config: State<'_, ConfigState>,
+    health: State<'_, HealthMonitor>,
 ) -> Result<(), String> {
-    let new_config: crate::config::AppConfig = serde_json::from_str(&json_str)
-        .map_err(|e| format!("Invalid config JSON: {}", e))?;
+    let (new_config, _) = crate::config::parse_config(&json_str)
+        .map_err(|e| format!("Invalid config: {}", e))?;
     let mut cfg = config.0.lock().unwrap();
     *cfg = new_config;
-    save_config(&cfg).map_err(|e| format!("Failed to save config: {}", e))?;
+    config.persist(&cfg).map_err(|e| format!("Failed to save config: {}", e))?;
+    health.refresh();
     Ok(())
 }
 
-fn check_online(ip: &str) -> bool {
-    #[cfg(unix)]
-    let result = std::process::Command::new("ping")
-        .args(["-c", "1", "-W", "2", ip])
-        .output();
-    #[cfg(windows)]
-    let result = std::process::Command::new("ping")
-        .args(["-n", "1", "-w", "2000", ip])
-        .output();
-    result.map(|o| o.status.success()).unwrap_or(false)
-}
-
-fn check_tcp(host: &str, port: u16) -> bool {
-    use std::net::{TcpStream, ToSocketAddrs};
-    use std::time::Duration;
-    let addr = format!("{}:{}", host, port);
-    addr.to_socket_addrs()
-        .ok()
-        .and_then(|mut addrs| addrs.next())
-        .map(|addr| TcpStream::connect_timeout(&addr, Duration::from_secs(3)).is_ok())
-        .unwrap_or(false)
+/// Show what merging an exported config into ours would do
+#[tauri::command]
+pub async fn import_config_preview(
+    json_str: String,
+    selection: Option<MergeSelection>,
+    config: State<'_, ConfigState>,
+) -> Result<MergeReport, String> {
+    let (theirs, _) = crate::config::parse_config(&json_str)
+        .map_err(|e| format!("Invalid config: {}", e))?;
+    let cfg = config.0.lock().unwrap();
+    Ok(merge::plan_merge(&cfg, &theirs, &selection.unwrap_or_default()))
+}
+
+/// Merge an exported config into ours instead of replacing it. Conflicting
+/// fields keep our value unless `resolutions` says otherwise.
+#[tauri::command]
+pub async fn import_config_merge(
+    json_str: String,
+    selection: Option<MergeSelection>,
+    resolutions: Option<Resolutions>,
+    config: State<'_, ConfigState>,
+    health: State<'_, HealthMonitor>,
+) -> Result<MergeReport, String> {
+    let (theirs, _) = crate::config::parse_config(&json_str)
+        .map_err(|e| format!("Invalid config: {}", e))?;
+    let mut cfg = config.0.lock().unwrap();
+    let mut merged = cfg.clone();
+    let report = merge::apply_merge(
+        &mut merged,
+        &theirs,
+        &selection.unwrap_or_default(),
+        &resolutions.unwrap_or_default(),
+    )?;
+    *cfg = merged;
+    config.persist(&cfg).map_err(|e| format!("Failed to save config: {}", e))?;
+    health.refresh();
+    Ok(report)
+}
+
+fn ssh_config_candidates(
+    path: Option<String>,
+    cfg: &AppConfig,
+) -> Result<Vec<ImportCandidate>, String> {
+    let path = match path {
+        Some(p) => PathBuf::from(p),
+        None => parser::default_config_path()?,
+    };
+    let entries = parser::parse_file(&path)?;
+    Ok(convert::entries_to_candidates(&entries, cfg))
+}
+
+/// Parse ~/.ssh/config (or `path`) and show what an import would add
+#[tauri::command]
+pub async fn ssh_config_preview(
+    path: Option<String>,
+    config: State<'_, ConfigState>,
+) -> Result<Vec<ImportCandidate>, String> {
+    let cfg = config.0.lock().unwrap().clone();
+    ssh_config_candidates(path, &cfg)
+}
+
+/// Import the selected ssh_config aliases. Jump hosts they depend on are
+/// pulled in automatically; existing devices are only replaced if `overwrite`.
+#[tauri::command]
+pub async fn ssh_config_import(
+    path: Option<String>,
+    aliases: Vec<String>,
+    overwrite: bool,
+    config: State<'_, ConfigState>,
+    health: State<'_, HealthMonitor>,
+) -> Result<Vec<Device>, String> {
+    let mut cfg = config.0.lock().unwrap();
+    let candidates = ssh_config_candidates(path, &cfg)?;
+
+    // Selected aliases plus everything they jump through
+    let mut wanted: Vec<String> = candidates
+        .iter()
+        .filter(|c| aliases.contains(&c.alias))
+        .map(|c| c.device.id.clone())
+        .collect();
+    let mut i = 0;
+    while i < wanted.len() {
+        if let Some(c) = candidates.iter().find(|c| c.device.id == wanted[i]) {
+            for jump in c.device.jumps() {
+                if !wanted.contains(jump) && candidates.iter().any(|c| &c.device.id == jump) {
+                    wanted.push(jump.clone());
+                }
+            }
+        }
+        i += 1;
+    }
+
+    // Candidate ids are provisional: matches keep the existing device's id,
+    // everything else gets a fresh one
+    let ids: HashMap<&str, String> = candidates
+        .iter()
+        .map(|c| {
+            let id = c.existing_id.clone().unwrap_or_else(Device::new_id);
+            (c.device.id.as_str(), id)
+        })
+        .collect();
+    let remap = |id: &str| -> String { ids.get(id).cloned().unwrap_or_else(|| id.to_string()) };
+
+    let mut updated = cfg.clone();
+    let mut imported = Vec::new();
+    for candidate in candidates.iter().filter(|c| wanted.contains(&c.device.id)) {
+        let mut device = candidate.device.clone();
+        device.id = remap(&device.id);
+        if let Some(jumps) = device.jump_hosts.as_mut() {
+            for jump in jumps.iter_mut() {
+                *jump = remap(jump);
+            }
+        }
+
+        match candidate.existing_id.as_deref() {
+            Some(existing) if overwrite => {
+                if let Some(slot) = updated.devices.iter_mut().find(|d| d.id == existing) {
+                    // Only what ssh_config knows about; groups, tags, Wake-on-LAN
+                    // and session settings stay as they were
+                    convert::apply_connection(slot, &device);
+                    imported.push(slot.clone());
+                }
+            }
+            Some(_) => continue,
+            None => {
+                updated.devices.push(device.clone());
+                imported.push(device);
+            }
+        }
+    }
+    // Checked once everything is in, since devices may jump through each other
+    for device in &imported {
+        validate::validate_device(device, &updated)
+            .map_err(|e| format!("Cannot import '{}': {}", device.name, e))?;
+    }
+
+    *cfg = updated;
+    config.persist(&cfg).map_err(|e| format!("Failed to save config: {}", e))?;
+    health.refresh();
+    log::info!("Imported {} devices from ssh_config", imported.len());
+    Ok(imported)
+}
+
+/// Render devices (all, or `device_ids` plus their jump hosts) as an
+/// ssh_config fragment, optionally writing it to `path`
+#[tauri::command]
+pub async fn ssh_config_export(
+    device_ids: Option<Vec<String>>,
+    path: Option<String>,
+    config: State<'_, ConfigState>,
+) -> Result<String, String> {
+    let cfg = config.0.lock().unwrap().clone();
+    // Exported with group defaults applied, since ssh has no notion of them
+    let devices: Vec<Device> = match device_ids {
+        Some(ids) => {
+            let mut wanted = Vec::new();
+            for id in &ids {
+                for jump in cfg.jump_chain_ids(id)? {
+                    if !wanted.contains(&jump) {
+                        wanted.push(jump);
+                    }
+                }
+                if !wanted.contains(id) {
+                    wanted.push(id.clone());
+                }
+            }
+            wanted.iter().filter_map(|id| cfg.effective_device_by_id(id)).collect()
+        }
+        None => cfg.devices.iter().map(|d| cfg.effective_device(d).0).collect(),
+    };
+
+    let fragment = convert::devices_to_ssh_config(&devices.iter().collect::<Vec<_>>());
+    if let Some(p) = path {
+        std::fs::write(&p, &fragment).map_err(|e| format!("Failed to write {}: {}", p, e))?;
+    }
+    Ok(fragment)
 }
@@ -1,6 +1,10 @@
-use tauri::AppHandle;
+use tauri::{AppHandle, State};
 
-use crate::filetransfer::ops::{self, RemoteFile};
+use crate::config::ConfigState;
+use crate::filetransfer::ops::{self, OpResult, RemoteFile, RemoteOp};
+use crate::filetransfer::queue::{Direction, TransferJob, TransferManager};
+use crate::filetransfer::sync::{self, SyncOptions, SyncPlan};
+use crate::filetransfer::tree::{self, TreeOptions, TreePlan};
 
 #[tauri::command]
 pub async fn sftp_list(
@@ -8,39 +12,135 @@ pub async fn sftp_list(
     user: String,
     port: Option<u16>,
     path: String,
+    device_id: Option<String>,
+    config: State<'_, ConfigState>,
 ) -> Result<Vec<RemoteFile>, String> {
-    tokio::task::spawn_blocking(move || ops::list_remote_dir(&host, &user, port, &path))
+    let target = config.resolve_target(&host, &user, port, device_id.as_deref())?;
+    tokio::task::spawn_blocking(move || ops::list_remote_dir(&target, &path))
         .await
         .map_err(|e| format!("Task failed: {}", e))?
 }
 
+/// Details of one remote path; a symlink is described, not followed
 #[tauri::command]
+pub async fn sftp_stat(
+    host: String,
+    user: String,
+    port: Option<u16>,
+    path: String,
+    device_id: Option<String>,
+    config: State<'_, ConfigState>,
+) -> Result<RemoteFile, String> {
+    let target = config.resolve_target(&host, &user, port, device_id.as_deref())?;
+    tokio::task::spawn_blocking(move || ops::stat_remote(&target, &path))
+        .await
+        .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Upload through the transfer queue and wait for it to finish
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn sftp_upload(
     host: String,
     user: String,
     port: Option<u16>,
     local_path: String,
     remote_path: String,
+    device_id: Option<String>,
+    app: AppHandle,
+    config: State<'_, ConfigState>,
+    transfers: State<'_, TransferManager>,
+) -> Result<(), String> {
+    let target = config.resolve_target(&host, &user, port, device_id.as_deref())?;
+    transfers
+        .run(Direction::Upload, target, local_path, remote_path, &app)
+        .await
+}
+
+/// Download through the transfer queue and wait for it to finish
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn sftp_download(
+    host: String,
+    user: String,
+    port: Option<u16>,
+    remote_path: String,
+    local_path: String,
+    device_id: Option<String>,
     app: AppHandle,
+    config: State<'_, ConfigState>,
+    transfers: State<'_, TransferManager>,
 ) -> Result<(), String> {
+    let target = config.resolve_target(&host, &user, port, device_id.as_deref())?;
+    transfers
+        .run(Direction::Download, target, local_path, remote_path, &app)
+        .await
+}
+
+/// Upload a directory tree; with `dry_run` only report what would be copied
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn sftp_upload_dir(
+    host: String,
+    user: String,
+    port: Option<u16>,
+    local_path: String,
+    remote_path: String,
+    options: Option<TreeOptions>,
+    device_id: Option<String>,
+    app: AppHandle,
+    config: State<'_, ConfigState>,
+) -> Result<TreePlan, String> {
+    let target = config.resolve_target(&host, &user, port, device_id.as_deref())?;
+    let options = options.unwrap_or_default();
     tokio::task::spawn_blocking(move || {
-        ops::upload_file(&host, &user, port, &local_path, &remote_path, &app)
+        tree::upload_tree(&target, &local_path, &remote_path, &options, &app)
     })
     .await
     .map_err(|e| format!("Task failed: {}", e))?
 }
 
+/// Download a directory tree; with `dry_run` only report what would be copied
 #[tauri::command]
-pub async fn sftp_download(
+#[allow(clippy::too_many_arguments)]
+pub async fn sftp_download_dir(
     host: String,
     user: String,
     port: Option<u16>,
     remote_path: String,
     local_path: String,
+    options: Option<TreeOptions>,
+    device_id: Option<String>,
     app: AppHandle,
-) -> Result<(), String> {
+    config: State<'_, ConfigState>,
+) -> Result<TreePlan, String> {
+    let target = config.resolve_target(&host, &user, port, device_id.as_deref())?;
+    let options = options.unwrap_or_default();
+    tokio::task::spawn_blocking(move || {
+        tree::download_tree(&target, &remote_path, &local_path, &options, &app)
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Bring the destination tree in line with the source, copying only what
+/// differs. Call with `dry_run` first to get the plan to show.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn sftp_sync(
+    host: String,
+    user: String,
+    port: Option<u16>,
+    local_path: String,
+    remote_path: String,
+    options: SyncOptions,
+    device_id: Option<String>,
+    app: AppHandle,
+    config: State<'_, ConfigState>,
+) -> Result<SyncPlan, String> {
+    let target = config.resolve_target(&host, &user, port, device_id.as_deref())?;
     tokio::task::spawn_blocking(move || {
-        ops::download_file(&host, &user, port, &remote_path, &local_path, &app)
+        sync::sync(&target, &local_path, &remote_path, &options, &app)
     })
     .await
     .map_err(|e| format!("Task failed: {}", e))?
@@ -52,8 +152,11 @@ pub async fn sftp_mkdir(
     user: String,
     port: Option<u16>,
     path: String,
+    device_id: Option<String>,
+    config: State<'_, ConfigState>,
 ) -> Result<(), String> {
-    tokio::task::spawn_blocking(move || ops::make_remote_dir(&host, &user, port, &path))
+    let target = config.resolve_target(&host, &user, port, device_id.as_deref())?;
+    tokio::task::spawn_blocking(move || ops::make_remote_dir(&target, &path))
         .await
         .map_err(|e| format!("Task failed: {}", e))?
 }
@@ -64,8 +167,120 @@ pub async fn sftp_delete(
     user: String,
     port: Option<u16>,
     path: String,
+    device_id: Option<String>,
+    config: State<'_, ConfigState>,
+) -> Result<(), String> {
+    let target = config.resolve_target(&host, &user, port, device_id.as_deref())?;
+    tokio::task::spawn_blocking(move || ops::delete_remote(&target, &path))
+        .await
+        .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Queue a transfer without waiting for it; progress arrives as
+/// `file-transfer-progress` events carrying the job id
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn transfer_enqueue(
+    host: String,
+    user: String,
+    port: Option<u16>,
+    direction: Direction,
+    local_path: String,
+    remote_path: String,
+    device_id: Option<String>,
+    app: AppHandle,
+    config: State<'_, ConfigState>,
+    transfers: State<'_, TransferManager>,
+) -> Result<TransferJob, String> {
+    let target = config.resolve_target(&host, &user, port, device_id.as_deref())?;
+    Ok(transfers.enqueue(direction, target, local_path, remote_path, &app))
+}
+
+#[tauri::command]
+pub async fn transfer_list(transfers: State<'_, TransferManager>) -> Result<Vec<TransferJob>, String> {
+    Ok(transfers.list())
+}
+
+#[tauri::command]
+pub async fn transfer_pause(
+    id: String,
+    app: AppHandle,
+    transfers: State<'_, TransferManager>,
+) -> Result<(), String> {
+    transfers.pause(&id, &app)
+}
+
+#[tauri::command]
+pub async fn transfer_resume(
+    id: String,
+    app: AppHandle,
+    transfers: State<'_, TransferManager>,
+) -> Result<(), String> {
+    transfers.resume(&id, &app)
+}
+
+#[tauri::command]
+pub async fn transfer_cancel(
+    id: String,
+    app: AppHandle,
+    transfers: State<'_, TransferManager>,
+) -> Result<(), String> {
+    transfers.cancel(&id, &app)
+}
+
+#[tauri::command]
+pub async fn transfer_remove(id: String, transfers: State<'_, TransferManager>) -> Result<(), String> {
+    transfers.remove(&id)
+}
+
+#[tauri::command]
+pub async fn transfer_clear_finished(transfers: State<'_, TransferManager>) -> Result<(), String> {
+    transfers.clear_finished();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn transfer_get_parallel(transfers: State<'_, TransferManager>) -> Result<usize, String> {
+    Ok(transfers.max_parallel())
+}
+
+#[tauri::command]
+pub async fn transfer_set_parallel(
+    max_parallel: usize,
+    app: AppHandle,
+    transfers: State<'_, TransferManager>,
+) -> Result<(), String> {
+    transfers.set_max_parallel(max_parallel, &app)
+}
+
+/// Rename, copy, chmod, chown, symlink, touch, mkdir or delete
+#[tauri::command]
+pub async fn sftp_op(
+    host: String,
+    user: String,
+    port: Option<u16>,
+    op: RemoteOp,
+    device_id: Option<String>,
+    config: State<'_, ConfigState>,
 ) -> Result<(), String> {
-    tokio::task::spawn_blocking(move || ops::delete_remote(&host, &user, port, &path))
+    let target = config.resolve_target(&host, &user, port, device_id.as_deref())?;
+    tokio::task::spawn_blocking(move || ops::remote_op(&target, &op))
+        .await
+        .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Several operations over one connection, with a result for each
+#[tauri::command]
+pub async fn sftp_batch(
+    host: String,
+    user: String,
+    port: Option<u16>,
+    ops: Vec<RemoteOp>,
+    device_id: Option<String>,
+    config: State<'_, ConfigState>,
+) -> Result<Vec<OpResult>, String> {
+    let target = config.resolve_target(&host, &user, port, device_id.as_deref())?;
+    tokio::task::spawn_blocking(move || ops::remote_ops(&target, &ops))
         .await
         .map_err(|e| format!("Task failed: {}", e))?
 }
@@ -0,0 +1,81 @@
+use crate::config::{validate, ConfigState, DeviceGroup, GroupDefaults};
+use tauri::State;
+
+#[tauri::command]
+pub async fn list_groups(config: State<'_, ConfigState>) -> Result<Vec<DeviceGroup>, String> {
+    Ok(config.0.lock().unwrap().groups.clone())
+}
+
+#[tauri::command]
+pub async fn add_group(
+    name: String,
+    parent: Option<String>,
+    defaults: Option<GroupDefaults>,
+    config: State<'_, ConfigState>,
+) -> Result<DeviceGroup, String> {
+    let mut cfg = config.0.lock().unwrap();
+    let group = DeviceGroup {
+        id: DeviceGroup::new_id(),
+        name: name.trim().to_string(),
+        parent,
+        defaults: defaults.unwrap_or_default(),
+    };
+    validate::validate_group(&group, &cfg)?;
+
+    cfg.groups.push(group.clone());
+    config.persist(&cfg).map_err(|e| format!("Failed to save config: {}", e))?;
+    Ok(group)
+}
+
+/// Rename, move or change the defaults of a group
+#[tauri::command]
+pub async fn update_group(
+    group: DeviceGroup,
+    config: State<'_, ConfigState>,
+) -> Result<DeviceGroup, String> {
+    let mut cfg = config.0.lock().unwrap();
+    if cfg.find_group(&group.id).is_none() {
+        return Err(format!("Group '{}' not found", group.id));
+    }
+    let group = DeviceGroup {
+        name: group.name.trim().to_string(),
+        ..group
+    };
+    validate::validate_group(&group, &cfg)?;
+
+    if let Some(slot) = cfg.groups.iter_mut().find(|g| g.id == group.id) {
+        *slot = group.clone();
+    }
+    config.persist(&cfg).map_err(|e| format!("Failed to save config: {}", e))?;
+    Ok(group)
+}
+
+/// Delete a group. Its devices, subgroups and snippets move up to its parent.
+#[tauri::command]
+pub async fn remove_group(id: String, config: State<'_, ConfigState>) -> Result<(), String> {
+    let mut cfg = config.0.lock().unwrap();
+    let parent = cfg
+        .find_group(&id)
+        .ok_or_else(|| format!("Group '{}' not found", id))?
+        .parent
+        .clone();
+
+    let mut updated = cfg.clone();
+    updated.groups.retain(|g| g.id != id);
+    for group in updated.groups.iter_mut().filter(|g| g.parent.as_deref() == Some(&id)) {
+        group.parent = parent.clone();
+    }
+    for device in updated.devices.iter_mut().filter(|d| d.group.as_deref() == Some(&id)) {
+        device.group = parent.clone();
+    }
+    for snippet in updated.snippets.iter_mut().filter(|s| s.group.as_deref() == Some(&id)) {
+        snippet.group = parent.clone();
+    }
+    // Devices now inherit different defaults; make sure no chain broke
+    for device in &updated.devices {
+        updated.jump_chain_ids(&device.id)?;
+    }
+    *cfg = updated;
+    config.persist(&cfg).map_err(|e| format!("Failed to save config: {}", e))?;
+    Ok(())
+}
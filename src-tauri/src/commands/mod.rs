@@ -1,7 +1,12 @@
 pub mod vpn;
 pub mod ssh;
 pub mod devices;
+pub mod groups;
+pub mod snippets;
 pub mod desktop;
 pub mod files;
 pub mod sshkeys;
 pub mod crypto;
+pub mod config;
+pub mod wol;
+pub mod audit;
@@ -0,0 +1,83 @@
+use crate::config::{ConfigState, Snippet};
+use crate::terminal::TerminalManager;
+use std::collections::HashMap;
+use tauri::State;
+
+/// All snippets, or those available to a device's sessions
+#[tauri::command]
+pub async fn snippets_list(
+    device_id: Option<String>,
+    config: State<'_, ConfigState>,
+) -> Result<Vec<Snippet>, String> {
+    let cfg = config.0.lock().unwrap();
+    Ok(match device_id {
+        Some(id) => cfg.snippets_for_device(Some(&id)),
+        None => cfg.snippets.clone(),
+    })
+}
+
+/// Add a snippet, or replace the one with the same id. An empty id gets a
+/// new one.
+#[tauri::command]
+pub async fn snippet_save(
+    snippet: Snippet,
+    config: State<'_, ConfigState>,
+) -> Result<Snippet, String> {
+    let mut cfg = config.0.lock().unwrap();
+    let snippet = Snippet {
+        id: if snippet.id.is_empty() { Snippet::new_id() } else { snippet.id },
+        name: snippet.name.trim().to_string(),
+        ..snippet
+    };
+    snippet.validate(&cfg)?;
+
+    match cfg.snippets.iter_mut().find(|s| s.id == snippet.id) {
+        Some(slot) => *slot = snippet.clone(),
+        None => cfg.snippets.push(snippet.clone()),
+    }
+    config.persist(&cfg).map_err(|e| format!("Failed to save config: {}", e))?;
+    Ok(snippet)
+}
+
+#[tauri::command]
+pub async fn snippet_delete(id: String, config: State<'_, ConfigState>) -> Result<(), String> {
+    let mut cfg = config.0.lock().unwrap();
+    let before = cfg.snippets.len();
+    cfg.snippets.retain(|s| s.id != id);
+    if cfg.snippets.len() == before {
+        return Err(format!("Snippet '{}' not found", id));
+    }
+    config.persist(&cfg).map_err(|e| format!("Failed to save config: {}", e))?;
+    Ok(())
+}
+
+/// Type a snippet into a session, as keyboard input, so it also reaches the
+/// session's broadcast group. `host` and `user` are filled in from the
+/// session unless given in `params`.
+#[tauri::command]
+pub async fn snippet_send(
+    session_id: String,
+    snippet_id: String,
+    params: Option<HashMap<String, String>>,
+    manager: State<'_, TerminalManager>,
+    config: State<'_, ConfigState>,
+) -> Result<(), String> {
+    let snippet = config
+        .0
+        .lock()
+        .unwrap()
+        .find_snippet(&snippet_id)
+        .cloned()
+        .ok_or_else(|| format!("Snippet '{}' not found", snippet_id))?;
+    let session = manager
+        .list_sessions()
+        .into_iter()
+        .find(|s| s.session_id == session_id)
+        .ok_or("Session not found")?;
+
+    let mut values = params.unwrap_or_default();
+    values.entry("host".to_string()).or_insert(session.host);
+    values.entry("user".to_string()).or_insert(session.user);
+    let input = snippet.render(&values)?;
+    manager.write_input(&session_id, input.as_bytes())
+}
@@ -1,25 +1,136 @@
-use crate::terminal::TerminalManager;
+use crate::config::{ConfigState, OutputTrigger, SshBackend};
+use crate::terminal::backend::LocalShell;
+use crate::terminal::broadcast::BroadcastGroup;
+use crate::terminal::multiplexer::{self, Attach, RemoteSession};
+use crate::terminal::pty::{Replay, SessionInfo, SessionOptions};
+use crate::terminal::serial::{self, SerialConfig, SerialPortInfo};
+use crate::terminal::share::{ShareInfo, ShareInvite, ShareManager};
+use crate::terminal::startup::Startup;
+use crate::terminal::{recording, TerminalManager};
+use crate::vpn::VpnManager;
+use tauri::ipc::{Channel, InvokeResponseBody};
 use tauri::{AppHandle, State};
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn ssh_open(
     session_id: String,
     host: String,
     user: String,
     port: Option<u16>,
+    device_id: Option<String>,
+    record: Option<bool>,
+    record_input: Option<bool>,
+    backend: Option<SshBackend>,
+    attach: Option<Attach>,
     app: AppHandle,
     manager: State<'_, TerminalManager>,
+    config: State<'_, ConfigState>,
 ) -> Result<(), String> {
-    manager.open_session(&session_id, &host, &user, port, app)
+    let target = config.resolve_target(&host, &user, port, device_id.as_deref())?;
+    let options = {
+        let cfg = config.0.lock().unwrap();
+        let device = device_id.as_deref().and_then(|id| cfg.effective_device_by_id(id));
+        SessionOptions {
+            // Without an explicit choice, follow the device's setting
+            record: record.unwrap_or_else(|| device.as_ref().is_some_and(|d| d.record_sessions)),
+            record_input: record_input.unwrap_or(false),
+            backend: backend.unwrap_or(cfg.ssh_backend).resolve(),
+            attach,
+            triggers: device.as_ref().map(|d| d.triggers.clone()).unwrap_or_default(),
+            startup: device.as_ref().map(Startup::from_device).unwrap_or_default(),
+        }
+    };
+    manager.open(&session_id, &target, options, app).await
 }
 
+/// Open a shell on this machine. Input, resize and close go through the
+/// same `ssh_*` commands as SSH sessions.
+#[tauri::command]
+pub async fn local_open(
+    session_id: String,
+    shell: Option<LocalShell>,
+    record: Option<bool>,
+    app: AppHandle,
+    manager: State<'_, TerminalManager>,
+) -> Result<(), String> {
+    manager.open_local_session(
+        &session_id,
+        shell.unwrap_or_default(),
+        record.unwrap_or(false),
+        app,
+    )
+}
+
+/// Open a serial console
+#[tauri::command]
+pub async fn serial_open(
+    session_id: String,
+    serial: SerialConfig,
+    record: Option<bool>,
+    app: AppHandle,
+    manager: State<'_, TerminalManager>,
+) -> Result<(), String> {
+    manager.open_serial_session(&session_id, serial, record.unwrap_or(false), app)
+}
+
+#[tauri::command]
+pub async fn serial_ports_list() -> Result<Vec<SerialPortInfo>, String> {
+    tokio::task::spawn_blocking(serial::list_ports)
+        .await
+        .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// tmux and screen sessions running on a device
+#[tauri::command]
+pub async fn remote_sessions_list(
+    device_id: String,
+    config: State<'_, ConfigState>,
+) -> Result<Vec<RemoteSession>, String> {
+    let target = config.0.lock().unwrap().device_target(&device_id)?;
+    let output = tokio::task::spawn_blocking(move || {
+        crate::filetransfer::ops::ssh_exec(&target, multiplexer::LIST_SCRIPT)
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))??;
+    Ok(multiplexer::parse_sessions(&output))
+}
+
+/// Open a terminal attached to an existing (or new) tmux/screen session
+#[tauri::command]
+pub async fn ssh_attach(
+    session_id: String,
+    device_id: String,
+    attach: Attach,
+    app: AppHandle,
+    manager: State<'_, TerminalManager>,
+    config: State<'_, ConfigState>,
+) -> Result<(), String> {
+    let (target, options) = {
+        let cfg = config.0.lock().unwrap();
+        let target = cfg.device_target(&device_id)?;
+        let device = cfg.effective_device_by_id(&device_id);
+        let options = SessionOptions {
+            record: device.as_ref().is_some_and(|d| d.record_sessions),
+            record_input: false,
+            backend: cfg.ssh_backend.resolve(),
+            attach: Some(attach),
+            triggers: device.as_ref().map(|d| d.triggers.clone()).unwrap_or_default(),
+            startup: device.as_ref().map(Startup::from_device).unwrap_or_default(),
+        };
+        (target, options)
+    };
+    manager.open(&session_id, &target, options, app).await
+}
+
+/// Keyboard input; fans out to the session's broadcast group, if any
 #[tauri::command]
 pub async fn ssh_write(
     session_id: String,
     data: Vec<u8>,
     manager: State<'_, TerminalManager>,
 ) -> Result<(), String> {
-    manager.write_session(&session_id, &data)
+    manager.write_input(&session_id, &data)
 }
 
 #[tauri::command]
@@ -32,10 +143,230 @@ pub async fn ssh_resize(
     manager.resize_session(&session_id, cols, rows)
 }
 
+/// Stream the session's output to `output` as raw binary batches, replacing
+/// any earlier subscriber (e.g. from before a reload). Returns the output
+/// buffered so far; the stream continues from its `end`.
+#[tauri::command]
+pub async fn ssh_subscribe(
+    session_id: String,
+    output: Channel<InvokeResponseBody>,
+    manager: State<'_, TerminalManager>,
+) -> Result<Replay, String> {
+    manager.subscribe(
+        &session_id,
+        Box::new(move |batch| output.send(InvokeResponseBody::Raw(batch.to_vec())).is_ok()),
+    )
+}
+
+/// The frontend has rendered output up to stream offset `offset`. Delivery
+/// pauses when it falls too far behind.
+#[tauri::command]
+pub async fn ssh_ack(
+    session_id: String,
+    offset: u64,
+    manager: State<'_, TerminalManager>,
+) -> Result<(), String> {
+    manager.ack_output(&session_id, offset)
+}
+
+/// Output buffered since stream offset `from`, for a frontend that is
+/// re-subscribing to a session (e.g. after a reload)
+#[tauri::command]
+pub async fn ssh_replay(
+    session_id: String,
+    from: Option<u64>,
+    manager: State<'_, TerminalManager>,
+) -> Result<Replay, String> {
+    manager.replay(&session_id, from)
+}
+
+#[tauri::command]
+pub async fn ssh_sessions(manager: State<'_, TerminalManager>) -> Result<Vec<SessionInfo>, String> {
+    Ok(manager.list_sessions())
+}
+
 #[tauri::command]
 pub async fn ssh_close(
     session_id: String,
+    app: AppHandle,
     manager: State<'_, TerminalManager>,
+    shares: State<'_, ShareManager>,
 ) -> Result<(), String> {
+    shares.stop(&session_id, &app);
     manager.close_session(&session_id)
 }
+
+/// A one-time link that lets someone watch the session over WebSocket.
+/// Served on this machine only, or with `on_vpn` on its VPN address so
+/// colleagues on the lab network can connect.
+#[tauri::command]
+pub async fn share_invite(
+    session_id: String,
+    on_vpn: Option<bool>,
+    app: AppHandle,
+    shares: State<'_, ShareManager>,
+    vpn: State<'_, VpnManager>,
+) -> Result<ShareInvite, String> {
+    let address = if on_vpn.unwrap_or(false) {
+        vpn.check_status()?
+            .local_ip
+            .ok_or("Connect the VPN to share with colleagues")?
+    } else {
+        "127.0.0.1".to_string()
+    };
+    shares.invite(&session_id, &address, &app).await
+}
+
+#[tauri::command]
+pub async fn share_list(shares: State<'_, ShareManager>) -> Result<Vec<ShareInfo>, String> {
+    Ok(shares.list())
+}
+
+/// Let an observer type into the session, or make them view-only again
+#[tauri::command]
+pub async fn share_set_control(
+    session_id: String,
+    observer_id: String,
+    control: bool,
+    app: AppHandle,
+    shares: State<'_, ShareManager>,
+) -> Result<(), String> {
+    shares.set_control(&session_id, &observer_id, control, &app)
+}
+
+#[tauri::command]
+pub async fn share_disconnect(
+    session_id: String,
+    observer_id: String,
+    shares: State<'_, ShareManager>,
+) -> Result<(), String> {
+    shares.disconnect(&session_id, &observer_id)
+}
+
+/// Stop sharing; disconnects all observers and invalidates unused links
+#[tauri::command]
+pub async fn share_stop(
+    session_id: String,
+    app: AppHandle,
+    shares: State<'_, ShareManager>,
+) -> Result<(), String> {
+    shares.stop(&session_id, &app);
+    Ok(())
+}
+
+/// Start recording a running session; returns the .cast file path.
+/// Keyboard input is only kept with `record_input`.
+#[tauri::command]
+pub async fn ssh_record_start(
+    session_id: String,
+    record_input: Option<bool>,
+    manager: State<'_, TerminalManager>,
+) -> Result<String, String> {
+    manager.start_recording(&session_id, record_input.unwrap_or(false))
+}
+
+#[tauri::command]
+pub async fn ssh_record_stop(
+    session_id: String,
+    manager: State<'_, TerminalManager>,
+) -> Result<Option<String>, String> {
+    manager.stop_recording(&session_id)
+}
+
+#[tauri::command]
+pub async fn ssh_recordings_list() -> Result<Vec<recording::RecordingInfo>, String> {
+    recording::list_recordings(&recording::recordings_dir())
+}
+
+#[tauri::command]
+pub async fn ssh_backend_get(config: State<'_, ConfigState>) -> Result<SshBackend, String> {
+    Ok(config.0.lock().unwrap().ssh_backend)
+}
+
+#[tauri::command]
+pub async fn ssh_backend_set(
+    backend: SshBackend,
+    config: State<'_, ConfigState>,
+) -> Result<(), String> {
+    if backend == SshBackend::Native && !cfg!(feature = "russh") {
+        return Err("This build has no native SSH backend".to_string());
+    }
+    let mut cfg = config.0.lock().unwrap();
+    cfg.ssh_backend = backend;
+    config.persist(&cfg)
+}
+
+#[tauri::command]
+pub async fn broadcast_list(
+    manager: State<'_, TerminalManager>,
+) -> Result<Vec<BroadcastGroup>, String> {
+    Ok(manager.broadcast_groups())
+}
+
+#[tauri::command]
+pub async fn broadcast_create(
+    name: String,
+    session_ids: Vec<String>,
+    app: AppHandle,
+    manager: State<'_, TerminalManager>,
+) -> Result<BroadcastGroup, String> {
+    manager.broadcast_create(&name, &session_ids, &app)
+}
+
+#[tauri::command]
+pub async fn broadcast_join(
+    group_id: String,
+    session_id: String,
+    app: AppHandle,
+    manager: State<'_, TerminalManager>,
+) -> Result<(), String> {
+    manager.broadcast_join(&group_id, &session_id, &app)
+}
+
+#[tauri::command]
+pub async fn broadcast_leave(
+    session_id: String,
+    app: AppHandle,
+    manager: State<'_, TerminalManager>,
+) -> Result<(), String> {
+    manager.broadcast_leave(&session_id, &app);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn broadcast_exclude(
+    session_id: String,
+    excluded: bool,
+    app: AppHandle,
+    manager: State<'_, TerminalManager>,
+) -> Result<(), String> {
+    manager.broadcast_exclude(&session_id, excluded, &app)
+}
+
+#[tauri::command]
+pub async fn broadcast_dissolve(
+    group_id: String,
+    app: AppHandle,
+    manager: State<'_, TerminalManager>,
+) -> Result<(), String> {
+    manager.broadcast_dissolve(&group_id, &app)
+}
+
+#[tauri::command]
+pub async fn terminal_triggers_get(
+    session_id: String,
+    manager: State<'_, TerminalManager>,
+) -> Result<Vec<OutputTrigger>, String> {
+    manager.triggers(&session_id)
+}
+
+/// Replace a running session's output triggers. Device triggers are edited
+/// with the device and apply to sessions opened afterwards.
+#[tauri::command]
+pub async fn terminal_triggers_set(
+    session_id: String,
+    triggers: Vec<OutputTrigger>,
+    manager: State<'_, TerminalManager>,
+) -> Result<(), String> {
+    manager.set_triggers(&session_id, &triggers)
+}
@@ -1,4 +1,6 @@
+use crate::config::ConfigState;
 use crate::sshkeys::ops;
+use tauri::State;
 
 #[tauri::command]
 pub async fn ssh_keys_list() -> Result<Vec<ops::SshKeyInfo>, String> {
@@ -23,8 +25,11 @@ pub async fn ssh_key_copy_to_remote(
     host: String,
     user: String,
     port: Option<u16>,
+    device_id: Option<String>,
+    config: State<'_, ConfigState>,
 ) -> Result<(), String> {
-    tokio::task::spawn_blocking(move || ops::copy_key_to_remote(&key_path, &host, &user, port))
+    let target = config.resolve_target(&host, &user, port, device_id.as_deref())?;
+    tokio::task::spawn_blocking(move || ops::copy_key_to_remote(&key_path, &target))
         .await
         .map_err(|e| format!("Task failed: {}", e))?
 }
@@ -0,0 +1,108 @@
+use std::net::Ipv4Addr;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, State};
+
+use crate::config::ConfigState;
+use crate::filetransfer::ops;
+use crate::health::{DeviceHealth, HealthMonitor};
+use crate::ssh_target::{JumpHost, SshTarget};
+use crate::wol;
+
+/// How long to wait for a woken machine by default (POST + OS boot)
+const DEFAULT_WAIT_SECS: u64 = 180;
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Send a Wake-on-LAN packet to a device, directly or from `via` (default:
+/// the device's configured relay) over SSH, then wait up to `wait_secs` for
+/// it to come online. `wait_secs` of 0 returns right after sending.
+#[tauri::command]
+pub async fn wake_device(
+    device_id: String,
+    via: Option<String>,
+    wait_secs: Option<u64>,
+    app: AppHandle,
+    config: State<'_, ConfigState>,
+    health: State<'_, HealthMonitor>,
+) -> Result<Option<DeviceHealth>, String> {
+    let (device, route, wol, relay_target) = {
+        let cfg = config.0.lock().unwrap();
+        let device = cfg
+            .effective_device_by_id(&device_id)
+            .ok_or_else(|| format!("Device '{}' not found", device_id))?;
+        let wol = device
+            .wol
+            .clone()
+            .ok_or_else(|| format!("No Wake-on-LAN settings for '{}'", device.name))?;
+        let relay = via.or_else(|| wol.relay.clone());
+        let relay_target = match relay {
+            Some(relay_id) => {
+                if relay_id == device_id {
+                    return Err("A device cannot wake itself".to_string());
+                }
+                if health.get(&relay_id).is_some_and(|h| !h.online) {
+                    return Err(format!("Relay device '{}' is offline", relay_id));
+                }
+                Some(cfg.device_target(&relay_id)?)
+            }
+            None => None,
+        };
+        // A machine woken through a relay is usually only reachable through it
+        let route = cfg.device_target(&device_id).ok().map(|route| match &relay_target {
+            Some(relay) if route.jumps.is_empty() => through(route, relay),
+            _ => route,
+        });
+        (device, route, wol, relay_target)
+    };
+
+    let mac = wol::parse_mac(&wol.mac_address)?;
+    let broadcast = wol
+        .broadcast
+        .as_deref()
+        .map(|b| b.parse::<Ipv4Addr>().map_err(|_| format!("Invalid broadcast address '{}'", b)))
+        .transpose()?;
+
+    match relay_target {
+        Some(target) => {
+            log::info!("Waking {} via {}", device.name, target.destination());
+            let script = wol::relay_script(&mac, broadcast, wol.interface.as_deref());
+            tokio::task::spawn_blocking(move || ops::ssh_exec(&target, &script))
+                .await
+                .map_err(|e| format!("Task failed: {}", e))??;
+        }
+        None => {
+            log::info!("Waking {} from this machine", device.name);
+            wol::send_local(&mac, broadcast, wol.interface.as_deref())?;
+        }
+    }
+
+    let wait = Duration::from_secs(wait_secs.unwrap_or(DEFAULT_WAIT_SECS));
+    if wait.is_zero() {
+        return Ok(None);
+    }
+    let deadline = Instant::now() + wait;
+    loop {
+        let status = health.check_device(&app, &device, route.as_ref()).await;
+        if status.online {
+            return Ok(Some(status));
+        }
+        if Instant::now() + POLL_INTERVAL > deadline {
+            return Err(format!(
+                "Magic packet sent, but {} did not come online within {}s",
+                device.name,
+                wait.as_secs()
+            ));
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// `target` reached by hopping through `relay` (and the relay's own jump hosts)
+fn through(mut target: SshTarget, relay: &SshTarget) -> SshTarget {
+    target.jumps = relay.jumps.clone();
+    target.jumps.push(JumpHost {
+        host: relay.host.clone(),
+        user: relay.user.clone(),
+        port: relay.port,
+    });
+    target
+}
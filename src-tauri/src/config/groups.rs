@@ -0,0 +1,279 @@
+use serde::{Deserialize, Serialize};
+
+use super::{AppConfig, Device};
+
+/// A folder of devices. Groups nest through `parent`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceGroup {
+    pub id: String,
+    pub name: String,
+    /// Enclosing group, None for a top-level group
+    #[serde(default)]
+    pub parent: Option<String>,
+    #[serde(default)]
+    pub defaults: GroupDefaults,
+}
+
+/// Connection settings member devices inherit when they leave the
+/// corresponding field unset. The nearest group that sets a field wins.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GroupDefaults {
+    #[serde(default)]
+    pub ssh_user: Option<String>,
+    #[serde(default)]
+    pub ssh_port: Option<u16>,
+    #[serde(default)]
+    pub jump_hosts: Option<Vec<String>>,
+    #[serde(default)]
+    pub identity_file: Option<String>,
+}
+
+impl DeviceGroup {
+    pub fn new_id() -> String {
+        uuid::Uuid::new_v4().to_string()
+    }
+}
+
+impl AppConfig {
+    pub fn find_group(&self, id: &str) -> Option<&DeviceGroup> {
+        self.groups.iter().find(|g| g.id == id)
+    }
+
+    /// The group and its ancestors, innermost first. Stops at a missing
+    /// parent or a cycle rather than failing.
+    pub fn group_lineage(&self, group_id: &str) -> Vec<&DeviceGroup> {
+        let mut lineage: Vec<&DeviceGroup> = Vec::new();
+        let mut next = self.find_group(group_id);
+        while let Some(group) = next {
+            if lineage.iter().any(|g| g.id == group.id) {
+                break;
+            }
+            lineage.push(group);
+            next = group.parent.as_deref().and_then(|p| self.find_group(p));
+        }
+        lineage
+    }
+
+    /// Whether `group_id` is `ancestor_id` or nested somewhere below it
+    pub fn group_is_within(&self, group_id: &str, ancestor_id: &str) -> bool {
+        self.group_lineage(group_id).iter().any(|g| g.id == ancestor_id)
+    }
+
+    /// A device with unset fields filled from its groups, and the names of
+    /// the fields that were inherited. `ssh_user` falls back to
+    /// `default_ssh_user` when neither the device nor a group sets it.
+    pub fn effective_device(&self, device: &Device) -> (Device, Vec<String>) {
+        let mut effective = device.clone();
+        let mut inherited = Vec::new();
+        let lineage = device
+            .group
+            .as_deref()
+            .map(|g| self.group_lineage(g))
+            .unwrap_or_default();
+        let defaults = || lineage.iter().map(|g| &g.defaults);
+
+        if effective.ssh_user.is_empty() {
+            effective.ssh_user = defaults()
+                .find_map(|d| d.ssh_user.clone())
+                .unwrap_or_else(|| self.default_ssh_user.clone());
+            inherited.push("ssh_user".to_string());
+        }
+        if effective.ssh_port.is_none() {
+            if let Some(port) = defaults().find_map(|d| d.ssh_port) {
+                effective.ssh_port = Some(port);
+                inherited.push("ssh_port".to_string());
+            }
+        }
+        if effective.jump_hosts.is_none() {
+            if let Some(jumps) = defaults().find_map(|d| d.jump_hosts.clone()) {
+                // A bastion inside the group must not jump through itself
+                effective.jump_hosts = Some(jumps.into_iter().filter(|j| *j != device.id).collect());
+                inherited.push("jump_hosts".to_string());
+            }
+        }
+        if effective.identity_file.is_none() {
+            if let Some(key) = defaults().find_map(|d| d.identity_file.clone()) {
+                effective.identity_file = Some(key);
+                inherited.push("identity_file".to_string());
+            }
+        }
+        (effective, inherited)
+    }
+
+    /// Effective settings for the device with `id`
+    pub fn effective_device_by_id(&self, id: &str) -> Option<Device> {
+        self.find_device(id).map(|d| self.effective_device(d).0)
+    }
+
+    /// Undo `effective_device` on an edited copy of a stored device: fields
+    /// the stored device inherited and the edit left at the inherited value
+    /// are unset again, so group defaults keep applying to them
+    pub fn strip_inherited(&self, mut edited: Device) -> Device {
+        let Some(stored) = self.find_device(&edited.id) else {
+            return edited;
+        };
+        let (effective, inherited) = self.effective_device(stored);
+        for field in &inherited {
+            match field.as_str() {
+                "ssh_user" if edited.ssh_user.trim() == effective.ssh_user => {
+                    edited.ssh_user = stored.ssh_user.clone();
+                }
+                "ssh_port" if edited.ssh_port == effective.ssh_port => {
+                    edited.ssh_port = stored.ssh_port;
+                }
+                "jump_hosts" if edited.jump_hosts == effective.jump_hosts => {
+                    edited.jump_hosts = stored.jump_hosts.clone();
+                }
+                "identity_file" if edited.identity_file == effective.identity_file => {
+                    edited.identity_file = stored.identity_file.clone();
+                }
+                _ => {}
+            }
+        }
+        edited
+    }
+}
+
+/// Lowercase, trim and dedupe tags, dropping empty ones
+pub fn normalize_tags(tags: Vec<String>) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim().to_lowercase();
+        if !tag.is_empty() && !out.contains(&tag) {
+            out.push(tag);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_device;
+
+    fn group(id: &str, parent: Option<&str>, defaults: GroupDefaults) -> DeviceGroup {
+        DeviceGroup {
+            id: id.to_string(),
+            name: id.to_string(),
+            parent: parent.map(str::to_string),
+            defaults,
+        }
+    }
+
+    /// User left empty, to be inherited
+    fn device(id: &str, group: Option<&str>) -> Device {
+        Device {
+            ssh_user: String::new(),
+            group: group.map(str::to_string),
+            ..test_device(id, id)
+        }
+    }
+
+    #[test]
+    fn test_nearest_group_default_wins() {
+        let config = AppConfig {
+            groups: vec![
+                group(
+                    "lab",
+                    None,
+                    GroupDefaults {
+                        ssh_user: Some("lab".into()),
+                        ssh_port: Some(2222),
+                        jump_hosts: Some(vec!["bastion".into()]),
+                        ..Default::default()
+                    },
+                ),
+                group(
+                    "gpu",
+                    Some("lab"),
+                    GroupDefaults { ssh_user: Some("cuda".into()), ..Default::default() },
+                ),
+            ],
+            devices: vec![device("bastion", Some("lab")), device("gpu1", Some("gpu"))],
+            ..Default::default()
+        };
+
+        let (gpu1, inherited) = config.effective_device(&config.devices[1]);
+        assert_eq!(gpu1.ssh_user, "cuda");
+        assert_eq!(gpu1.ssh_port, Some(2222));
+        assert_eq!(gpu1.jumps(), ["bastion"]);
+        assert_eq!(inherited, vec!["ssh_user", "ssh_port", "jump_hosts"]);
+
+        // The group's bastion does not route through itself
+        let bastion = config.effective_device_by_id("bastion").unwrap();
+        assert!(bastion.jumps().is_empty());
+        assert!(config.group_is_within("gpu", "lab"));
+    }
+
+    #[test]
+    fn test_device_overrides_and_global_fallback() {
+        let mut own = device("a", Some("lab"));
+        own.ssh_user = "me".into();
+        own.ssh_port = Some(22);
+        let config = AppConfig {
+            groups: vec![group(
+                "lab",
+                Some("lab"),
+                GroupDefaults { ssh_port: Some(2222), ..Default::default() },
+            )],
+            devices: vec![own, device("b", None)],
+            ..Default::default()
+        };
+        let (a, inherited) = config.effective_device(&config.devices[0]);
+        assert_eq!((a.ssh_user.as_str(), a.ssh_port), ("me", Some(22)));
+        assert!(inherited.is_empty());
+
+        let b = config.effective_device_by_id("b").unwrap();
+        assert_eq!(b.ssh_user, "root");
+    }
+
+    #[test]
+    fn test_strip_inherited_keeps_edits() {
+        let config = AppConfig {
+            groups: vec![group(
+                "lab",
+                None,
+                GroupDefaults {
+                    ssh_user: Some("lab".into()),
+                    ssh_port: Some(2222),
+                    ..Default::default()
+                },
+            )],
+            devices: vec![device("a", Some("lab"))],
+            ..Default::default()
+        };
+        // The frontend edits the effective device and only changes the port
+        let mut edited = config.effective_device_by_id("a").unwrap();
+        edited.ssh_port = Some(22);
+        edited.name = "renamed".into();
+
+        let stored = config.strip_inherited(edited);
+        assert_eq!(stored.ssh_user, "");
+        assert_eq!(stored.ssh_port, Some(22));
+        assert_eq!(stored.name, "renamed");
+    }
+
+    #[test]
+    fn test_empty_jump_hosts_override_the_group() {
+        let mut config = AppConfig {
+            groups: vec![group(
+                "lab",
+                None,
+                GroupDefaults { jump_hosts: Some(vec!["bastion".into()]), ..Default::default() },
+            )],
+            devices: vec![device("bastion", None), device("a", Some("lab"))],
+            ..Default::default()
+        };
+        let mut edited = config.effective_device_by_id("a").unwrap();
+        assert_eq!(edited.jumps(), ["bastion"]);
+        assert_eq!(config.strip_inherited(edited.clone()).jump_hosts, None);
+
+        edited.jump_hosts = Some(Vec::new());
+        let stored = config.strip_inherited(edited);
+        assert_eq!(stored.jump_hosts, Some(Vec::new()));
+        config.devices[1] = stored;
+        let (a, inherited) = config.effective_device(&config.devices[1]);
+        assert!(a.jumps().is_empty());
+        assert!(!inherited.contains(&"jump_hosts".to_string()));
+    }
+}
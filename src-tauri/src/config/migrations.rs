@@ -0,0 +1,175 @@
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// Schema version written by this build
+pub const CURRENT_SCHEMA_VERSION: u32 = 5;
+
+type Migration = fn(&mut Value) -> Result<(), String>;
+
+/// `MIGRATIONS[n]` upgrades a config from schema `n` to `n + 1`
+const MIGRATIONS: &[Migration] = &[v0_to_v1, v1_to_v2, v2_to_v3, v3_to_v4, v4_to_v5];
+
+/// Version recorded in a raw config; files from before versioning are 0
+pub fn schema_version(value: &Value) -> u32 {
+    value
+        .get("schema_version")
+        .and_then(Value::as_u64)
+        .map(|v| v as u32)
+        .unwrap_or(0)
+}
+
+/// Upgrade a raw config to the current schema in place, one step at a time.
+/// Returns the version the config started at.
+pub fn migrate(value: &mut Value) -> Result<u32, String> {
+    let from = schema_version(value);
+    if from > CURRENT_SCHEMA_VERSION {
+        return Err(format!(
+            "Config uses schema v{} but this version of RemoteLab only understands up to v{}",
+            from, CURRENT_SCHEMA_VERSION
+        ));
+    }
+
+    for version in from..CURRENT_SCHEMA_VERSION {
+        MIGRATIONS[version as usize](value)
+            .map_err(|e| format!("Migration v{} -> v{} failed: {}", version, version + 1, e))?;
+        value["schema_version"] = json!(version + 1);
+        log::info!("Migrated config schema v{} -> v{}", version, version + 1);
+    }
+    Ok(from)
+}
+
+fn devices_mut(value: &mut Value) -> Result<&mut Vec<Value>, String> {
+    value
+        .as_object_mut()
+        .ok_or("config root is not an object")?
+        .entry("devices")
+        .or_insert_with(|| json!([]))
+        .as_array_mut()
+        .ok_or_else(|| "`devices` is not an array".to_string())
+}
+
+/// v0 (unversioned) -> v1: fill fields older builds could omit
+fn v0_to_v1(value: &mut Value) -> Result<(), String> {
+    let root = value.as_object_mut().ok_or("config root is not an object")?;
+    root.entry("default_ssh_user").or_insert_with(|| json!("root"));
+    for key in ["wg_config_path", "rustdesk_server", "rustdesk_key"] {
+        root.entry(key).or_insert(Value::Null);
+    }
+
+    for device in devices_mut(value)? {
+        let device = device.as_object_mut().ok_or("device entry is not an object")?;
+        device.entry("jump_hosts").or_insert_with(|| json!([]));
+        device.entry("rustdesk_id").or_insert(Value::Null);
+    }
+    Ok(())
+}
+
+/// v1 -> v2: replace name-derived device ids with UUIDs, rewriting
+/// jump host references to match
+fn v1_to_v2(value: &mut Value) -> Result<(), String> {
+    let devices = devices_mut(value)?;
+    let mut renamed: HashMap<String, String> = HashMap::new();
+    for device in devices.iter_mut() {
+        let id = device
+            .get("id")
+            .and_then(Value::as_str)
+            .ok_or("device entry has no id")?
+            .to_string();
+        if uuid::Uuid::parse_str(&id).is_ok() {
+            continue;
+        }
+        let new_id = uuid::Uuid::new_v4().to_string();
+        device["id"] = json!(new_id);
+        // Duplicate ids were ambiguous already; references keep the first
+        renamed.entry(id).or_insert(new_id);
+    }
+
+    for device in devices.iter_mut() {
+        if let Some(jumps) = device.get_mut("jump_hosts").and_then(Value::as_array_mut) {
+            for jump in jumps.iter_mut() {
+                if let Some(new_id) = jump.as_str().and_then(|j| renamed.get(j)) {
+                    *jump = json!(new_id);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// v2 -> v3: device groups and tags
+fn v2_to_v3(value: &mut Value) -> Result<(), String> {
+    let root = value.as_object_mut().ok_or("config root is not an object")?;
+    root.entry("groups").or_insert_with(|| json!([]));
+    for device in devices_mut(value)? {
+        let device = device.as_object_mut().ok_or("device entry is not an object")?;
+        device.entry("group").or_insert(Value::Null);
+        device.entry("tags").or_insert_with(|| json!([]));
+    }
+    Ok(())
+}
+
+/// v3 -> v4: Wake-on-LAN settings
+fn v3_to_v4(value: &mut Value) -> Result<(), String> {
+    for device in devices_mut(value)? {
+        let device = device.as_object_mut().ok_or("device entry is not an object")?;
+        device.entry("wol").or_insert(Value::Null);
+    }
+    Ok(())
+}
+
+/// v4 -> v5: an empty `jump_hosts` used to mean "inherit from the groups";
+/// it now means "connect directly", and inheriting is `null`
+fn v4_to_v5(value: &mut Value) -> Result<(), String> {
+    for device in devices_mut(value)? {
+        let device = device.as_object_mut().ok_or("device entry is not an object")?;
+        if device.get("jump_hosts").and_then(Value::as_array).is_some_and(|j| j.is_empty()) {
+            device.insert("jump_hosts".to_string(), Value::Null);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unversioned_config_is_upgraded() {
+        let mut value = json!({
+            "devices": [{ "id": "gpu1", "name": "gpu1", "vpn_ip": "10.0.0.2", "ssh_user": "lab" }],
+        });
+        let from = migrate(&mut value).unwrap();
+        assert_eq!(from, 0);
+        assert_eq!(schema_version(&value), CURRENT_SCHEMA_VERSION);
+        assert_eq!(value["default_ssh_user"], "root");
+        assert_eq!(value["devices"][0]["jump_hosts"], Value::Null);
+    }
+
+    #[test]
+    fn test_device_ids_become_uuids() {
+        let mut value = json!({
+            "schema_version": 1,
+            "devices": [
+                { "id": "bastion", "name": "Bastion", "jump_hosts": [] },
+                { "id": "gpu-1", "name": "GPU 1", "jump_hosts": ["bastion"] },
+            ],
+        });
+        migrate(&mut value).unwrap();
+        let bastion_id = value["devices"][0]["id"].as_str().unwrap();
+        assert!(uuid::Uuid::parse_str(bastion_id).is_ok());
+        assert_eq!(value["devices"][1]["jump_hosts"], json!([bastion_id]));
+        assert_eq!(value["devices"][0]["jump_hosts"], Value::Null);
+        assert_eq!(value["devices"][1]["name"], "GPU 1");
+    }
+
+    #[test]
+    fn test_newer_schema_is_rejected() {
+        let mut value = json!({ "schema_version": CURRENT_SCHEMA_VERSION + 1, "devices": [] });
+        assert!(migrate(&mut value).is_err());
+    }
+
+    #[test]
+    fn test_every_version_has_a_migration() {
+        assert_eq!(MIGRATIONS.len(), CURRENT_SCHEMA_VERSION as usize);
+    }
+}
@@ -0,0 +1,511 @@
+pub mod groups;
+pub mod merge;
+pub mod migrations;
+pub mod snippets;
+pub mod store;
+pub mod validate;
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{App, Manager};
+
+use crate::ssh_target::{JumpHost, SshTarget};
+pub use groups::{DeviceGroup, GroupDefaults};
+pub use snippets::Snippet;
+use migrations::CURRENT_SCHEMA_VERSION;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Device {
+    pub id: String,
+    pub name: String,
+    pub vpn_ip: String,
+    /// Empty to inherit from the device's groups / `default_ssh_user`
+    pub ssh_user: String,
+    pub rustdesk_id: Option<String>,
+    /// Override SSH host (if different from vpn_ip, e.g. public hostname)
+    #[serde(default)]
+    pub ssh_host: Option<String>,
+    /// Override SSH port (default 22)
+    #[serde(default)]
+    pub ssh_port: Option<u16>,
+    /// Ordered ids of devices to hop through (outermost bastion first).
+    /// None inherits from the device's groups; an empty list connects
+    /// directly even where a group sets jump hosts.
+    #[serde(default)]
+    pub jump_hosts: Option<Vec<String>>,
+    /// Private key to authenticate with (default: ssh's own lookup)
+    #[serde(default)]
+    pub identity_file: Option<String>,
+    /// Group the device is filed under; unset fields inherit its defaults
+    #[serde(default)]
+    pub group: Option<String>,
+    /// Free-form labels, stored lowercase
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Wake-on-LAN settings, None if the machine can't be woken remotely
+    #[serde(default)]
+    pub wol: Option<WakeOnLan>,
+    /// Record terminal sessions to an asciicast file unless turned off
+    /// when opening the session
+    #[serde(default)]
+    pub record_sessions: bool,
+    /// Output patterns watched for in this device's terminal sessions
+    #[serde(default)]
+    pub triggers: Vec<OutputTrigger>,
+    /// Directory terminal sessions start in (default: the remote home)
+    #[serde(default)]
+    pub initial_dir: Option<String>,
+    /// Exported before the remote shell starts
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    /// Run on the remote before the shell of new terminal sessions starts,
+    /// e.g. `conda activate lab`
+    #[serde(default)]
+    pub startup_command: Option<String>,
+    #[serde(default)]
+    pub forward_agent: bool,
+    #[serde(default)]
+    pub forward_x11: bool,
+    /// Extra `Key=Value` ssh options for terminal sessions
+    #[serde(default)]
+    pub ssh_options: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WakeOnLan {
+    pub mac_address: String,
+    /// Broadcast address for the magic packet (default 255.255.255.255)
+    #[serde(default)]
+    pub broadcast: Option<String>,
+    /// Interface to send from, on this machine or on the relay
+    #[serde(default)]
+    pub interface: Option<String>,
+    /// Device to send the packet from over SSH, for machines whose LAN is
+    /// only reachable through the VPN
+    #[serde(default)]
+    pub relay: Option<String>,
+}
+
+/// A regex watched for in terminal output, e.g. "Epoch \d+ done" or
+/// "Segmentation fault". Matched line by line, with escape sequences removed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutputTrigger {
+    pub id: String,
+    /// Used as the notification title; empty to show the pattern
+    #[serde(default)]
+    pub name: String,
+    pub pattern: String,
+    #[serde(default)]
+    pub case_insensitive: bool,
+    pub actions: Vec<TriggerAction>,
+    /// Minimum time between two firings, at least
+    /// `MIN_TRIGGER_COOLDOWN_SECS`; matches in between are counted but don't
+    /// fire
+    #[serde(default = "default_trigger_cooldown")]
+    pub cooldown_secs: u64,
+    #[serde(default = "default_trigger_enabled")]
+    pub enabled: bool,
+}
+
+/// Output can match thousands of lines a second
+pub const MIN_TRIGGER_COOLDOWN_SECS: u64 = 1;
+
+fn default_trigger_cooldown() -> u64 {
+    10
+}
+
+fn default_trigger_enabled() -> bool {
+    true
+}
+
+impl OutputTrigger {
+    pub fn regex(&self) -> Result<regex::Regex, String> {
+        if self.pattern.is_empty() {
+            return Err("Trigger pattern must not be empty".to_string());
+        }
+        regex::RegexBuilder::new(&self.pattern)
+            .case_insensitive(self.case_insensitive)
+            // Runs on every line of output; keep pathological patterns out
+            .size_limit(1 << 20)
+            .build()
+            .map_err(|e| format!("Invalid trigger pattern '{}': {}", self.pattern, e))
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        self.regex()?;
+        if self.actions.is_empty() {
+            return Err(format!("Trigger '{}' has no actions", self.pattern));
+        }
+        if self.cooldown_secs < MIN_TRIGGER_COOLDOWN_SECS {
+            return Err(format!(
+                "Trigger '{}' needs a cooldown of at least {}s",
+                self.pattern, MIN_TRIGGER_COOLDOWN_SECS
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TriggerAction {
+    /// Desktop notification
+    Notify,
+    /// `terminal-trigger` event for the rest of the UI
+    Event,
+    /// Mark the matching line in the terminal
+    Highlight,
+}
+
+impl Device {
+    /// Fresh id for a new device. Ids are opaque and never derived from the
+    /// name, so renaming a device does not break references to it.
+    pub fn new_id() -> String {
+        uuid::Uuid::new_v4().to_string()
+    }
+
+    /// Host used for SSH (explicit override, else the VPN address)
+    pub fn ssh_address(&self) -> &str {
+        self.ssh_host.as_deref().unwrap_or(&self.vpn_ip)
+    }
+
+    /// Jump hosts as set on this copy of the device; empty if unset
+    pub fn jumps(&self) -> &[String] {
+        self.jump_hosts.as_deref().unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppConfig {
+    /// Bumped whenever the on-disk layout changes; see `migrations`
+    #[serde(default)]
+    pub schema_version: u32,
+    pub devices: Vec<Device>,
+    #[serde(default)]
+    pub groups: Vec<DeviceGroup>,
+    pub wg_config_path: Option<String>,
+    pub default_ssh_user: String,
+    pub rustdesk_server: Option<String>,
+    pub rustdesk_key: Option<String>,
+    /// How terminal sessions reach the remote host
+    #[serde(default)]
+    pub ssh_backend: SshBackend,
+    /// Text macros that can be sent to terminal sessions
+    #[serde(default)]
+    pub snippets: Vec<Snippet>,
+}
+
+/// Terminal session transport
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SshBackend {
+    /// Native when the build includes it, otherwise the ssh binary
+    #[default]
+    Auto,
+    /// In-process client (`russh` feature)
+    Native,
+    /// Spawn the system `ssh` in a PTY
+    Process,
+}
+
+impl SshBackend {
+    /// Resolve `Auto` to what this build actually supports
+    pub fn resolve(self) -> Self {
+        match self {
+            Self::Auto if cfg!(feature = "russh") => Self::Native,
+            Self::Auto => Self::Process,
+            other => other,
+        }
+    }
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            schema_version: CURRENT_SCHEMA_VERSION,
+            devices: vec![],
+            groups: vec![],
+            wg_config_path: None,
+            default_ssh_user: "root".to_string(),
+            rustdesk_server: None,
+            rustdesk_key: None,
+            ssh_backend: SshBackend::Auto,
+            snippets: vec![],
+        }
+    }
+}
+
+impl AppConfig {
+    pub fn find_device(&self, id: &str) -> Option<&Device> {
+        self.devices.iter().find(|d| d.id == id)
+    }
+
+    /// Expand a device's jump chain into device ids. Jump devices that
+    /// themselves sit behind bastions are expanded in place, so the result
+    /// is the full path from the local machine outwards.
+    pub fn jump_chain_ids(&self, device_id: &str) -> Result<Vec<String>, String> {
+        let mut chain = Vec::new();
+        let mut visiting = vec![device_id.to_string()];
+        self.expand_jumps(device_id, &mut visiting, &mut chain)?;
+        Ok(chain)
+    }
+
+    /// Concrete hops for a device's jump chain
+    pub fn jump_chain(&self, device_id: &str) -> Result<Vec<JumpHost>, String> {
+        Ok(self
+            .jump_chain_ids(device_id)?
+            .iter()
+            .filter_map(|id| self.effective_device_by_id(id))
+            .map(|jump| JumpHost {
+                host: jump.ssh_address().to_string(),
+                user: jump.ssh_user.clone(),
+                port: jump.ssh_port,
+            })
+            .collect())
+    }
+
+    fn expand_jumps(
+        &self,
+        device_id: &str,
+        visiting: &mut Vec<String>,
+        chain: &mut Vec<String>,
+    ) -> Result<(), String> {
+        let device = self
+            .effective_device_by_id(device_id)
+            .ok_or_else(|| format!("Device '{}' not found", device_id))?;
+        for jump_id in device.jumps() {
+            if visiting.contains(jump_id) {
+                return Err(format!(
+                    "Jump host loop detected: {} -> {}",
+                    visiting.join(" -> "),
+                    jump_id
+                ));
+            }
+            if self.find_device(jump_id).is_none() {
+                return Err(format!("Jump host '{}' not found", jump_id));
+            }
+            visiting.push(jump_id.clone());
+            self.expand_jumps(jump_id, visiting, chain)?;
+            visiting.pop();
+            chain.push(jump_id.clone());
+        }
+        Ok(())
+    }
+
+    /// SSH target for a configured device, using its effective settings
+    pub fn device_target(&self, device_id: &str) -> Result<SshTarget, String> {
+        let device = self
+            .effective_device_by_id(device_id)
+            .ok_or_else(|| format!("Device '{}' not found", device_id))?;
+        self.resolve_target(
+            device.ssh_address(),
+            &device.ssh_user,
+            device.ssh_port,
+            Some(device_id),
+        )
+    }
+
+    /// SSH target for a command invocation: host/user/port as supplied by
+    /// the frontend, routed through the jump chain of `device_id` if given.
+    pub fn resolve_target(
+        &self,
+        host: &str,
+        user: &str,
+        port: Option<u16>,
+        device_id: Option<&str>,
+    ) -> Result<SshTarget, String> {
+        let target = SshTarget::new(host, user, port);
+        match device_id {
+            Some(id) => {
+                let identity = self.effective_device_by_id(id).and_then(|d| d.identity_file);
+                Ok(target
+                    .with_jumps(self.jump_chain(id)?)
+                    .with_identity(identity)
+                    .with_device(Some(id.to_string())))
+            }
+            None => Ok(target),
+        }
+    }
+}
+
+pub struct ConfigState(pub Mutex<AppConfig>, pub Mutex<Option<String>>);
+
+impl ConfigState {
+    /// Resolve an SSH target against the current config
+    pub fn resolve_target(
+        &self,
+        host: &str,
+        user: &str,
+        port: Option<u16>,
+        device_id: Option<&str>,
+    ) -> Result<SshTarget, String> {
+        self.0.lock().unwrap().resolve_target(host, user, port, device_id)
+    }
+
+    /// Persist `config`, encrypted if a password is set. Callers pass the
+    /// config they already hold locked, so this only takes the password lock.
+    pub fn persist(&self, config: &AppConfig) -> Result<(), String> {
+        let password = self.1.lock().unwrap().clone();
+        save_config_with_password(config, &password)
+    }
+}
+
+pub fn config_path() -> PathBuf {
+    let config_dir = dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("remotelab");
+    fs::create_dir_all(&config_dir).ok();
+    config_dir.join("config.json")
+}
+
+/// Rotating copies of previous config.json versions
+pub fn backups_dir() -> PathBuf {
+    config_path()
+        .parent()
+        .map(|p| p.join("backups"))
+        .unwrap_or_else(|| PathBuf::from("backups"))
+}
+
+pub fn init_config(app: &App) -> Result<(), Box<dyn std::error::Error>> {
+    let path = config_path();
+    let config = if path.exists() {
+        let data = fs::read(&path)?;
+        if crate::crypto::is_encrypted(&data) {
+            // Config is encrypted — start with empty config, frontend will prompt for password
+            AppConfig::default()
+        } else {
+            // Never fall back to defaults here: saving them would wipe the user's devices
+            let text = String::from_utf8(data)
+                .map_err(|_| format!("Config file {} is not valid UTF-8", path.display()))?;
+            let (config, from) = parse_config(&text).map_err(|e| {
+                format!(
+                    "Failed to load {}: {}. The file was left untouched; fix or remove it and restart.",
+                    path.display(),
+                    e
+                )
+            })?;
+            if from < CURRENT_SCHEMA_VERSION {
+                backup_before_migration(from)?;
+                save_config_with_password(&config, &None)?;
+            }
+            config
+        }
+    } else {
+        let config = AppConfig::default();
+        save_config_with_password(&config, &None)?;
+        config
+    };
+
+    app.manage(ConfigState(Mutex::new(config), Mutex::new(None)));
+    Ok(())
+}
+
+/// Parse config JSON, upgrading older schemas. Returns the config and the
+/// schema version the text was written with.
+pub fn parse_config(text: &str) -> Result<(AppConfig, u32), String> {
+    let mut value: serde_json::Value =
+        serde_json::from_str(text).map_err(|e| format!("invalid JSON: {}", e))?;
+    let from = migrations::migrate(&mut value)?;
+    let config = serde_json::from_value(value)
+        .map_err(|e| format!("does not match config schema v{}: {}", CURRENT_SCHEMA_VERSION, e))?;
+    Ok((config, from))
+}
+
+/// Copy the on-disk config aside before a migration rewrites it
+pub fn backup_before_migration(from_version: u32) -> Result<PathBuf, String> {
+    let path = config_path();
+    let backup = path.with_file_name(format!("config.v{}.json.bak", from_version));
+    fs::copy(&path, &backup).map_err(|e| format!("Failed to back up config: {}", e))?;
+    log::info!("Backed up schema v{} config to {}", from_version, backup.display());
+    Ok(backup)
+}
+
+/// Remove unencrypted `backup_before_migration` copies, which live next to
+/// config.json rather than in the backup rotation
+pub fn purge_plaintext_migration_backups() -> Result<usize, String> {
+    let path = config_path();
+    let Some(dir) = path.parent() else { return Ok(0) };
+    let entries = fs::read_dir(dir).map_err(|e| format!("Cannot read {}: {}", dir.display(), e))?;
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !(name.starts_with("config.v") && name.ends_with(".json.bak")) {
+            continue;
+        }
+        let encrypted = fs::read(entry.path()).is_ok_and(|data| crate::crypto::is_encrypted(&data));
+        if !encrypted && fs::remove_file(entry.path()).is_ok() {
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+/// The single write path for config.json: snapshot the current file into
+/// the backup rotation, then atomically replace it (encrypted if `password`).
+/// Prefer `ConfigState::persist`, which supplies the unlocked password.
+pub fn save_config_with_password(
+    config: &AppConfig,
+    password: &Option<String>,
+) -> Result<(), String> {
+    let path = config_path();
+    let json = serde_json::to_string_pretty(config)
+        .map_err(|e| format!("Failed to serialize config: {}", e))?;
+    let data = match password {
+        Some(pw) => crate::crypto::encrypt_config(&json, pw)?,
+        None => json.into_bytes(),
+    };
+    store::rotate_backup(&path, &backups_dir(), store::MAX_BACKUPS)?;
+    store::write_atomic(&path, &data)
+}
+
+/// A device at 10.0.0.2 with user `lab`, for the config tests to adjust
+#[cfg(test)]
+pub(crate) fn test_device(id: &str, name: &str) -> Device {
+    Device {
+        id: id.to_string(),
+        name: name.to_string(),
+        vpn_ip: "10.0.0.2".to_string(),
+        ssh_user: "lab".to_string(),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(id: &str, host: &str, jumps: &[&str]) -> Device {
+        Device {
+            vpn_ip: host.to_string(),
+            jump_hosts: Some(jumps.iter().map(|j| j.to_string()).collect()),
+            ..test_device(id, id)
+        }
+    }
+
+    #[test]
+    fn test_jump_chain_expands_nested_bastions() {
+        let config = AppConfig {
+            devices: vec![
+                device("edge", "203.0.113.1", &[]),
+                device("bastion", "10.0.0.1", &["edge"]),
+                device("gpu1", "10.0.1.5", &["bastion"]),
+            ],
+            ..Default::default()
+        };
+        let chain = config.jump_chain("gpu1").unwrap();
+        let hosts: Vec<_> = chain.iter().map(|j| j.host.as_str()).collect();
+        assert_eq!(hosts, vec!["203.0.113.1", "10.0.0.1"]);
+    }
+
+    #[test]
+    fn test_jump_chain_detects_loops() {
+        let config = AppConfig {
+            devices: vec![device("a", "10.0.0.1", &["b"]), device("b", "10.0.0.2", &["a"])],
+            ..Default::default()
+        };
+        assert!(config.jump_chain("a").unwrap_err().contains("loop"));
+    }
+}
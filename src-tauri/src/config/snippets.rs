@@ -0,0 +1,172 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::AppConfig;
+
+/// Text that can be sent to terminal sessions. `{{name}}` placeholders are
+/// filled in when sending.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snippet {
+    pub id: String,
+    pub name: String,
+    pub body: String,
+    /// Only offered for devices in this group or nested below it; None for
+    /// every session
+    #[serde(default)]
+    pub group: Option<String>,
+    /// Press Enter after the text
+    #[serde(default = "default_execute")]
+    pub execute: bool,
+}
+
+fn default_execute() -> bool {
+    true
+}
+
+/// A piece of a snippet body
+enum Part<'a> {
+    Text(&'a str),
+    Param(&'a str),
+}
+
+/// Split `body` into text and `{{param}}` placeholders. An unclosed `{{`
+/// is plain text.
+fn parse(body: &str) -> Vec<Part<'_>> {
+    let mut parts = Vec::new();
+    let mut rest = body;
+    while let Some(open) = rest.find("{{") {
+        let Some(close) = rest[open + 2..].find("}}") else { break };
+        if open > 0 {
+            parts.push(Part::Text(&rest[..open]));
+        }
+        parts.push(Part::Param(rest[open + 2..open + 2 + close].trim()));
+        rest = &rest[open + 2 + close + 2..];
+    }
+    if !rest.is_empty() {
+        parts.push(Part::Text(rest));
+    }
+    parts
+}
+
+impl Snippet {
+    pub fn new_id() -> String {
+        uuid::Uuid::new_v4().to_string()
+    }
+
+    /// Placeholder names, in order of first use
+    pub fn params(&self) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+        for part in parse(&self.body) {
+            if let Part::Param(name) = part {
+                if !names.iter().any(|n| n == name) {
+                    names.push(name.to_string());
+                }
+            }
+        }
+        names
+    }
+
+    /// The body with placeholders filled in, as keystrokes: line breaks
+    /// become Enter, plus a final Enter if `execute` is set
+    pub fn render(&self, values: &HashMap<String, String>) -> Result<String, String> {
+        let missing: Vec<String> = self
+            .params()
+            .into_iter()
+            .filter(|p| !values.contains_key(p))
+            .collect();
+        if !missing.is_empty() {
+            return Err(format!("Missing snippet parameters: {}", missing.join(", ")));
+        }
+        let mut text = String::new();
+        for part in parse(&self.body) {
+            match part {
+                Part::Text(t) => text.push_str(t),
+                Part::Param(name) => text.push_str(&values[name]),
+            }
+        }
+        let mut keys = text.replace("\r\n", "\r").replace('\n', "\r");
+        if self.execute && !keys.ends_with('\r') {
+            keys.push('\r');
+        }
+        Ok(keys)
+    }
+
+    pub fn validate(&self, config: &AppConfig) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Snippet name must not be empty".to_string());
+        }
+        if self.body.is_empty() {
+            return Err("Snippet body must not be empty".to_string());
+        }
+        for name in self.params() {
+            if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                return Err(format!("Invalid snippet parameter '{{{{{}}}}}'", name));
+            }
+        }
+        if let Some(group) = &self.group {
+            if config.find_group(group).is_none() {
+                return Err(format!("Group '{}' not found", group));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl AppConfig {
+    pub fn find_snippet(&self, id: &str) -> Option<&Snippet> {
+        self.snippets.iter().find(|s| s.id == id)
+    }
+
+    /// Snippets available to a session: the global ones plus, for a
+    /// device, those of its group and the groups enclosing it
+    pub fn snippets_for_device(&self, device_id: Option<&str>) -> Vec<Snippet> {
+        let group = device_id
+            .and_then(|id| self.find_device(id))
+            .and_then(|d| d.group.as_deref());
+        self.snippets
+            .iter()
+            .filter(|s| match (&s.group, group) {
+                (None, _) => true,
+                (Some(wanted), Some(group)) => self.group_is_within(group, wanted),
+                (Some(_), None) => false,
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snippet(body: &str) -> Snippet {
+        Snippet {
+            id: "s".into(),
+            name: "train".into(),
+            body: body.into(),
+            group: None,
+            execute: true,
+        }
+    }
+
+    #[test]
+    fn test_render_substitutes_params() {
+        let s = snippet("cd {{ dir }}\npython train.py --epochs {{epochs}} {{dir}}");
+        assert_eq!(s.params(), vec!["dir", "epochs"]);
+
+        let mut values = HashMap::new();
+        values.insert("dir".to_string(), "/data".to_string());
+        assert_eq!(s.render(&values).unwrap_err(), "Missing snippet parameters: epochs");
+
+        values.insert("epochs".to_string(), "100".to_string());
+        assert_eq!(
+            s.render(&values).unwrap(),
+            "cd /data\rpython train.py --epochs 100 /data\r"
+        );
+
+        let literal = Snippet { execute: false, ..snippet("echo {{ unclosed") };
+        assert!(literal.params().is_empty());
+        assert_eq!(literal.render(&HashMap::new()).unwrap(), "echo {{ unclosed");
+        assert!(snippet("{{bad name}}").validate(&AppConfig::default()).is_err());
+    }
+}
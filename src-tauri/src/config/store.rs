@@ -0,0 +1,179 @@
+use serde::Serialize;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// How many previous versions of config.json to keep
+pub const MAX_BACKUPS: usize = 10;
+
+const BACKUP_PREFIX: &str = "config-";
+const BACKUP_SUFFIX: &str = ".json";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupInfo {
+    pub name: String,
+    pub path: String,
+    /// Unix time in milliseconds when the backup was taken
+    pub created_ms: u64,
+    pub size: u64,
+    pub encrypted: bool,
+}
+
+/// Write `data` to `path` atomically: temp file in the same directory,
+/// fsync, then rename over the target. A crash leaves either the old or
+/// the new file, never a truncated one.
+pub fn write_atomic(path: &Path, data: &[u8]) -> Result<(), String> {
+    let dir = path.parent().ok_or("Path has no parent directory")?;
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let tmp = dir.join(format!(".{}.tmp-{}", file_name, std::process::id()));
+
+    let result = (|| -> std::io::Result<()> {
+        let mut file = fs::File::create(&tmp)?;
+        file.write_all(data)?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)?;
+        // Persist the rename itself
+        #[cfg(unix)]
+        fs::File::open(dir)?.sync_all()?;
+        Ok(())
+    })();
+
+    if let Err(e) = result {
+        fs::remove_file(&tmp).ok();
+        return Err(format!("Failed to write {}: {}", path.display(), e));
+    }
+    Ok(())
+}
+
+/// Copy the current file (if any) into `backup_dir`, then drop the oldest
+/// backups beyond `keep`
+pub fn rotate_backup(path: &Path, backup_dir: &Path, keep: usize) -> Result<(), String> {
+    if !path.exists() {
+        return Ok(());
+    }
+    fs::create_dir_all(backup_dir).map_err(|e| format!("Failed to create backup dir: {}", e))?;
+
+    let now_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    // Stay strictly after the newest backup even if several saves land in
+    // the same millisecond (or the clock steps back), so order is preserved
+    let existing = list_backups(backup_dir)?;
+    let stamp = match existing.first() {
+        Some(newest) => now_ms.max(newest.created_ms + 1),
+        None => now_ms,
+    };
+    let backup = backup_dir.join(backup_name(stamp));
+    fs::copy(path, &backup).map_err(|e| format!("Failed to back up config: {}", e))?;
+
+    let backups = list_backups(backup_dir)?;
+    for old in backups.iter().skip(keep) {
+        fs::remove_file(&old.path).ok();
+    }
+    Ok(())
+}
+
+fn backup_name(ms: u64) -> String {
+    // Zero-padded so lexical order is chronological
+    format!("{}{:013}{}", BACKUP_PREFIX, ms, BACKUP_SUFFIX)
+}
+
+/// Backups in `backup_dir`, newest first
+pub fn list_backups(backup_dir: &Path) -> Result<Vec<BackupInfo>, String> {
+    if !backup_dir.exists() {
+        return Ok(Vec::new());
+    }
+    let entries =
+        fs::read_dir(backup_dir).map_err(|e| format!("Failed to read backup dir: {}", e))?;
+
+    let mut backups: Vec<BackupInfo> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let created_ms = name
+                .strip_prefix(BACKUP_PREFIX)?
+                .strip_suffix(BACKUP_SUFFIX)?
+                .parse()
+                .ok()?;
+            let path = entry.path();
+            let data = fs::read(&path).ok()?;
+            Some(BackupInfo {
+                name,
+                path: path.to_string_lossy().to_string(),
+                created_ms,
+                size: data.len() as u64,
+                encrypted: crate::crypto::is_encrypted(&data),
+            })
+        })
+        .collect();
+    backups.sort_by_key(|b| std::cmp::Reverse(b.created_ms));
+    Ok(backups)
+}
+
+/// Resolve a backup by file name, refusing anything outside `backup_dir`
+pub fn backup_path(backup_dir: &Path, name: &str) -> Result<PathBuf, String> {
+    if name.contains(['/', '\\']) || !name.starts_with(BACKUP_PREFIX) {
+        return Err(format!("Invalid backup name: {}", name));
+    }
+    let path = backup_dir.join(name);
+    if !path.exists() {
+        return Err(format!("Backup '{}' not found", name));
+    }
+    Ok(path)
+}
+
+/// Remove unencrypted backups, e.g. once the user turns on encryption
+pub fn purge_plaintext_backups(backup_dir: &Path) -> Result<usize, String> {
+    let mut removed = 0;
+    for backup in list_backups(backup_dir)?.iter().filter(|b| !b.encrypted) {
+        if fs::remove_file(&backup.path).is_ok() {
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(tag: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("remotelab-store-{}-{}", tag, std::process::id()));
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_write_atomic_replaces_contents() {
+        let dir = temp_dir("atomic");
+        let path = dir.join("config.json");
+        write_atomic(&path, b"{\"a\":1}").unwrap();
+        write_atomic(&path, b"{\"a\":2}").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{\"a\":2}");
+        // No stray temp files left behind
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_rotate_backup_keeps_newest() {
+        let dir = temp_dir("rotate");
+        let path = dir.join("config.json");
+        let backups = dir.join("backups");
+        for i in 0..5 {
+            fs::write(&path, format!("{{\"v\":{}}}", i)).unwrap();
+            rotate_backup(&path, &backups, 3).unwrap();
+        }
+        let list = list_backups(&backups).unwrap();
+        assert_eq!(list.len(), 3);
+        assert_eq!(fs::read_to_string(&list[0].path).unwrap(), "{\"v\":4}");
+        assert!(backup_path(&backups, "../config.json").is_err());
+        fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,317 @@
+use std::net::{IpAddr, Ipv4Addr};
+
+use super::{AppConfig, Device, DeviceGroup, WakeOnLan};
+
+/// Check a device before it is stored. `config` is the current config; the
+/// device may be new or replace the entry with the same id.
+pub fn validate_device(device: &Device, config: &AppConfig) -> Result<(), String> {
+    let name = device.name.trim();
+    if name.is_empty() {
+        return Err("Device name must not be empty".to_string());
+    }
+    if let Some(other) = config
+        .devices
+        .iter()
+        .find(|d| d.id != device.id && d.name.trim().eq_ignore_ascii_case(name))
+    {
+        return Err(format!("A device named '{}' already exists", other.name));
+    }
+
+    if !is_valid_host(&device.vpn_ip) {
+        return Err(format!("Invalid address '{}'", device.vpn_ip));
+    }
+    if let Some(host) = &device.ssh_host {
+        if !is_valid_host(host) {
+            return Err(format!("Invalid SSH host '{}'", host));
+        }
+    }
+    if device.ssh_port == Some(0) {
+        return Err("SSH port must be between 1 and 65535".to_string());
+    }
+    // Empty means inherit from the device's groups
+    if !device.ssh_user.is_empty() && !is_valid_user(&device.ssh_user) {
+        return Err(format!("Invalid SSH user '{}'", device.ssh_user));
+    }
+    if let Some(key) = &device.identity_file {
+        if key.trim().is_empty() {
+            return Err("Identity file path must not be empty".to_string());
+        }
+    }
+
+    if let Some(group) = &device.group {
+        if config.find_group(group).is_none() {
+            return Err(format!("Group '{}' not found", group));
+        }
+    }
+    if let Some(wol) = &device.wol {
+        validate_wol(wol, device, config)?;
+    }
+    for trigger in &device.triggers {
+        trigger.validate()?;
+    }
+    validate_session_settings(device)?;
+
+    if device.jumps().contains(&device.id) {
+        return Err("A device cannot be its own jump host".to_string());
+    }
+    // Resolve the chain as it would look after saving, to catch loops
+    // through other devices as well as missing ones
+    let mut updated = config.clone();
+    match updated.devices.iter_mut().find(|d| d.id == device.id) {
+        Some(slot) => *slot = device.clone(),
+        None => updated.devices.push(device.clone()),
+    }
+    updated.jump_chain_ids(&device.id)?;
+    Ok(())
+}
+
+/// Check a group before it is stored, including that its default jump hosts
+/// don't create a loop for any device that would inherit them
+pub fn validate_group(group: &DeviceGroup, config: &AppConfig) -> Result<(), String> {
+    let name = group.name.trim();
+    if name.is_empty() {
+        return Err("Group name must not be empty".to_string());
+    }
+    if config.groups.iter().any(|g| {
+        g.id != group.id && g.parent == group.parent && g.name.trim().eq_ignore_ascii_case(name)
+    }) {
+        return Err(format!("A group named '{}' already exists here", name));
+    }
+
+    let mut updated = config.clone();
+    match updated.groups.iter_mut().find(|g| g.id == group.id) {
+        Some(slot) => *slot = group.clone(),
+        None => updated.groups.push(group.clone()),
+    }
+
+    if let Some(parent) = &group.parent {
+        if updated.find_group(parent).is_none() {
+            return Err(format!("Parent group '{}' not found", parent));
+        }
+        if updated.group_is_within(parent, &group.id) {
+            return Err("A group cannot be nested inside itself".to_string());
+        }
+    }
+
+    let defaults = &group.defaults;
+    if defaults.ssh_port == Some(0) {
+        return Err("SSH port must be between 1 and 65535".to_string());
+    }
+    if let Some(user) = &defaults.ssh_user {
+        if !is_valid_user(user) {
+            return Err(format!("Invalid SSH user '{}'", user));
+        }
+    }
+    for jump in defaults.jump_hosts.iter().flatten() {
+        if updated.find_device(jump).is_none() {
+            return Err(format!("Jump host '{}' not found", jump));
+        }
+    }
+    for device in &updated.devices {
+        updated.jump_chain_ids(&device.id)?;
+    }
+    Ok(())
+}
+
+/// ssh options a device may set. Anything that runs a local command
+/// (`ProxyCommand`, `LocalCommand`, `KnownHostsCommand`, …), weakens host key
+/// checking or exposes this machine is left out: devices arrive through
+/// config imports from other people.
+const ALLOWED_SSH_OPTIONS: &[&str] = &[
+    "AddressFamily",
+    "BatchMode",
+    "Ciphers",
+    "Compression",
+    "ConnectionAttempts",
+    "ConnectTimeout",
+    "EscapeChar",
+    "ExitOnForwardFailure",
+    "GSSAPIAuthentication",
+    "HostKeyAlgorithms",
+    "IdentitiesOnly",
+    "IPQoS",
+    "KbdInteractiveAuthentication",
+    "KexAlgorithms",
+    "LocalForward",
+    "LogLevel",
+    "MACs",
+    "NumberOfPasswordPrompts",
+    "PasswordAuthentication",
+    "PreferredAuthentications",
+    "PubkeyAcceptedAlgorithms",
+    "PubkeyAuthentication",
+    "RekeyLimit",
+    "RequestTTY",
+    "ServerAliveCountMax",
+    "ServerAliveInterval",
+    "TCPKeepAlive",
+];
+
+/// Whether a `Key=Value` ssh option is on the allowlist
+pub fn is_allowed_ssh_option(option: &str) -> bool {
+    let key = option.split_once('=').map(|(key, _)| key.trim()).unwrap_or_default();
+    ALLOWED_SSH_OPTIONS.iter().any(|allowed| allowed.eq_ignore_ascii_case(key))
+}
+
+/// Startup directory, environment, command and ssh options
+fn validate_session_settings(device: &Device) -> Result<(), String> {
+    if device.initial_dir.as_deref().is_some_and(|d| d.trim().is_empty()) {
+        return Err("Initial directory must not be empty".to_string());
+    }
+    if device.startup_command.as_deref().is_some_and(|c| c.trim().is_empty()) {
+        return Err("Startup command must not be empty".to_string());
+    }
+    for name in device.env.keys() {
+        let valid = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid {
+            return Err(format!("Invalid environment variable name '{}'", name));
+        }
+    }
+    for option in &device.ssh_options {
+        let valid = option
+            .split_once('=')
+            .is_some_and(|(key, value)| {
+                !key.is_empty()
+                    && key.chars().all(|c| c.is_ascii_alphanumeric())
+                    && !value.trim().is_empty()
+            });
+        if !valid {
+            return Err(format!("Invalid ssh option '{}' (expected Key=Value)", option));
+        }
+        if !is_allowed_ssh_option(option) {
+            let key = option.split_once('=').map(|(key, _)| key).unwrap_or_default();
+            return Err(format!("ssh option '{}' is not allowed", key));
+        }
+    }
+    Ok(())
+}
+
+fn validate_wol(wol: &WakeOnLan, device: &Device, config: &AppConfig) -> Result<(), String> {
+    crate::wol::parse_mac(&wol.mac_address)?;
+    if let Some(bcast) = &wol.broadcast {
+        bcast
+            .parse::<Ipv4Addr>()
+            .map_err(|_| format!("Invalid broadcast address '{}'", bcast))?;
+    }
+    if let Some(iface) = &wol.interface {
+        let valid = !iface.is_empty()
+            && iface.len() <= 32
+            && iface.chars().all(|c| c.is_ascii_alphanumeric() || "-_.@:".contains(c));
+        if !valid {
+            return Err(format!("Invalid interface name '{}'", iface));
+        }
+    }
+    if let Some(relay) = &wol.relay {
+        if relay == &device.id {
+            return Err("A device cannot wake itself".to_string());
+        }
+        if config.find_device(relay).is_none() {
+            return Err(format!("Relay device '{}' not found", relay));
+        }
+    }
+    Ok(())
+}
+
+/// Something ssh will accept before the `@` of a destination
+fn is_valid_user(user: &str) -> bool {
+    !user.is_empty() && !user.contains(|c: char| c.is_whitespace() || c == '@' || c == ':')
+}
+
+/// An IP literal (v4, or v6 with or without brackets) or a DNS hostname
+pub fn is_valid_host(host: &str) -> bool {
+    let unbracketed = host
+        .strip_prefix('[')
+        .and_then(|h| h.strip_suffix(']'))
+        .unwrap_or(host);
+    unbracketed.parse::<IpAddr>().is_ok() || is_valid_hostname(host)
+}
+
+/// RFC 1123 hostname: dot-separated labels of letters, digits and hyphens,
+/// each 1-63 characters and not starting or ending with a hyphen
+pub fn is_valid_hostname(host: &str) -> bool {
+    let host = host.strip_suffix('.').unwrap_or(host);
+    if host.is_empty() || host.len() > 253 {
+        return false;
+    }
+    // All-numeric names like 10.0.0.300 are mistyped addresses, not hosts
+    if host.split('.').all(|l| l.chars().all(|c| c.is_ascii_digit())) {
+        return false;
+    }
+    host.split('.').all(|label| {
+        !label.is_empty()
+            && label.len() <= 63
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_device;
+
+    #[test]
+    fn test_host_validation() {
+        assert!(is_valid_host("10.0.0.2"));
+        assert!(is_valid_host("fd00::1"));
+        assert!(is_valid_host("[fd00::1]"));
+        assert!(is_valid_host("gpu-1.lab.example.com"));
+        assert!(!is_valid_host("10.0.0.300"));
+        assert!(!is_valid_host("-bad.example.com"));
+        assert!(!is_valid_host("has space"));
+        assert!(!is_valid_host(""));
+    }
+
+    #[test]
+    fn test_device_validation() {
+        let config = AppConfig {
+            devices: vec![test_device("a", "Bastion")],
+            ..Default::default()
+        };
+
+        // Renaming a device to its own name is fine, taking another's is not
+        assert!(validate_device(&test_device("a", "bastion"), &config).is_ok());
+        assert!(validate_device(&test_device("b", "BASTION"), &config).is_err());
+
+        let mut bad_port = test_device("b", "gpu1");
+        bad_port.ssh_port = Some(0);
+        assert!(validate_device(&bad_port, &config).is_err());
+
+        let mut missing_jump = test_device("b", "gpu1");
+        missing_jump.jump_hosts = Some(vec!["nope".to_string()]);
+        assert!(validate_device(&missing_jump, &config).is_err());
+
+        // a -> b would close a loop once b jumps through a
+        let mut looped = config.clone();
+        looped.devices[0].jump_hosts = Some(vec!["b".to_string()]);
+        let mut b = test_device("b", "gpu1");
+        b.jump_hosts = Some(vec!["a".to_string()]);
+        assert!(validate_device(&b, &looped).unwrap_err().contains("loop"));
+    }
+
+    #[test]
+    fn test_ssh_options_that_run_commands_are_rejected() {
+        let config = AppConfig::default();
+        let with_option = |option: &str| {
+            let mut d = test_device("a", "gpu1");
+            d.ssh_options = vec![option.to_string()];
+            validate_device(&d, &config)
+        };
+        assert!(with_option("Compression=yes").is_ok());
+        assert!(with_option("serveraliveinterval=15").is_ok());
+        for rejected in [
+            "ProxyCommand=nc %h %p",
+            "proxycommand=sh -c id",
+            "LocalCommand=touch /tmp/x",
+            "PermitLocalCommand=yes",
+            "KnownHostsCommand=/bin/true",
+            "Match=exec true",
+            "StrictHostKeyChecking=no",
+            "RemoteForward=2222 localhost:22",
+        ] {
+            assert!(with_option(rejected).unwrap_err().contains("not allowed"), "{}", rejected);
+        }
+    }
+}
@@ -1,5 +1,7 @@
 use std::process::{Command, Stdio};
 
+use crate::ssh_target::SshTarget;
+
 /// GPU capabilities detected on remote host
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct GpuInfo {
@@ -29,8 +31,8 @@ impl GpuInfo {
 }
 
 /// Detect GPU capabilities on remote host via SSH
-pub fn detect_remote_gpu(host: &str, user: &str, port: Option<u16>) -> Result<GpuInfo, String> {
-    log::info!("Detecting GPU on {}@{}:{}", user, host, port.unwrap_or(22));
+pub fn detect_remote_gpu(target: &SshTarget) -> Result<GpuInfo, String> {
+    log::info!("Detecting GPU on {}:{}", target.destination(), target.port.unwrap_or(22));
 
     let script = r#"
 # GPU info — check nvidia-smi exit code first
@@ -77,11 +79,8 @@ echo "DISPLAY:$HAS_DISPLAY"
         "-o".to_string(), "StrictHostKeyChecking=accept-new".to_string(),
         "-o".to_string(), "ConnectTimeout=10".to_string(),
     ];
-    if let Some(p) = port {
-        ssh_args.push("-p".to_string());
-        ssh_args.push(p.to_string());
-    }
-    ssh_args.push(format!("{}@{}", user, host));
+    ssh_args.extend(target.ssh_args());
+    ssh_args.push(target.destination());
     ssh_args.push("bash".to_string());
 
     #[cfg(unix)]
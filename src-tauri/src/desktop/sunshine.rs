@@ -2,6 +2,8 @@ use std::io::BufRead;
 use std::process::{Command, Stdio};
 use tauri::Emitter;
 
+use crate::ssh_target::SshTarget;
+
 /// Emit a progress event to the frontend
 fn emit_progress(app: &tauri::AppHandle, percent: u8, message: &str) {
     log::info!("Sunshine progress: {}% - {}", percent, message);
@@ -18,17 +20,11 @@ fn emit_progress(app: &tauri::AppHandle, percent: u8, message: &str) {
 /// Setup Sunshine on remote host for NVENC GPU streaming.
 /// Installs xorg + xfce4 + Sunshine if not present (first-time may take 3-5 min).
 /// Returns the Sunshine web UI port (47990).
-pub fn setup_sunshine(
-    host: &str,
-    user: &str,
-    port: Option<u16>,
-    app: &tauri::AppHandle,
-) -> Result<u16, String> {
+pub fn setup_sunshine(target: &SshTarget, app: &tauri::AppHandle) -> Result<u16, String> {
     log::info!(
-        "Setting up Sunshine on {}@{}:{}",
-        user,
-        host,
-        port.unwrap_or(22)
+        "Setting up Sunshine on {}:{}",
+        target.destination(),
+        target.port.unwrap_or(22)
     );
 
     let script = r#"#!/bin/bash
@@ -240,11 +236,8 @@ exit 1
         "-o".to_string(),
         "ServerAliveInterval=30".to_string(),
     ];
-    if let Some(p) = port {
-        ssh_args.push("-p".to_string());
-        ssh_args.push(p.to_string());
-    }
-    ssh_args.push(format!("{}@{}", user, host));
+    ssh_args.extend(target.ssh_args());
+    ssh_args.push(target.destination());
     ssh_args.push("bash".to_string());
 
     #[cfg(unix)]
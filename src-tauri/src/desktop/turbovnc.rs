@@ -2,6 +2,8 @@ use std::io::BufRead;
 use std::process::{Command, Stdio};
 use tauri::Emitter;
 
+use crate::ssh_target::SshTarget;
+
 /// Setup VNC on remote host for remote desktop.
 /// Strategy:
 ///   1. Check for already-running VNC server → reuse it
@@ -9,8 +11,8 @@ use tauri::Emitter;
 ///   3. Start x11vnc with -create (auto-creates virtual display via Xvfb)
 ///      or attach to existing user X display
 /// Returns the VNC port number on the remote host.
-pub fn setup_turbovnc(host: &str, user: &str, port: Option<u16>, app: &tauri::AppHandle) -> Result<u16, String> {
-    log::info!("Setting up VNC on {}@{}:{}", user, host, port.unwrap_or(22));
+pub fn setup_turbovnc(target: &SshTarget, app: &tauri::AppHandle) -> Result<u16, String> {
+    log::info!("Setting up VNC on {}:{}", target.destination(), target.port.unwrap_or(22));
 
     let script = r#"#!/bin/bash
 
@@ -237,11 +239,8 @@ exit 1
         "-o".to_string(), "ConnectTimeout=15".to_string(),
         "-o".to_string(), "ServerAliveInterval=30".to_string(),
     ];
-    if let Some(p) = port {
-        ssh_args.push("-p".to_string());
-        ssh_args.push(p.to_string());
-    }
-    ssh_args.push(format!("{}@{}", user, host));
+    ssh_args.extend(target.ssh_args());
+    ssh_args.push(target.destination());
     ssh_args.push("bash".to_string());
 
     #[cfg(unix)]
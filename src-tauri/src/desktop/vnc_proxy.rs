@@ -5,6 +5,8 @@ use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::{TcpListener, TcpStream};
 use tokio_tungstenite::tungstenite::Message;
 
+use crate::ssh_target::SshTarget;
+
 /// Manages SSH tunnel + WebSocket-to-TCP proxy for VNC connections
 pub struct VncProxy {
     /// SSH tunnel process
@@ -40,8 +42,8 @@ impl VncProxy {
 
     /// Auto-setup VNC server on remote host via SSH
     /// Installs x11vnc if needed and starts it
-    pub fn setup_remote_vnc(&self, host: &str, user: &str) -> Result<u16, String> {
-        log::info!("Setting up VNC server on {}@{}...", user, host);
+    pub fn setup_remote_vnc(&self, target: &SshTarget) -> Result<u16, String> {
+        log::info!("Setting up VNC server on {}...", target.destination());
 
         // Run a setup script on the remote host via SSH
         // This will: check for x11vnc, install if needed, find a display, start x11vnc
@@ -117,14 +119,17 @@ else
 fi
 "#;
 
+        let mut ssh_args = vec![
+            "-o".to_string(), "StrictHostKeyChecking=accept-new".to_string(),
+            "-o".to_string(), "ConnectTimeout=10".to_string(),
+            "-o".to_string(), "ServerAliveInterval=30".to_string(),
+        ];
+        ssh_args.extend(target.ssh_args());
+        ssh_args.push(target.destination());
+        ssh_args.push("bash".to_string());
+
         let output = Command::new("/usr/bin/ssh")
-            .args([
-                "-o", "StrictHostKeyChecking=accept-new",
-                "-o", "ConnectTimeout=10",
-                "-o", "ServerAliveInterval=30",
-                &format!("{}@{}", user, host),
-                "bash",
-            ])
+            .args(&ssh_args)
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
@@ -169,7 +174,7 @@ fi
     }
 
     /// Start SSH tunnel: ssh -L <local>:localhost:<remote_vnc_port> -N user@host
-    pub fn start_tunnel(&self, host: &str, user: &str, vnc_port: u16, ssh_port: Option<u16>) -> Result<u16, String> {
+    pub fn start_tunnel(&self, target: &SshTarget, vnc_port: u16) -> Result<u16, String> {
         // Stop any existing tunnel
         self.stop();
 
@@ -181,14 +186,11 @@ fi
             "-o".to_string(), "ExitOnForwardFailure=yes".to_string(),
             "-o".to_string(), "ConnectTimeout=10".to_string(),
         ];
-        if let Some(p) = ssh_port {
-            ssh_args.push("-p".to_string());
-            ssh_args.push(p.to_string());
-        }
+        ssh_args.extend(target.ssh_args());
         ssh_args.push("-N".to_string());
         ssh_args.push("-L".to_string());
         ssh_args.push(format!("{}:localhost:{}", local_port, vnc_port));
-        ssh_args.push(format!("{}@{}", user, host));
+        ssh_args.push(target.destination());
 
         let child = Command::new("/usr/bin/ssh")
             .args(&ssh_args)
@@ -261,20 +263,18 @@ fi
     /// Full VNC connection: auto-setup remote VNC server + tunnel + WS proxy
     pub async fn connect(
         &self,
-        host: &str,
-        user: &str,
+        target: &SshTarget,
         remote_vnc_port: Option<u16>,
-        ssh_port: Option<u16>,
     ) -> Result<u16, String> {
         // Step 1: Auto-setup VNC server on remote host (install x11vnc if needed)
         let vnc_port = if let Some(port) = remote_vnc_port {
             port
         } else {
-            self.setup_remote_vnc(host, user)?
+            self.setup_remote_vnc(target)?
         };
 
         // Step 2: SSH tunnel
-        self.start_tunnel(host, user, vnc_port, ssh_port)?;
+        self.start_tunnel(target, vnc_port)?;
 
         // Step 3: WebSocket proxy
         self.start_ws_proxy().await
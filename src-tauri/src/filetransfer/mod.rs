@@ -1 +1,5 @@
 pub mod ops;
+pub mod queue;
+pub mod sftp;
+pub mod sync;
+pub mod tree;
@@ -1,29 +1,119 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
+use std::io::{Seek, SeekFrom, Write};
 use std::process::{Command, Stdio};
-use tauri::Emitter;
+
+use super::sftp::{self, FileAttrs, SftpSession};
+use crate::audit::{self, AuditAction};
+use crate::ssh_target::SshTarget;
+use crate::terminal::startup::quote;
 
 #[cfg(unix)]
-fn ssh_bin() -> &'static str { "/usr/bin/ssh" }
-#[cfg(windows)]
-fn ssh_bin() -> &'static str { "ssh" }
-#[cfg(unix)]
-fn scp_bin() -> &'static str { "/usr/bin/scp" }
+pub(crate) fn ssh_bin() -> &'static str { "/usr/bin/ssh" }
 #[cfg(windows)]
-fn scp_bin() -> &'static str { "scp" }
+pub(crate) fn ssh_bin() -> &'static str { "ssh" }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileKind {
+    File,
+    Dir,
+    Symlink,
+    Other,
+}
 
 #[derive(Debug, Clone, Serialize)]
 pub struct RemoteFile {
     pub name: String,
     pub path: String,
+    /// True for directories and symlinks to directories
     pub is_dir: bool,
     pub size: u64,
+    /// For display, e.g. "Jan  5 14:03"
     pub modified: String,
+    /// `ls -l` style, e.g. "drwxr-xr-x"
     pub permissions: String,
+    pub kind: FileKind,
+    /// Unix time in seconds
+    pub mtime: Option<u64>,
+    /// Permission bits, without the file type
+    pub mode: Option<u32>,
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+    /// Account and group names, when the server's listing includes them
+    pub owner: Option<String>,
+    pub group: Option<String>,
+    pub symlink_target: Option<String>,
+}
+
+impl RemoteFile {
+    /// `attrs` as from LSTAT, so a symlink is reported as one
+    fn new(name: &str, path: String, attrs: &FileAttrs) -> Self {
+        let kind = if attrs.is_dir() {
+            FileKind::Dir
+        } else if attrs.is_symlink() {
+            FileKind::Symlink
+        } else if attrs.is_file() {
+            FileKind::File
+        } else {
+            FileKind::Other
+        };
+        Self {
+            name: name.to_string(),
+            path,
+            is_dir: kind == FileKind::Dir,
+            size: attrs.size.unwrap_or(0),
+            modified: match attrs.mtime {
+                Some(t) if t > 0 => format_epoch(t as i64),
+                _ => "-".to_string(),
+            },
+            permissions: attrs
+                .permissions
+                .map(sftp::mode_string)
+                .unwrap_or_else(|| "----------".to_string()),
+            kind,
+            mtime: attrs.mtime.map(u64::from),
+            mode: attrs.permissions.map(|p| p & 0o7777),
+            uid: attrs.uid,
+            gid: attrs.gid,
+            owner: None,
+            group: None,
+            symlink_target: None,
+        }
+    }
+
+    /// Fill in what needs another request for symlinks: the target, and
+    /// whether it is a directory
+    fn resolve_link(&mut self, sftp: &mut SftpSession) {
+        if self.kind != FileKind::Symlink {
+            return;
+        }
+        self.symlink_target = sftp.readlink(&self.path).ok();
+        self.is_dir = sftp.try_stat(&self.path).ok().flatten().is_some_and(|a| a.is_dir());
+    }
+}
+
+/// Owner and group columns of an `ls -l` style line
+fn longname_owner(longname: &str) -> Option<(String, String)> {
+    let fields: Vec<&str> = longname.split_whitespace().collect();
+    if fields.len() < 4 || fields[0].len() < 10 {
+        return None;
+    }
+    Some((fields[2].to_string(), fields[3].to_string()))
+}
+
+/// `name` inside remote directory `dir`
+pub fn join_remote(dir: &str, name: &str) -> String {
+    if dir.ends_with('/') {
+        format!("{}{}", dir, name)
+    } else {
+        format!("{}/{}", dir, name)
+    }
 }
 
-/// Build common SSH args for host/user/port
-fn ssh_base_args(user: &str, host: &str, port: Option<u16>) -> Vec<String> {
+/// Common SSH args for a target (options, port, jump chain), without the
+/// destination
+pub(crate) fn ssh_connect_args(target: &SshTarget) -> Vec<String> {
     let mut args = vec![
         "-o".to_string(),
         "StrictHostKeyChecking=accept-new".to_string(),
@@ -32,17 +122,20 @@ fn ssh_base_args(user: &str, host: &str, port: Option<u16>) -> Vec<String> {
         "-o".to_string(),
         "ServerAliveInterval=30".to_string(),
     ];
-    if let Some(p) = port {
-        args.push("-p".to_string());
-        args.push(p.to_string());
-    }
-    args.push(format!("{}@{}", user, host));
+    args.extend(target.ssh_args());
+    args
+}
+
+/// `ssh_connect_args` followed by the destination
+fn ssh_base_args(target: &SshTarget) -> Vec<String> {
+    let mut args = ssh_connect_args(target);
+    args.push(target.destination());
     args
 }
 
 /// Run a remote command and return stdout
-fn ssh_exec(user: &str, host: &str, port: Option<u16>, command: &str) -> Result<String, String> {
-    let mut args = ssh_base_args(user, host, port);
+pub(crate) fn ssh_exec(target: &SshTarget, command: &str) -> Result<String, String> {
+    let mut args = ssh_base_args(target);
     args.push("bash".to_string());
 
     let mut child = Command::new(ssh_bin())
@@ -72,75 +165,37 @@ fn ssh_exec(user: &str, host: &str, port: Option<u16>, command: &str) -> Result<
     Ok(String::from_utf8_lossy(&output.stdout).to_string())
 }
 
-/// List files in a remote directory using a structured script output
+/// List files in a remote directory over SFTP
 pub fn list_remote_dir(
-    host: &str,
-    user: &str,
-    port: Option<u16>,
+    target: &SshTarget,
     path: &str,
 ) -> Result<Vec<RemoteFile>, String> {
-    // Use a remote script that outputs structured data (pipe-delimited)
-    // Format per line: name|path|type|size|modified|permissions
-    let script = format!(
-        r#"
-DIR="{path}"
-if [ ! -d "$DIR" ]; then
-    echo "ERROR:NOT_A_DIRECTORY"
-    exit 1
-fi
-cd "$DIR" 2>/dev/null || {{ echo "ERROR:CANNOT_ACCESS"; exit 1; }}
-for f in .* *; do
-    [ "$f" = "." ] && continue
-    if [ -e "$f" ] || [ -L "$f" ]; then
-        FULLPATH="$DIR/$f"
-        if [ -d "$f" ]; then
-            TYPE="dir"
-        else
-            TYPE="file"
-        fi
-        SIZE=$(stat -c '%s' "$f" 2>/dev/null || stat -f '%z' "$f" 2>/dev/null || echo "0")
-        MOD=$(stat -c '%Y' "$f" 2>/dev/null || stat -f '%m' "$f" 2>/dev/null || echo "0")
-        PERM=$(stat -c '%A' "$f" 2>/dev/null || ls -ld "$f" 2>/dev/null | awk '{{print $1}}' || echo "----------")
-        echo "ENTRY:$f|$FULLPATH|$TYPE|$SIZE|$MOD|$PERM"
-    fi
-done
-echo "DONE"
-"#,
-        path = path
-    );
-
-    let output = ssh_exec(user, host, port, &script)?;
+    audit::track(AuditAction::SftpList, target, Some(path), || {
+        list_dir(target, path)
+    })
+}
 
-    let mut files = Vec::new();
+fn list_dir(
+    target: &SshTarget,
+    path: &str,
+) -> Result<Vec<RemoteFile>, String> {
+    let mut sftp = SftpSession::connect(target)?;
+    if !sftp.stat(path)?.is_dir() {
+        return Err(format!("Not a directory: {}", path));
+    }
 
-    for line in output.lines() {
-        if line.starts_with("ERROR:") {
-            return Err(line.strip_prefix("ERROR:").unwrap_or(line).to_string());
+    let mut files = Vec::new();
+    for entry in sftp.read_dir(path)? {
+        if entry.name == "." {
+            continue;
         }
-        if let Some(entry) = line.strip_prefix("ENTRY:") {
-            let parts: Vec<&str> = entry.splitn(6, '|').collect();
-            if parts.len() == 6 {
-                let size = parts[3].trim().parse::<u64>().unwrap_or(0);
-                let timestamp = parts[4].trim().parse::<i64>().unwrap_or(0);
-
-                // Convert epoch timestamp to human-readable
-                let modified = if timestamp > 0 {
-                    // Simple date formatting from epoch
-                    format_epoch(timestamp)
-                } else {
-                    "-".to_string()
-                };
-
-                files.push(RemoteFile {
-                    name: parts[0].to_string(),
-                    path: parts[1].to_string(),
-                    is_dir: parts[2] == "dir",
-                    size,
-                    modified,
-                    permissions: parts[5].trim().to_string(),
-                });
-            }
+        let mut file = RemoteFile::new(&entry.name, join_remote(path, &entry.name), &entry.attrs);
+        if let Some((owner, group)) = longname_owner(&entry.longname) {
+            file.owner = Some(owner);
+            file.group = Some(group);
         }
+        file.resolve_link(&mut sftp);
+        files.push(file);
     }
 
     // Sort: directories first, then by name
@@ -161,165 +216,397 @@ echo "DONE"
     Ok(files)
 }
 
-/// Upload a local file to remote via scp
+/// Details of a single remote path, without following a final symlink
+pub fn stat_remote(target: &SshTarget, path: &str) -> Result<RemoteFile, String> {
+    let mut sftp = SftpSession::connect(target)?;
+    let attrs = sftp.lstat(path)?;
+    let name = path.trim_end_matches('/').rsplit('/').next().unwrap_or(path);
+    let mut file = RemoteFile::new(name, path.to_string(), &attrs);
+    file.resolve_link(&mut sftp);
+    Ok(file)
+}
+
+/// Suffix of a file being transferred; renamed away once it is complete
+pub const PARTIAL_SUFFIX: &str = ".part";
+
+/// Upload a local file to remote over SFTP. The data goes to
+/// `<remote_path>.part` first. `resume_at` is how many bytes an earlier
+/// attempt had acknowledged; its partial file is continued from there when
+/// it holds at least that much, and started over otherwise. `progress` gets
+/// the bytes done and the total; returning false stops the transfer and
+/// keeps the partial file.
 pub fn upload_file(
-    host: &str,
-    user: &str,
-    port: Option<u16>,
+    target: &SshTarget,
     local_path: &str,
     remote_path: &str,
-    app: &tauri::AppHandle,
+    resume_at: u64,
+    progress: impl FnMut(u64, u64) -> bool,
 ) -> Result<(), String> {
-    let filename = std::path::Path::new(local_path)
-        .file_name()
-        .map(|n| n.to_string_lossy().to_string())
-        .unwrap_or_else(|| local_path.to_string());
-
-    log::info!("Uploading {} to {}:{}", local_path, host, remote_path);
-
-    let _ = app.emit(
-        "file-transfer-progress",
-        serde_json::json!({
-            "filename": filename,
-            "percent": 0,
-            "direction": "upload",
-        }),
-    );
-
-    let mut scp_args = vec![
-        "-o".to_string(),
-        "StrictHostKeyChecking=accept-new".to_string(),
-        "-o".to_string(),
-        "ConnectTimeout=10".to_string(),
-    ];
-    if let Some(p) = port {
-        scp_args.push("-P".to_string());
-        scp_args.push(p.to_string());
-    }
-    scp_args.push(local_path.to_string());
-    scp_args.push(format!("{}@{}:{}", user, host, remote_path));
-
-    let output = Command::new(scp_bin())
-        .args(&scp_args)
-        .stdin(Stdio::null())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .output()
-        .map_err(|e| format!("SCP failed: {}", e))?;
+    audit::track(AuditAction::SftpUpload, target, Some(remote_path), || {
+        let mut sftp = SftpSession::connect(target)?;
+        upload(&mut sftp, local_path, remote_path, resume_at, progress)
+    })
+}
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("Upload failed: {}", stderr.trim()));
+pub(crate) fn upload(
+    sftp: &mut SftpSession,
+    local_path: &str,
+    remote_path: &str,
+    resume_at: u64,
+    mut progress: impl FnMut(u64, u64) -> bool,
+) -> Result<(), String> {
+    log::info!("Uploading {} to {}", local_path, remote_path);
+
+    let mut file = std::fs::File::open(local_path)
+        .map_err(|e| format!("Cannot open {}: {}", local_path, e))?;
+    let meta = file
+        .metadata()
+        .map_err(|e| format!("Cannot read {}: {}", local_path, e))?;
+    let total = meta.len();
+    let partial = format!("{}{}", remote_path, PARTIAL_SUFFIX);
+
+    let offset = match resume_at {
+        0 => 0,
+        _ => sftp
+            .try_stat(&partial)?
+            .and_then(|a| a.size)
+            .filter(|&s| s >= resume_at && resume_at <= total)
+            .map_or(0, |_| resume_at),
+    };
+    let mut flags = sftp::OPEN_WRITE | sftp::OPEN_CREATE;
+    if offset == 0 {
+        flags |= sftp::OPEN_TRUNCATE;
+    } else {
+        log::info!("Resuming upload of {} at byte {}", local_path, offset);
+        file.seek(SeekFrom::Start(offset))
+            .map_err(|e| format!("Cannot seek {}: {}", local_path, e))?;
     }
 
-    let _ = app.emit(
-        "file-transfer-progress",
-        serde_json::json!({
-            "filename": filename,
-            "percent": 100,
-            "direction": "upload",
-        }),
-    );
+    let handle = sftp.open(&partial, flags, &FileAttrs::with_mode(local_mode(&meta)))?;
+    progress(offset, total);
+    let written = sftp.write_from(&handle, offset, &mut file, |done| progress(done, total));
+    sftp.close(handle)?;
+    written.map_err(|e| format!("Upload failed: {}", e))?;
+    sftp.rename(&partial, remote_path)?;
 
-    log::info!("Upload complete: {}", filename);
+    log::info!("Upload complete: {}", remote_path);
     Ok(())
 }
 
-/// Download a remote file to local via scp
+/// Download a remote file to local over SFTP, through `<local_path>.part`
+/// like `upload_file`
 pub fn download_file(
-    host: &str,
-    user: &str,
-    port: Option<u16>,
+    target: &SshTarget,
     remote_path: &str,
     local_path: &str,
-    app: &tauri::AppHandle,
+    resume_at: u64,
+    progress: impl FnMut(u64, u64) -> bool,
 ) -> Result<(), String> {
-    let filename = std::path::Path::new(remote_path)
-        .file_name()
-        .map(|n| n.to_string_lossy().to_string())
-        .unwrap_or_else(|| remote_path.to_string());
-
-    log::info!("Downloading {}:{} to {}", host, remote_path, local_path);
-
-    let _ = app.emit(
-        "file-transfer-progress",
-        serde_json::json!({
-            "filename": filename,
-            "percent": 0,
-            "direction": "download",
-        }),
-    );
-
-    let mut scp_args = vec![
-        "-o".to_string(),
-        "StrictHostKeyChecking=accept-new".to_string(),
-        "-o".to_string(),
-        "ConnectTimeout=10".to_string(),
-    ];
-    if let Some(p) = port {
-        scp_args.push("-P".to_string());
-        scp_args.push(p.to_string());
-    }
-    scp_args.push(format!("{}@{}:{}", user, host, remote_path));
-    scp_args.push(local_path.to_string());
-
-    let output = Command::new(scp_bin())
-        .args(&scp_args)
-        .stdin(Stdio::null())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .output()
-        .map_err(|e| format!("SCP failed: {}", e))?;
+    audit::track(AuditAction::SftpDownload, target, Some(remote_path), || {
+        let mut sftp = SftpSession::connect(target)?;
+        download(&mut sftp, remote_path, local_path, resume_at, progress)
+    })
+}
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("Download failed: {}", stderr.trim()));
-    }
+pub(crate) fn download(
+    sftp: &mut SftpSession,
+    remote_path: &str,
+    local_path: &str,
+    resume_at: u64,
+    mut progress: impl FnMut(u64, u64) -> bool,
+) -> Result<(), String> {
+    log::info!("Downloading {} to {}", remote_path, local_path);
+
+    let handle = sftp.open(remote_path, sftp::OPEN_READ, &FileAttrs::default())?;
+    let read = sftp.fstat(&handle).and_then(|attrs| {
+        let total = attrs.size.unwrap_or(0);
+        let partial = format!("{}{}", local_path, PARTIAL_SUFFIX);
+        let offset = match resume_at {
+            0 => 0,
+            _ => std::fs::metadata(&partial)
+                .ok()
+                .filter(|m| m.len() >= resume_at && resume_at <= total)
+                .map_or(0, |_| resume_at),
+        };
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(offset == 0)
+            .open(&partial)
+            .map_err(|e| format!("Cannot create {}: {}", partial, e))?;
+        if offset > 0 {
+            log::info!("Resuming download of {} at byte {}", remote_path, offset);
+            file.seek(SeekFrom::Start(offset))
+                .map_err(|e| format!("Cannot seek {}: {}", partial, e))?;
+            file.set_len(offset).ok();
+        }
 
-    let _ = app.emit(
-        "file-transfer-progress",
-        serde_json::json!({
-            "filename": filename,
-            "percent": 100,
-            "direction": "download",
-        }),
-    );
+        progress(offset, total);
+        let mut out = std::io::BufWriter::new(&mut file);
+        sftp.read_to(&handle, offset, &mut out, |done| progress(done, total))?;
+        out.flush().map_err(|e| format!("Write failed: {}", e))?;
+        drop(out);
+        Ok(partial)
+    });
+    sftp.close(handle)?;
+    let partial = read.map_err(|e| format!("Download failed: {}", e))?;
+    std::fs::rename(&partial, local_path)
+        .map_err(|e| format!("Cannot move {} into place: {}", partial, e))?;
 
-    log::info!("Download complete: {}", filename);
+    log::info!("Download complete: {}", local_path);
     Ok(())
 }
 
+/// Permission bits for a new remote copy of a local file
+#[cfg(unix)]
+pub(crate) fn local_mode(meta: &std::fs::Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    meta.permissions().mode() & 0o7777
+}
+
+#[cfg(not(unix))]
+pub(crate) fn local_mode(meta: &std::fs::Metadata) -> u32 {
+    if meta.permissions().readonly() { 0o444 } else { 0o644 }
+}
+
 /// Create a directory on the remote host
 pub fn make_remote_dir(
-    host: &str,
-    user: &str,
-    port: Option<u16>,
+    target: &SshTarget,
+    path: &str,
+) -> Result<(), String> {
+    audit::track(AuditAction::SftpMkdir, target, Some(path), || {
+        make_dir(target, path)
+    })
+}
+
+fn make_dir(
+    target: &SshTarget,
     path: &str,
 ) -> Result<(), String> {
-    let cmd = format!("mkdir -p '{}'", path.replace('\'', "'\\''"));
-    ssh_exec(user, host, port, &cmd)?;
+    let mut sftp = SftpSession::connect(target)?;
+    make_dir_all(&mut sftp, path)
+}
+
+/// Create `path` and any missing parents, like `mkdir -p`
+pub(crate) fn make_dir_all(sftp: &mut SftpSession, path: &str) -> Result<(), String> {
+    let mut current = if path.starts_with('/') { "/".to_string() } else { String::new() };
+    for part in path.split('/').filter(|p| !p.is_empty()) {
+        current = if current.is_empty() { part.to_string() } else { join_remote(&current, part) };
+        match sftp.try_stat(&current)? {
+            Some(attrs) if attrs.is_dir() => {}
+            Some(_) => return Err(format!("Not a directory: {}", current)),
+            None => sftp.mkdir(&current, None)?,
+        }
+    }
     Ok(())
 }
 
 /// Delete a file or directory on the remote host
 pub fn delete_remote(
-    host: &str,
-    user: &str,
-    port: Option<u16>,
+    target: &SshTarget,
     path: &str,
 ) -> Result<(), String> {
+    audit::track(AuditAction::SftpDelete, target, Some(path), || {
+        delete(target, path)
+    })
+}
+
+fn delete(
+    target: &SshTarget,
+    path: &str,
+) -> Result<(), String> {
+    let clean = deletable(path)?;
+    let mut sftp = SftpSession::connect(target)?;
+    remove_all(&mut sftp, clean)
+}
+
+/// `path` without trailing slashes, unless it is one of the critical paths
+/// that are never deleted
+fn deletable(path: &str) -> Result<&str, String> {
     // Safety: refuse to delete root-level critical paths
     let dangerous = ["/", "/bin", "/boot", "/dev", "/etc", "/home", "/lib",
         "/lib64", "/opt", "/proc", "/root", "/run", "/sbin", "/srv",
         "/sys", "/tmp", "/usr", "/var"];
     let clean = path.trim_end_matches('/');
-    if dangerous.contains(&clean) {
+    if dangerous.contains(&clean) || clean.is_empty() {
         return Err(format!("Refusing to delete critical path: {}", path));
     }
+    Ok(clean)
+}
 
-    let cmd = format!("rm -rf '{}'", path.replace('\'', "'\\''"));
-    ssh_exec(user, host, port, &cmd)?;
-    Ok(())
+/// Remove a file, symlink or whole directory tree. Symlinks to directories
+/// are removed, not followed.
+pub(crate) fn remove_all(sftp: &mut SftpSession, path: &str) -> Result<(), String> {
+    let Some(attrs) = sftp.try_lstat(path)? else {
+        return Err(format!("No such file or directory: {}", path));
+    };
+    if !attrs.is_dir() {
+        return sftp.remove(path);
+    }
+    for entry in sftp.read_dir(path)? {
+        if entry.name != "." && entry.name != ".." {
+            remove_all(sftp, &join_remote(path, &entry.name))?;
+        }
+    }
+    sftp.rmdir(path)
+}
+
+/// A change to remote files, for `remote_op` and `remote_ops`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum RemoteOp {
+    /// Rename or move; into `to` if that is an existing directory
+    Rename { from: String, to: String },
+    /// Copy on the server, recursively for a directory; into `to` if that is
+    /// an existing directory
+    Copy { from: String, to: String },
+    /// Set the permission bits, e.g. 0o755
+    Chmod { path: String, mode: u32 },
+    /// Set the owner and group ids; one left out stays as it is
+    Chown { path: String, uid: Option<u32>, gid: Option<u32> },
+    /// Create `link` pointing at `target`
+    Symlink { link: String, target: String },
+    /// Create an empty file, or set an existing one's times to now
+    Touch { path: String },
+    Mkdir { path: String },
+    Delete { path: String },
+}
+
+impl RemoteOp {
+    fn audit_action(&self) -> AuditAction {
+        match self {
+            Self::Rename { .. } => AuditAction::SftpRename,
+            Self::Copy { .. } => AuditAction::SftpCopy,
+            Self::Chmod { .. } => AuditAction::SftpChmod,
+            Self::Chown { .. } => AuditAction::SftpChown,
+            Self::Symlink { .. } => AuditAction::SftpSymlink,
+            Self::Touch { .. } => AuditAction::SftpTouch,
+            Self::Mkdir { .. } => AuditAction::SftpMkdir,
+            Self::Delete { .. } => AuditAction::SftpDelete,
+        }
+    }
+
+    /// The path operated on
+    pub fn path(&self) -> &str {
+        match self {
+            Self::Rename { from, .. } | Self::Copy { from, .. } => from,
+            Self::Symlink { link, .. } => link,
+            Self::Chmod { path, .. }
+            | Self::Chown { path, .. }
+            | Self::Touch { path }
+            | Self::Mkdir { path }
+            | Self::Delete { path } => path,
+        }
+    }
+}
+
+/// Outcome of one operation of a batch
+#[derive(Debug, Clone, Serialize)]
+pub struct OpResult {
+    pub path: String,
+    pub ok: bool,
+    pub error: Option<String>,
+}
+
+/// Apply one operation
+pub fn remote_op(target: &SshTarget, op: &RemoteOp) -> Result<(), String> {
+    audit::track(op.audit_action(), target, Some(op.path()), || {
+        let mut sftp = SftpSession::connect(target)?;
+        apply(&mut sftp, target, op)
+    })
+}
+
+/// Apply `ops` in order over one connection, e.g. for a multi-selection.
+/// Each is audited and reported on its own and a failure doesn't stop the
+/// rest; only failing to connect fails the whole batch.
+pub fn remote_ops(target: &SshTarget, ops: &[RemoteOp]) -> Result<Vec<OpResult>, String> {
+    let mut sftp = SftpSession::connect(target)?;
+    Ok(ops
+        .iter()
+        .map(|op| {
+            let result = audit::track(op.audit_action(), target, Some(op.path()), || {
+                apply(&mut sftp, target, op)
+            });
+            OpResult { path: op.path().to_string(), ok: result.is_ok(), error: result.err() }
+        })
+        .collect())
+}
+
+fn apply(sftp: &mut SftpSession, target: &SshTarget, op: &RemoteOp) -> Result<(), String> {
+    match op {
+        RemoteOp::Rename { from, to } => {
+            let to = destination(sftp, from, to)?;
+            sftp.rename(from, &to)
+        }
+        RemoteOp::Copy { from, to } => {
+            let to = destination(sftp, from, to)?;
+            copy(sftp, target, from, &to)
+        }
+        RemoteOp::Chmod { path, mode } => sftp.setstat(path, &FileAttrs::with_mode(*mode)),
+        RemoteOp::Chown { path, uid, gid } => {
+            // SETSTAT takes both ids or neither
+            let current = sftp.stat(path)?;
+            let (Some(uid), Some(gid)) = (uid.or(current.uid), gid.or(current.gid)) else {
+                return Err(format!("Server did not report the owner of {}", path));
+            };
+            sftp.setstat(path, &FileAttrs { uid: Some(uid), gid: Some(gid), ..FileAttrs::default() })
+        }
+        RemoteOp::Symlink { link, target } => sftp.symlink(link, target),
+        RemoteOp::Touch { path } => match sftp.try_stat(path)? {
+            Some(_) => {
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.as_secs() as u32)
+                    .unwrap_or(0);
+                sftp.setstat(path, &FileAttrs { atime: Some(now), mtime: Some(now), ..FileAttrs::default() })
+            }
+            None => {
+                let handle = sftp.open(path, sftp::OPEN_WRITE | sftp::OPEN_CREATE, &FileAttrs::default())?;
+                sftp.close(handle)
+            }
+        },
+        RemoteOp::Mkdir { path } => make_dir_all(sftp, path),
+        RemoteOp::Delete { path } => remove_all(sftp, deletable(path)?),
+    }
+}
+
+/// Where `from` ends up for a move or copy to `to`: inside `to` when that is
+/// an existing directory, like `mv` and `cp`
+fn destination(sftp: &mut SftpSession, from: &str, to: &str) -> Result<String, String> {
+    match sftp.try_stat(to)? {
+        Some(attrs) if attrs.is_dir() => {
+            let name = from.trim_end_matches('/').rsplit('/').next().unwrap_or(from);
+            Ok(join_remote(to, name))
+        }
+        _ => Ok(to.to_string()),
+    }
+}
+
+/// Copy on the server: a regular file with the `copy-data` extension when
+/// available, anything else with `cp`
+fn copy(sftp: &mut SftpSession, target: &SshTarget, from: &str, to: &str) -> Result<(), String> {
+    let attrs = sftp.lstat(from)?;
+    if sftp.try_stat(to)?.is_some() && sftp.realpath(to)? == sftp.realpath(from)? {
+        return Err(format!("{} and {} are the same file", from, to));
+    }
+    if !attrs.is_file() || !sftp.has_extension("copy-data") {
+        // Relative paths resolve against the login directory, as over SFTP
+        ssh_exec(target, &format!("cp -pR -- {} {}", quote(from), quote(to)))?;
+        return Ok(());
+    }
+    let source = sftp.open(from, sftp::OPEN_READ, &FileAttrs::default())?;
+    let mode = attrs.permissions.map(|p| p & 0o7777).unwrap_or(0o644);
+    let flags = sftp::OPEN_WRITE | sftp::OPEN_CREATE | sftp::OPEN_TRUNCATE;
+    let copied = sftp.open(to, flags, &FileAttrs::with_mode(mode)).and_then(|dest| {
+        let copied = sftp.copy_data(&source, &dest);
+        sftp.close(dest)?;
+        copied
+    });
+    sftp.close(source)?;
+    copied?;
+    // Keep the times, like `cp -p`
+    let times = FileAttrs { atime: attrs.atime, mtime: attrs.mtime, ..FileAttrs::default() };
+    sftp.setstat(to, &times)
 }
 
 /// Format an epoch timestamp to a human-readable string
@@ -361,3 +648,21 @@ fn format_epoch(epoch: i64) -> String {
 
     format!("{} {:2} {:02}:{:02}", months[m], day, hours, minutes)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remote_op_json_and_delete_guard() {
+        let op: RemoteOp = serde_json::from_str(r#"{"op":"chown","path":"data","uid":1000}"#).unwrap();
+        assert_eq!(op, RemoteOp::Chown { path: "data".into(), uid: Some(1000), gid: None });
+        let op: RemoteOp =
+            serde_json::from_str(r#"{"op":"symlink","link":"latest","target":"run-3"}"#).unwrap();
+        assert_eq!(op.path(), "latest");
+
+        assert_eq!(deletable("/data/run-1/"), Ok("/data/run-1"));
+        assert!(deletable("/etc/").is_err());
+        assert!(deletable("/").is_err());
+    }
+}
@@ -0,0 +1,644 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{Emitter, Manager};
+use tokio::sync::oneshot;
+
+use super::ops::{self, PARTIAL_SUFFIX};
+use super::sftp::SftpSession;
+use super::tree::epoch_secs;
+use crate::config::store;
+use crate::ssh_target::SshTarget;
+
+/// Attempts per job before it is marked failed; each resumes where the
+/// previous one stopped
+const MAX_ATTEMPTS: u32 = 3;
+pub const MAX_PARALLEL_LIMIT: usize = 8;
+const DEFAULT_PARALLEL: usize = 2;
+/// Minimum time between progress events for one job
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+/// How often a running job's progress is written to the queue file
+const SAVE_INTERVAL: Duration = Duration::from_secs(5);
+
+const RUN: u8 = 0;
+const PAUSE: u8 = 1;
+const CANCEL: u8 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Upload,
+    Download,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Paused,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl JobStatus {
+    pub fn is_finished(self) -> bool {
+        matches!(self, Self::Completed | Self::Failed | Self::Cancelled)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferJob {
+    pub id: String,
+    pub direction: Direction,
+    pub target: SshTarget,
+    pub local_path: String,
+    pub remote_path: String,
+    pub status: JobStatus,
+    /// Bytes done, as of the last progress report
+    pub transferred: u64,
+    /// File size; 0 until the job has started
+    pub total: u64,
+    pub error: Option<String>,
+    /// Failed attempts since the job was last (re)started
+    pub attempts: u32,
+    /// Size and mtime (Unix seconds) of the source when the first attempt
+    /// started. A later attempt only continues the partial file while the
+    /// source still matches.
+    #[serde(default)]
+    pub source_size: Option<u64>,
+    #[serde(default)]
+    pub source_mtime: Option<u64>,
+    pub created_ms: u64,
+    pub finished_ms: Option<u64>,
+}
+
+impl TransferJob {
+    fn filename(&self) -> String {
+        let path = match self.direction {
+            Direction::Upload => &self.local_path,
+            Direction::Download => &self.remote_path,
+        };
+        std::path::Path::new(path)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.clone())
+    }
+
+    /// Where to continue, given the source as it is now: the acknowledged
+    /// bytes if the source is unchanged, otherwise the start
+    fn resume_offset(&self, size: u64, mtime: Option<u64>) -> u64 {
+        let unchanged = self.source_size == Some(size) && self.source_mtime == mtime;
+        match unchanged && self.transferred <= size {
+            true => self.transferred,
+            false => 0,
+        }
+    }
+
+    /// Size and mtime of the file being copied
+    fn stat_source(&self) -> Result<(u64, Option<u64>), String> {
+        match self.direction {
+            Direction::Upload => std::fs::metadata(&self.local_path)
+                .map(|meta| (meta.len(), epoch_secs(meta.modified())))
+                .map_err(|e| format!("Cannot read {}: {}", self.local_path, e)),
+            Direction::Download => {
+                ops::stat_remote(&self.target, &self.remote_path).map(|file| (file.size, file.mtime))
+            }
+        }
+    }
+}
+
+/// Payload of `file-transfer-progress`
+#[derive(Debug, Clone, Serialize)]
+pub struct TransferProgress {
+    pub id: String,
+    pub filename: String,
+    pub direction: Direction,
+    pub status: JobStatus,
+    pub bytes: u64,
+    pub total: u64,
+    /// Only a completed job reports 100
+    pub percent: u64,
+    pub bytes_per_sec: u64,
+    pub eta_secs: Option<u64>,
+}
+
+/// What is kept in transfers.json
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct QueueFile {
+    #[serde(default)]
+    max_parallel: Option<usize>,
+    #[serde(default)]
+    jobs: Vec<TransferJob>,
+}
+
+#[derive(Default)]
+struct Queue {
+    max_parallel: usize,
+    jobs: Vec<TransferJob>,
+    /// Run/pause/cancel flags of running jobs, checked between chunks
+    controls: HashMap<String, Arc<AtomicU8>>,
+    /// Callers waiting for a job to finish
+    waiters: HashMap<String, Vec<oneshot::Sender<Result<(), String>>>>,
+}
+
+impl Queue {
+    fn job_mut(&mut self, id: &str) -> Result<&mut TransferJob, String> {
+        self.jobs
+            .iter_mut()
+            .find(|j| j.id == id)
+            .ok_or_else(|| format!("Transfer '{}' not found", id))
+    }
+
+    fn notify(&mut self, id: &str, result: Result<(), String>) {
+        for waiter in self.waiters.remove(id).unwrap_or_default() {
+            waiter.send(result.clone()).ok();
+        }
+    }
+}
+
+/// Upload and download jobs, run a few at a time on worker threads. The
+/// queue survives restarts: jobs that were running are queued again and
+/// resume from their partial file.
+pub struct TransferManager {
+    queue: Mutex<Queue>,
+    path: PathBuf,
+}
+
+pub fn queue_path() -> PathBuf {
+    crate::config::config_path()
+        .parent()
+        .map(|p| p.join("transfers.json"))
+        .unwrap_or_else(|| PathBuf::from("transfers.json"))
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+impl TransferManager {
+    pub fn load(path: PathBuf) -> Self {
+        let file: QueueFile = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|text| match serde_json::from_str(&text) {
+                Ok(file) => Some(file),
+                Err(e) => {
+                    log::warn!("Ignoring unreadable {}: {}", path.display(), e);
+                    None
+                }
+            })
+            .unwrap_or_default();
+        let mut jobs = file.jobs;
+        for job in jobs.iter_mut().filter(|j| j.status == JobStatus::Running) {
+            job.status = JobStatus::Queued;
+        }
+        let queue = Queue {
+            max_parallel: file.max_parallel.unwrap_or(DEFAULT_PARALLEL).clamp(1, MAX_PARALLEL_LIMIT),
+            jobs,
+            ..Queue::default()
+        };
+        Self { queue: Mutex::new(queue), path }
+    }
+
+    fn save(&self, queue: &Queue) {
+        let file = QueueFile { max_parallel: Some(queue.max_parallel), jobs: queue.jobs.clone() };
+        let written = serde_json::to_string_pretty(&file)
+            .map_err(|e| e.to_string())
+            .and_then(|json| store::write_atomic(&self.path, json.as_bytes()));
+        if let Err(e) = written {
+            log::warn!("Failed to save transfer queue: {}", e);
+        }
+    }
+
+    pub fn list(&self) -> Vec<TransferJob> {
+        self.queue.lock().unwrap().jobs.clone()
+    }
+
+    pub fn max_parallel(&self) -> usize {
+        self.queue.lock().unwrap().max_parallel
+    }
+
+    pub fn set_max_parallel(&self, n: usize, app: &tauri::AppHandle) -> Result<(), String> {
+        if !(1..=MAX_PARALLEL_LIMIT).contains(&n) {
+            return Err(format!("Parallel transfers must be between 1 and {}", MAX_PARALLEL_LIMIT));
+        }
+        {
+            let mut queue = self.queue.lock().unwrap();
+            queue.max_parallel = n;
+            self.save(&queue);
+        }
+        self.schedule(app);
+        Ok(())
+    }
+
+    /// Add a job; it starts when a slot is free
+    pub fn enqueue(
+        &self,
+        direction: Direction,
+        target: SshTarget,
+        local_path: String,
+        remote_path: String,
+        app: &tauri::AppHandle,
+    ) -> TransferJob {
+        let job = self.add(direction, target, local_path, remote_path);
+        self.schedule(app);
+        job
+    }
+
+    /// Add a job and wait for it to complete, fail or be cancelled
+    pub async fn run(
+        &self,
+        direction: Direction,
+        target: SshTarget,
+        local_path: String,
+        remote_path: String,
+        app: &tauri::AppHandle,
+    ) -> Result<(), String> {
+        let job = self.add(direction, target, local_path, remote_path);
+        let (tx, rx) = oneshot::channel();
+        self.queue.lock().unwrap().waiters.entry(job.id).or_default().push(tx);
+        self.schedule(app);
+        rx.await.unwrap_or_else(|_| Err("Transfer was removed".to_string()))
+    }
+
+    fn add(&self, direction: Direction, target: SshTarget, local_path: String, remote_path: String) -> TransferJob {
+        let job = TransferJob {
+            id: uuid::Uuid::new_v4().to_string(),
+            direction,
+            target,
+            local_path,
+            remote_path,
+            status: JobStatus::Queued,
+            transferred: 0,
+            total: 0,
+            error: None,
+            attempts: 0,
+            source_size: None,
+            source_mtime: None,
+            created_ms: now_ms(),
+            finished_ms: None,
+        };
+        let mut queue = self.queue.lock().unwrap();
+        queue.jobs.push(job.clone());
+        self.save(&queue);
+        job
+    }
+
+    /// Stop a queued or running job; it keeps its partial file and can be
+    /// resumed
+    pub fn pause(&self, id: &str, app: &tauri::AppHandle) -> Result<(), String> {
+        let mut queue = self.queue.lock().unwrap();
+        let job = queue.job_mut(id)?;
+        match job.status {
+            JobStatus::Queued => job.status = JobStatus::Paused,
+            // The worker sees the flag after the current chunk and sets the status
+            JobStatus::Running => {
+                if let Some(control) = queue.controls.get(id) {
+                    control.store(PAUSE, Ordering::SeqCst);
+                }
+                return Ok(());
+            }
+            JobStatus::Paused => return Ok(()),
+            _ => return Err("Transfer has already finished".to_string()),
+        }
+        let job = job.clone();
+        self.save(&queue);
+        emit(app, &job, 0.0);
+        Ok(())
+    }
+
+    /// Queue a paused or failed job again, continuing from its partial file
+    pub fn resume(&self, id: &str, app: &tauri::AppHandle) -> Result<(), String> {
+        {
+            let mut queue = self.queue.lock().unwrap();
+            let job = queue.job_mut(id)?;
+            if !matches!(job.status, JobStatus::Paused | JobStatus::Failed) {
+                return Err("Only paused or failed transfers can be resumed".to_string());
+            }
+            job.status = JobStatus::Queued;
+            job.attempts = 0;
+            job.error = None;
+            job.finished_ms = None;
+            let job = job.clone();
+            self.save(&queue);
+            emit(app, &job, 0.0);
+        }
+        self.schedule(app);
+        Ok(())
+    }
+
+    /// Stop a job for good and remove its partial file
+    pub fn cancel(&self, id: &str, app: &tauri::AppHandle) -> Result<(), String> {
+        let mut queue = self.queue.lock().unwrap();
+        let job = queue.job_mut(id)?;
+        match job.status {
+            JobStatus::Running => {
+                if let Some(control) = queue.controls.get(id) {
+                    control.store(CANCEL, Ordering::SeqCst);
+                }
+                return Ok(());
+            }
+            status if status.is_finished() => return Err("Transfer has already finished".to_string()),
+            _ => {}
+        }
+        job.status = JobStatus::Cancelled;
+        job.finished_ms = Some(now_ms());
+        let job = job.clone();
+        queue.notify(id, Err("Transfer cancelled".to_string()));
+        self.save(&queue);
+        drop(queue);
+        emit(app, &job, 0.0);
+        thread::spawn(move || remove_partial(&job));
+        Ok(())
+    }
+
+    /// Forget a job that is not running
+    pub fn remove(&self, id: &str) -> Result<(), String> {
+        let mut queue = self.queue.lock().unwrap();
+        if queue.job_mut(id)?.status == JobStatus::Running {
+            return Err("Cancel the transfer before removing it".to_string());
+        }
+        queue.jobs.retain(|j| j.id != id);
+        queue.notify(id, Err("Transfer was removed".to_string()));
+        self.save(&queue);
+        Ok(())
+    }
+
+    /// Forget completed, failed and cancelled jobs
+    pub fn clear_finished(&self) {
+        let mut queue = self.queue.lock().unwrap();
+        queue.jobs.retain(|j| !j.status.is_finished());
+        self.save(&queue);
+    }
+
+    /// Start queued jobs while there are free slots
+    pub fn schedule(&self, app: &tauri::AppHandle) {
+        let mut queue = self.queue.lock().unwrap();
+        let mut running = queue.jobs.iter().filter(|j| j.status == JobStatus::Running).count();
+        let max_parallel = queue.max_parallel;
+        let mut started = Vec::new();
+        for job in queue.jobs.iter_mut() {
+            if running >= max_parallel {
+                break;
+            }
+            if job.status == JobStatus::Queued {
+                job.status = JobStatus::Running;
+                running += 1;
+                started.push(job.clone());
+            }
+        }
+        if started.is_empty() {
+            return;
+        }
+        for job in started {
+            let control = Arc::new(AtomicU8::new(RUN));
+            queue.controls.insert(job.id.clone(), control.clone());
+            emit(app, &job, 0.0);
+            let app = app.clone();
+            thread::spawn(move || work(app, job, control));
+        }
+        self.save(&queue);
+    }
+
+    /// Record progress of a running job; saved every `SAVE_INTERVAL`
+    fn progress(&self, id: &str, done: u64, total: u64, save: bool) {
+        let mut queue = self.queue.lock().unwrap();
+        if let Ok(job) = queue.job_mut(id) {
+            job.transferred = done;
+            job.total = total;
+        }
+        if save {
+            self.save(&queue);
+        }
+    }
+
+    /// Remember the source a running job is copying
+    fn set_source(&self, id: &str, size: u64, mtime: Option<u64>) {
+        let mut queue = self.queue.lock().unwrap();
+        if let Ok(job) = queue.job_mut(id) {
+            job.source_size = Some(size);
+            job.source_mtime = mtime;
+        }
+        self.save(&queue);
+    }
+
+    /// A worker is done with a job
+    fn finish(&self, job: &TransferJob, result: Result<(), String>, control: u8, app: &tauri::AppHandle) {
+        let updated = {
+            let mut queue = self.queue.lock().unwrap();
+            queue.controls.remove(&job.id);
+            let Ok(slot) = queue.job_mut(&job.id) else { return };
+            match (&result, control) {
+                (Ok(()), _) => {
+                    slot.status = JobStatus::Completed;
+                    slot.transferred = slot.total;
+                    slot.error = None;
+                    slot.finished_ms = Some(now_ms());
+                }
+                (Err(_), PAUSE) => slot.status = JobStatus::Paused,
+                (Err(_), CANCEL) => {
+                    slot.status = JobStatus::Cancelled;
+                    slot.finished_ms = Some(now_ms());
+                }
+                (Err(e), _) => {
+                    slot.attempts += 1;
+                    slot.error = Some(e.clone());
+                    if slot.attempts < MAX_ATTEMPTS {
+                        log::warn!("Transfer {} failed (attempt {}), retrying: {}", slot.id, slot.attempts, e);
+                        slot.status = JobStatus::Queued;
+                    } else {
+                        log::error!("Transfer {} failed: {}", slot.id, e);
+                        slot.status = JobStatus::Failed;
+                        slot.finished_ms = Some(now_ms());
+                    }
+                }
+            }
+            let updated = slot.clone();
+            match updated.status {
+                JobStatus::Completed => queue.notify(&job.id, Ok(())),
+                JobStatus::Failed => queue.notify(&job.id, result.clone()),
+                JobStatus::Cancelled => queue.notify(&job.id, Err("Transfer cancelled".to_string())),
+                _ => {}
+            }
+            self.save(&queue);
+            updated
+        };
+        emit(app, &updated, 0.0);
+        if updated.status == JobStatus::Cancelled {
+            remove_partial(&updated);
+        }
+        self.schedule(app);
+    }
+}
+
+fn emit(app: &tauri::AppHandle, job: &TransferJob, bytes_per_sec: f64) {
+    let percent = match job.status {
+        JobStatus::Completed => 100,
+        _ => (job.transferred.min(job.total) * 100).checked_div(job.total).unwrap_or(0).min(99),
+    };
+    let eta_secs = (bytes_per_sec > 0.0 && job.status == JobStatus::Running)
+        .then(|| (job.total.saturating_sub(job.transferred) as f64 / bytes_per_sec).ceil() as u64);
+    let progress = TransferProgress {
+        id: job.id.clone(),
+        filename: job.filename(),
+        direction: job.direction,
+        status: job.status,
+        bytes: job.transferred,
+        total: job.total,
+        percent,
+        bytes_per_sec: bytes_per_sec as u64,
+        eta_secs,
+    };
+    app.emit("file-transfer-progress", progress).ok();
+}
+
+/// Smoothed transfer rate
+pub(crate) struct Throughput {
+    last_at: Instant,
+    last_bytes: u64,
+    pub(crate) bytes_per_sec: f64,
+}
+
+impl Throughput {
+    pub(crate) fn new(bytes: u64) -> Self {
+        Self { last_at: Instant::now(), last_bytes: bytes, bytes_per_sec: 0.0 }
+    }
+
+    /// Fold in the bytes done so far, at most every `PROGRESS_INTERVAL`;
+    /// true if the rate was updated
+    pub(crate) fn update(&mut self, bytes: u64) -> bool {
+        let elapsed = self.last_at.elapsed();
+        if elapsed < PROGRESS_INTERVAL {
+            return false;
+        }
+        let current = bytes.saturating_sub(self.last_bytes) as f64 / elapsed.as_secs_f64();
+        self.bytes_per_sec = if self.bytes_per_sec == 0.0 {
+            current
+        } else {
+            0.7 * self.bytes_per_sec + 0.3 * current
+        };
+        self.last_at = Instant::now();
+        self.last_bytes = bytes;
+        true
+    }
+}
+
+/// Run one attempt of `job` on a worker thread
+fn work(app: tauri::AppHandle, job: TransferJob, control: Arc<AtomicU8>) {
+    let manager = app.state::<TransferManager>();
+    // Only a job that has made progress on an unchanged source continues its
+    // partial file, and only as far as the progress it recorded
+    let resume_at = match job.stat_source() {
+        Ok((size, mtime)) => {
+            let offset = job.resume_offset(size, mtime);
+            if job.transferred > 0 && offset == 0 {
+                log::info!("Source of transfer {} changed, starting over", job.id);
+            }
+            manager.set_source(&job.id, size, mtime);
+            Ok(offset)
+        }
+        Err(e) => Err(e),
+    };
+    let mut rate = Throughput::new(resume_at.as_ref().copied().unwrap_or(0));
+    let mut last_save = Instant::now();
+    let mut current = job.clone();
+    let progress = |done: u64, total: u64| {
+        if control.load(Ordering::SeqCst) != RUN {
+            return false;
+        }
+        if rate.update(done) {
+            let save = last_save.elapsed() >= SAVE_INTERVAL;
+            if save {
+                last_save = Instant::now();
+            }
+            manager.progress(&job.id, done, total, save);
+            current.transferred = done;
+            current.total = total;
+            emit(&app, &current, rate.bytes_per_sec);
+        }
+        true
+    };
+    let result = resume_at.and_then(|resume_at| match job.direction {
+        Direction::Upload => ops::upload_file(&job.target, &job.local_path, &job.remote_path, resume_at, progress),
+        Direction::Download => ops::download_file(&job.target, &job.remote_path, &job.local_path, resume_at, progress),
+    });
+    if result.is_err() && job.attempts + 1 < MAX_ATTEMPTS {
+        // Back off before the retry, still holding the slot; a pause or
+        // cancel meanwhile is picked up by `finish`
+        let backoff = Instant::now() + Duration::from_secs(2u64.pow(job.attempts + 1));
+        while Instant::now() < backoff && control.load(Ordering::SeqCst) == RUN {
+            thread::sleep(Duration::from_millis(200));
+        }
+    }
+    manager.finish(&job, result, control.load(Ordering::SeqCst), &app);
+}
+
+/// Best-effort cleanup of a cancelled job's partial file
+fn remove_partial(job: &TransferJob) {
+    match job.direction {
+        Direction::Download => {
+            std::fs::remove_file(format!("{}{}", job.local_path, PARTIAL_SUFFIX)).ok();
+        }
+        Direction::Upload => {
+            if job.transferred == 0 {
+                return;
+            }
+            if let Ok(mut sftp) = SftpSession::connect(&job.target) {
+                sftp.remove(&format!("{}{}", job.remote_path, PARTIAL_SUFFIX)).ok();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_running_jobs_are_requeued_on_load() {
+        let dir = std::env::temp_dir().join(format!("remotelab-queue-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("transfers.json");
+
+        let manager = TransferManager::load(path.clone());
+        let target = SshTarget::new("10.0.0.2", "lab", None);
+        let job = manager.add(Direction::Upload, target, "/tmp/a.bin".into(), "/data/a.bin".into());
+        manager.queue.lock().unwrap().jobs[0].status = JobStatus::Running;
+        manager.queue.lock().unwrap().max_parallel = 4;
+        manager.progress(&job.id, 4096, 8192, true);
+
+        let reloaded = TransferManager::load(path);
+        let jobs = reloaded.list();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].status, JobStatus::Queued);
+        assert_eq!(jobs[0].transferred, 4096);
+        assert_eq!(reloaded.max_parallel(), 4);
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_resume_only_from_acknowledged_bytes_of_same_source() {
+        let target = SshTarget::new("10.0.0.2", "lab", None);
+        let path = std::env::temp_dir().join(format!("remotelab-queue-{}.json", uuid::Uuid::new_v4()));
+        let manager = TransferManager::load(path.clone());
+        let mut job = manager.add(Direction::Upload, target, "/tmp/a.bin".into(), "/data/a.bin".into());
+        job.transferred = 4096;
+        // Never started: nothing to continue
+        assert_eq!(job.resume_offset(8192, Some(100)), 0);
+
+        job.source_size = Some(8192);
+        job.source_mtime = Some(100);
+        assert_eq!(job.resume_offset(8192, Some(100)), 4096);
+        assert_eq!(job.resume_offset(8192, Some(101)), 0);
+        assert_eq!(job.resume_offset(9000, Some(100)), 0);
+        std::fs::remove_file(path).ok();
+    }
+}
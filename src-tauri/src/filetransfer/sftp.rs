@@ -0,0 +1,828 @@
+//! SFTP version 3 client (draft-ietf-secsh-filexfer-02), run over the `sftp`
+//! subsystem of the system ssh client. Unlike shell scripts, it doesn't care
+//! what the remote shell is or which characters a file name contains.
+
+use std::collections::{HashMap, VecDeque};
+use std::io::{Read, Write};
+use std::ops::{Deref, DerefMut};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+use crate::ssh_target::SshTarget;
+
+const SSH_FXP_INIT: u8 = 1;
+const SSH_FXP_VERSION: u8 = 2;
+const SSH_FXP_OPEN: u8 = 3;
+const SSH_FXP_CLOSE: u8 = 4;
+const SSH_FXP_READ: u8 = 5;
+const SSH_FXP_WRITE: u8 = 6;
+const SSH_FXP_LSTAT: u8 = 7;
+const SSH_FXP_FSTAT: u8 = 8;
+const SSH_FXP_SETSTAT: u8 = 9;
+const SSH_FXP_OPENDIR: u8 = 11;
+const SSH_FXP_READDIR: u8 = 12;
+const SSH_FXP_REMOVE: u8 = 13;
+const SSH_FXP_MKDIR: u8 = 14;
+const SSH_FXP_RMDIR: u8 = 15;
+const SSH_FXP_REALPATH: u8 = 16;
+const SSH_FXP_STAT: u8 = 17;
+const SSH_FXP_RENAME: u8 = 18;
+const SSH_FXP_READLINK: u8 = 19;
+const SSH_FXP_SYMLINK: u8 = 20;
+const SSH_FXP_STATUS: u8 = 101;
+const SSH_FXP_HANDLE: u8 = 102;
+const SSH_FXP_DATA: u8 = 103;
+const SSH_FXP_NAME: u8 = 104;
+const SSH_FXP_ATTRS: u8 = 105;
+const SSH_FXP_EXTENDED: u8 = 200;
+const SSH_FXP_EXTENDED_REPLY: u8 = 201;
+
+const SSH_FX_OK: u32 = 0;
+const SSH_FX_EOF: u32 = 1;
+const SSH_FX_NO_SUCH_FILE: u32 = 2;
+const SSH_FX_PERMISSION_DENIED: u32 = 3;
+const SSH_FX_OP_UNSUPPORTED: u32 = 8;
+
+const ATTR_SIZE: u32 = 0x1;
+const ATTR_UIDGID: u32 = 0x2;
+const ATTR_PERMISSIONS: u32 = 0x4;
+const ATTR_ACMODTIME: u32 = 0x8;
+const ATTR_EXTENDED: u32 = 0x8000_0000;
+
+pub const OPEN_READ: u32 = 0x1;
+pub const OPEN_WRITE: u32 = 0x2;
+pub const OPEN_CREATE: u32 = 0x8;
+pub const OPEN_TRUNCATE: u32 = 0x10;
+
+/// `st_mode` file type bits
+const S_IFMT: u32 = 0o170000;
+const S_IFDIR: u32 = 0o040000;
+const S_IFREG: u32 = 0o100000;
+const S_IFLNK: u32 = 0o120000;
+
+/// Bytes per read or write request; every server accepts this much
+pub const CHUNK: usize = 32 * 1024;
+/// Requests kept in flight during a transfer, so throughput isn't bound by
+/// the round-trip time
+const PIPELINE: usize = 32;
+/// Largest reply accepted from the server
+const MAX_PACKET: usize = 4 * 1024 * 1024;
+
+/// File attributes; a field is None when the server didn't send it
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileAttrs {
+    pub size: Option<u64>,
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+    /// Full `st_mode`, including the file type bits
+    pub permissions: Option<u32>,
+    pub atime: Option<u32>,
+    pub mtime: Option<u32>,
+}
+
+impl FileAttrs {
+    fn file_type(&self) -> Option<u32> {
+        self.permissions.map(|p| p & S_IFMT)
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.file_type() == Some(S_IFDIR)
+    }
+
+    pub fn is_symlink(&self) -> bool {
+        self.file_type() == Some(S_IFLNK)
+    }
+
+    pub fn is_file(&self) -> bool {
+        self.file_type() == Some(S_IFREG)
+    }
+
+    /// Attributes setting only the permission bits, e.g. for a new file
+    pub fn with_mode(mode: u32) -> Self {
+        Self { permissions: Some(mode & 0o7777), ..Self::default() }
+    }
+
+    fn encode(&self, buf: &mut Encoder) {
+        let mut flags = 0;
+        if self.size.is_some() {
+            flags |= ATTR_SIZE;
+        }
+        if self.uid.is_some() && self.gid.is_some() {
+            flags |= ATTR_UIDGID;
+        }
+        if self.permissions.is_some() {
+            flags |= ATTR_PERMISSIONS;
+        }
+        if self.atime.is_some() && self.mtime.is_some() {
+            flags |= ATTR_ACMODTIME;
+        }
+        buf.u32(flags);
+        if let Some(size) = self.size {
+            buf.u64(size);
+        }
+        if let (Some(uid), Some(gid)) = (self.uid, self.gid) {
+            buf.u32(uid);
+            buf.u32(gid);
+        }
+        if let Some(permissions) = self.permissions {
+            buf.u32(permissions);
+        }
+        if let (Some(atime), Some(mtime)) = (self.atime, self.mtime) {
+            buf.u32(atime);
+            buf.u32(mtime);
+        }
+    }
+
+    fn decode(cur: &mut Decoder) -> Result<Self, String> {
+        let flags = cur.u32()?;
+        let mut attrs = Self::default();
+        if flags & ATTR_SIZE != 0 {
+            attrs.size = Some(cur.u64()?);
+        }
+        if flags & ATTR_UIDGID != 0 {
+            attrs.uid = Some(cur.u32()?);
+            attrs.gid = Some(cur.u32()?);
+        }
+        if flags & ATTR_PERMISSIONS != 0 {
+            attrs.permissions = Some(cur.u32()?);
+        }
+        if flags & ATTR_ACMODTIME != 0 {
+            attrs.atime = Some(cur.u32()?);
+            attrs.mtime = Some(cur.u32()?);
+        }
+        if flags & ATTR_EXTENDED != 0 {
+            for _ in 0..cur.u32()? {
+                cur.bytes()?;
+                cur.bytes()?;
+            }
+        }
+        Ok(attrs)
+    }
+}
+
+/// A directory entry from READDIR
+#[derive(Debug, Clone)]
+pub struct DirEntry {
+    pub name: String,
+    /// `ls -l` style line from the server; its format is not standardized
+    pub longname: String,
+    pub attrs: FileAttrs,
+}
+
+/// `ls -l` style mode string, e.g. `drwxr-xr-x`
+pub fn mode_string(mode: u32) -> String {
+    let kind = match mode & S_IFMT {
+        S_IFDIR => 'd',
+        S_IFLNK => 'l',
+        S_IFREG => '-',
+        0o020000 => 'c',
+        0o060000 => 'b',
+        0o010000 => 'p',
+        0o140000 => 's',
+        _ => '?',
+    };
+    let mut s = String::with_capacity(10);
+    s.push(kind);
+    for (shift, special, set_char) in [(6, 0o4000, 's'), (3, 0o2000, 's'), (0, 0o1000, 't')] {
+        let bits = (mode >> shift) & 0o7;
+        s.push(if bits & 4 != 0 { 'r' } else { '-' });
+        s.push(if bits & 2 != 0 { 'w' } else { '-' });
+        s.push(match (bits & 1 != 0, mode & special != 0) {
+            (true, true) => set_char,
+            (false, true) => set_char.to_ascii_uppercase(),
+            (true, false) => 'x',
+            (false, false) => '-',
+        });
+    }
+    s
+}
+
+struct Encoder(Vec<u8>);
+
+impl Encoder {
+    fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    fn u32(&mut self, v: u32) -> &mut Self {
+        self.0.extend_from_slice(&v.to_be_bytes());
+        self
+    }
+
+    fn u64(&mut self, v: u64) -> &mut Self {
+        self.0.extend_from_slice(&v.to_be_bytes());
+        self
+    }
+
+    fn bytes(&mut self, v: &[u8]) -> &mut Self {
+        self.u32(v.len() as u32);
+        self.0.extend_from_slice(v);
+        self
+    }
+
+    fn str(&mut self, v: &str) -> &mut Self {
+        self.bytes(v.as_bytes())
+    }
+}
+
+struct Decoder<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], String> {
+        let end = self.pos.checked_add(n).filter(|&e| e <= self.data.len());
+        let end = end.ok_or("Truncated SFTP packet")?;
+        let slice = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_be_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8], String> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+
+    /// File names are bytes on the wire; non-UTF-8 ones are shown lossily
+    fn string(&mut self) -> Result<String, String> {
+        Ok(String::from_utf8_lossy(self.bytes()?).into_owned())
+    }
+}
+
+#[derive(Debug)]
+enum Reply {
+    Status { code: u32, message: String },
+    Handle(Vec<u8>),
+    Data(Vec<u8>),
+    Name(Vec<DirEntry>),
+    Attrs(FileAttrs),
+    Extended(Vec<u8>),
+}
+
+/// An error status as a message naming `path`
+fn status_error(code: u32, message: &str, path: &str) -> String {
+    match code {
+        SSH_FX_NO_SUCH_FILE => format!("No such file or directory: {}", path),
+        SSH_FX_PERMISSION_DENIED => format!("Permission denied: {}", path),
+        SSH_FX_OP_UNSUPPORTED => format!("Operation not supported by the server: {}", path),
+        _ if message.is_empty() => format!("SFTP error {} on {}", code, path),
+        _ => format!("{}: {}", message, path),
+    }
+}
+
+fn unexpected(reply: Reply, path: &str) -> String {
+    match reply {
+        Reply::Status { code, message } => status_error(code, &message, path),
+        other => format!("Unexpected SFTP reply for {}: {:?}", path, other),
+    }
+}
+
+/// Read into `buf` until it is full or the input ends
+fn read_full(input: &mut impl Read, buf: &mut [u8]) -> Result<usize, String> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match input.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(format!("Read failed: {}", e)),
+        }
+    }
+    Ok(filled)
+}
+
+/// An open remote file or directory; close it with `SftpClient::close`
+#[derive(Debug, Clone)]
+pub struct Handle(Vec<u8>);
+
+/// SFTP over any byte stream pair
+pub struct SftpClient<R, W> {
+    reader: R,
+    writer: W,
+    next_id: u32,
+    extensions: Vec<(String, String)>,
+    /// Replies that arrived before the one being waited for
+    stash: HashMap<u32, Reply>,
+}
+
+impl<R: Read, W: Write> SftpClient<R, W> {
+    /// Negotiate protocol version 3
+    pub fn new(reader: R, writer: W) -> Result<Self, String> {
+        let mut client = Self { reader, writer, next_id: 0, extensions: Vec::new(), stash: HashMap::new() };
+        let mut init = Encoder::new();
+        init.u32(3);
+        client.write_packet(SSH_FXP_INIT, &init.0)?;
+        let packet = client.read_packet()?;
+        let mut cur = Decoder::new(&packet);
+        if cur.u8()? != SSH_FXP_VERSION {
+            return Err("Server did not answer the SFTP handshake".to_string());
+        }
+        let version = cur.u32()?;
+        if version < 3 {
+            return Err(format!("Unsupported SFTP version {}", version));
+        }
+        while cur.pos < packet.len() {
+            let name = cur.string()?;
+            let data = cur.string()?;
+            client.extensions.push((name, data));
+        }
+        Ok(client)
+    }
+
+    /// Whether the server advertised extension `name`, e.g. `copy-data`
+    pub fn has_extension(&self, name: &str) -> bool {
+        self.extensions.iter().any(|(n, _)| n == name)
+    }
+
+    fn write_packet(&mut self, kind: u8, body: &[u8]) -> Result<(), String> {
+        let len = (body.len() + 1) as u32;
+        let mut frame = Vec::with_capacity(body.len() + 5);
+        frame.extend_from_slice(&len.to_be_bytes());
+        frame.push(kind);
+        frame.extend_from_slice(body);
+        self.writer
+            .write_all(&frame)
+            .and_then(|_| self.writer.flush())
+            .map_err(|e| format!("SFTP connection lost: {}", e))
+    }
+
+    fn read_packet(&mut self) -> Result<Vec<u8>, String> {
+        let mut len = [0u8; 4];
+        self.reader
+            .read_exact(&mut len)
+            .map_err(|e| format!("SFTP connection lost: {}", e))?;
+        let len = u32::from_be_bytes(len) as usize;
+        if len == 0 || len > MAX_PACKET {
+            return Err(format!("Bad SFTP packet length {}", len));
+        }
+        let mut packet = vec![0u8; len];
+        self.reader
+            .read_exact(&mut packet)
+            .map_err(|e| format!("SFTP connection lost: {}", e))?;
+        Ok(packet)
+    }
+
+    /// Send a request; `body` is everything after the request id
+    fn send(&mut self, kind: u8, body: &[u8]) -> Result<u32, String> {
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        let mut packet = Vec::with_capacity(body.len() + 4);
+        packet.extend_from_slice(&id.to_be_bytes());
+        packet.extend_from_slice(body);
+        self.write_packet(kind, &packet)?;
+        Ok(id)
+    }
+
+    /// Wait for the reply to request `id`
+    fn recv(&mut self, id: u32) -> Result<Reply, String> {
+        if let Some(reply) = self.stash.remove(&id) {
+            return Ok(reply);
+        }
+        loop {
+            let packet = self.read_packet()?;
+            let mut cur = Decoder::new(&packet);
+            let kind = cur.u8()?;
+            let reply_id = cur.u32()?;
+            let reply = match kind {
+                SSH_FXP_STATUS => Reply::Status {
+                    code: cur.u32()?,
+                    // Some old servers leave out the message
+                    message: cur.string().unwrap_or_default(),
+                },
+                SSH_FXP_HANDLE => Reply::Handle(cur.bytes()?.to_vec()),
+                SSH_FXP_DATA => Reply::Data(cur.bytes()?.to_vec()),
+                SSH_FXP_NAME => {
+                    let count = cur.u32()?;
+                    let mut entries = Vec::with_capacity(count.min(1024) as usize);
+                    for _ in 0..count {
+                        entries.push(DirEntry {
+                            name: cur.string()?,
+                            longname: cur.string()?,
+                            attrs: FileAttrs::decode(&mut cur)?,
+                        });
+                    }
+                    Reply::Name(entries)
+                }
+                SSH_FXP_ATTRS => Reply::Attrs(FileAttrs::decode(&mut cur)?),
+                SSH_FXP_EXTENDED_REPLY => Reply::Extended(packet[cur.pos..].to_vec()),
+                other => return Err(format!("Unknown SFTP reply type {}", other)),
+            };
+            if reply_id == id {
+                return Ok(reply);
+            }
+            self.stash.insert(reply_id, reply);
+        }
+    }
+
+    fn call(&mut self, kind: u8, body: &Encoder) -> Result<Reply, String> {
+        let id = self.send(kind, &body.0)?;
+        self.recv(id)
+    }
+
+    fn call_ok(&mut self, kind: u8, body: &Encoder, path: &str) -> Result<(), String> {
+        match self.call(kind, body)? {
+            Reply::Status { code: SSH_FX_OK, .. } => Ok(()),
+            other => Err(unexpected(other, path)),
+        }
+    }
+
+    fn call_attrs(&mut self, kind: u8, path: &str) -> Result<Option<FileAttrs>, String> {
+        match self.call(kind, Encoder::new().str(path))? {
+            Reply::Attrs(attrs) => Ok(Some(attrs)),
+            Reply::Status { code: SSH_FX_NO_SUCH_FILE, .. } => Ok(None),
+            other => Err(unexpected(other, path)),
+        }
+    }
+
+    /// Wait out replies to requests that are no longer of interest, so they
+    /// don't pile up in the stash
+    fn drain(&mut self, pending: impl IntoIterator<Item = u32>) {
+        for id in pending {
+            if self.recv(id).is_err() {
+                break;
+            }
+        }
+    }
+
+    /// Absolute, canonical form of `path`; "." is the login directory
+    pub fn realpath(&mut self, path: &str) -> Result<String, String> {
+        match self.call(SSH_FXP_REALPATH, Encoder::new().str(path))? {
+            Reply::Name(entries) if !entries.is_empty() => Ok(entries[0].name.clone()),
+            other => Err(unexpected(other, path)),
+        }
+    }
+
+    /// Attributes, following symlinks
+    pub fn stat(&mut self, path: &str) -> Result<FileAttrs, String> {
+        self.try_stat(path)?
+            .ok_or_else(|| status_error(SSH_FX_NO_SUCH_FILE, "", path))
+    }
+
+    /// Like `stat`, but None when the path doesn't exist
+    pub fn try_stat(&mut self, path: &str) -> Result<Option<FileAttrs>, String> {
+        self.call_attrs(SSH_FXP_STAT, path)
+    }
+
+    /// Attributes of the path itself, not a symlink's target; None when it
+    /// doesn't exist
+    pub fn try_lstat(&mut self, path: &str) -> Result<Option<FileAttrs>, String> {
+        self.call_attrs(SSH_FXP_LSTAT, path)
+    }
+
+    pub fn lstat(&mut self, path: &str) -> Result<FileAttrs, String> {
+        self.try_lstat(path)?
+            .ok_or_else(|| status_error(SSH_FX_NO_SUCH_FILE, "", path))
+    }
+
+    pub fn fstat(&mut self, handle: &Handle) -> Result<FileAttrs, String> {
+        match self.call(SSH_FXP_FSTAT, Encoder::new().bytes(&handle.0))? {
+            Reply::Attrs(attrs) => Ok(attrs),
+            other => Err(unexpected(other, "open file")),
+        }
+    }
+
+    pub fn setstat(&mut self, path: &str, attrs: &FileAttrs) -> Result<(), String> {
+        let mut body = Encoder::new();
+        body.str(path);
+        attrs.encode(&mut body);
+        self.call_ok(SSH_FXP_SETSTAT, &body, path)
+    }
+
+    pub fn readlink(&mut self, path: &str) -> Result<String, String> {
+        match self.call(SSH_FXP_READLINK, Encoder::new().str(path))? {
+            Reply::Name(entries) if !entries.is_empty() => Ok(entries[0].name.clone()),
+            other => Err(unexpected(other, path)),
+        }
+    }
+
+    /// Create symlink `link` pointing at `target`
+    pub fn symlink(&mut self, link: &str, target: &str) -> Result<(), String> {
+        // OpenSSH swapped the arguments relative to the draft, and every
+        // common server followed it: target first, then the link
+        self.call_ok(SSH_FXP_SYMLINK, Encoder::new().str(target).str(link), link)
+    }
+
+    pub fn mkdir(&mut self, path: &str, mode: Option<u32>) -> Result<(), String> {
+        let mut body = Encoder::new();
+        body.str(path);
+        FileAttrs { permissions: mode, ..FileAttrs::default() }.encode(&mut body);
+        self.call_ok(SSH_FXP_MKDIR, &body, path)
+    }
+
+    pub fn rmdir(&mut self, path: &str) -> Result<(), String> {
+        self.call_ok(SSH_FXP_RMDIR, Encoder::new().str(path), path)
+    }
+
+    /// Remove a file or symlink
+    pub fn remove(&mut self, path: &str) -> Result<(), String> {
+        self.call_ok(SSH_FXP_REMOVE, Encoder::new().str(path), path)
+    }
+
+    /// Rename; fails if `to` exists unless the server supports
+    /// `posix-rename@openssh.com`, which replaces it
+    pub fn rename(&mut self, from: &str, to: &str) -> Result<(), String> {
+        if self.has_extension("posix-rename@openssh.com") {
+            let mut body = Encoder::new();
+            body.str("posix-rename@openssh.com").str(from).str(to);
+            return self.call_ok(SSH_FXP_EXTENDED, &body, from);
+        }
+        self.call_ok(SSH_FXP_RENAME, Encoder::new().str(from).str(to), from)
+    }
+
+    /// Copy all of `from` into `to` on the server with the `copy-data`
+    /// extension; check `has_extension("copy-data")` first
+    pub fn copy_data(&mut self, from: &Handle, to: &Handle) -> Result<(), String> {
+        let mut body = Encoder::new();
+        // A length of 0 copies up to the end of the file
+        body.str("copy-data").bytes(&from.0).u64(0).u64(0).bytes(&to.0).u64(0);
+        self.call_ok(SSH_FXP_EXTENDED, &body, "copy")
+    }
+
+    pub fn open(&mut self, path: &str, flags: u32, attrs: &FileAttrs) -> Result<Handle, String> {
+        let mut body = Encoder::new();
+        body.str(path).u32(flags);
+        attrs.encode(&mut body);
+        match self.call(SSH_FXP_OPEN, &body)? {
+            Reply::Handle(handle) => Ok(Handle(handle)),
+            other => Err(unexpected(other, path)),
+        }
+    }
+
+    pub fn close(&mut self, handle: Handle) -> Result<(), String> {
+        self.call_ok(SSH_FXP_CLOSE, Encoder::new().bytes(&handle.0), "open file")
+    }
+
+    /// All entries of a directory, including `.` and `..`
+    pub fn read_dir(&mut self, path: &str) -> Result<Vec<DirEntry>, String> {
+        let handle = match self.call(SSH_FXP_OPENDIR, Encoder::new().str(path))? {
+            Reply::Handle(handle) => Handle(handle),
+            other => return Err(unexpected(other, path)),
+        };
+        let mut entries = Vec::new();
+        let listed = loop {
+            match self.call(SSH_FXP_READDIR, Encoder::new().bytes(&handle.0)) {
+                Ok(Reply::Name(batch)) => entries.extend(batch),
+                Ok(Reply::Status { code: SSH_FX_EOF, .. }) => break Ok(entries),
+                Ok(other) => break Err(unexpected(other, path)),
+                Err(e) => break Err(e),
+            }
+        };
+        self.close(handle)?;
+        listed
+    }
+
+    /// Copy the file behind `handle` from byte `offset` on into `out`.
+    /// `progress` gets the offset reached after each chunk and can stop the
+    /// transfer by returning false. Returns the final offset.
+    pub fn read_to(
+        &mut self,
+        handle: &Handle,
+        mut offset: u64,
+        out: &mut impl Write,
+        mut progress: impl FnMut(u64) -> bool,
+    ) -> Result<u64, String> {
+        let mut pending: VecDeque<(u32, u64)> = VecDeque::new();
+        let mut next = offset;
+        let mut eof = false;
+        loop {
+            while !eof && pending.len() < PIPELINE {
+                let id = self.send(SSH_FXP_READ, Encoder::new().bytes(&handle.0).u64(next).u32(CHUNK as u32).0.as_slice())?;
+                pending.push_back((id, next));
+                next += CHUNK as u64;
+            }
+            let Some((id, at)) = pending.pop_front() else { break };
+            let mut want = CHUNK as u64;
+            let mut reply = self.recv(id)?;
+            // Short reads leave a gap before the next request's offset, so
+            // fill it before moving on
+            loop {
+                match reply {
+                    Reply::Data(data) => {
+                        out.write_all(&data).map_err(|e| format!("Write failed: {}", e))?;
+                        offset += data.len() as u64;
+                        want = want.saturating_sub(data.len() as u64);
+                    }
+                    Reply::Status { code: SSH_FX_EOF, .. } => eof = true,
+                    other => {
+                        self.drain(pending.iter().map(|(id, _)| *id));
+                        return Err(unexpected(other, "open file"));
+                    }
+                }
+                if eof || want == 0 {
+                    break;
+                }
+                let body = Encoder::new().bytes(&handle.0).u64(offset).u32(want as u32).0.clone();
+                let id = self.send(SSH_FXP_READ, &body)?;
+                reply = self.recv(id)?;
+            }
+            debug_assert!(eof || offset == at + CHUNK as u64);
+            if !progress(offset) {
+                self.drain(pending.iter().map(|(id, _)| *id));
+                return Err("Transfer stopped".to_string());
+            }
+        }
+        Ok(offset)
+    }
+
+    /// Write everything `input` yields to the file behind `handle`, starting
+    /// at byte `offset`. `progress` works as in `read_to`.
+    pub fn write_from(
+        &mut self,
+        handle: &Handle,
+        offset: u64,
+        input: &mut impl Read,
+        mut progress: impl FnMut(u64) -> bool,
+    ) -> Result<u64, String> {
+        let mut pending: VecDeque<(u32, usize)> = VecDeque::new();
+        let mut next = offset;
+        let mut acked = offset;
+        let mut done = false;
+        let mut buf = vec![0u8; CHUNK];
+        loop {
+            while !done && pending.len() < PIPELINE {
+                let n = match read_full(input, &mut buf) {
+                    Ok(n) => n,
+                    Err(e) => {
+                        self.drain(pending.iter().map(|(id, _)| *id));
+                        return Err(e);
+                    }
+                };
+                if n == 0 {
+                    done = true;
+                    break;
+                }
+                let id = self.send(SSH_FXP_WRITE, &Encoder::new().bytes(&handle.0).u64(next).bytes(&buf[..n]).0)?;
+                pending.push_back((id, n));
+                next += n as u64;
+                done = n < CHUNK;
+            }
+            let Some((id, n)) = pending.pop_front() else { break };
+            match self.recv(id)? {
+                Reply::Status { code: SSH_FX_OK, .. } => acked += n as u64,
+                other => {
+                    self.drain(pending.iter().map(|(id, _)| *id));
+                    return Err(unexpected(other, "open file"));
+                }
+            }
+            if !progress(acked) {
+                self.drain(pending.iter().map(|(id, _)| *id));
+                return Err("Transfer stopped".to_string());
+            }
+        }
+        Ok(acked)
+    }
+}
+
+/// An SFTP connection through `ssh -s <host> sftp`. Dropping it ends the
+/// ssh process.
+pub struct SftpSession {
+    client: SftpClient<ChildStdout, ChildStdin>,
+    child: Child,
+}
+
+impl SftpSession {
+    pub fn connect(target: &SshTarget) -> Result<Self, String> {
+        let mut args = vec![
+            "-o".to_string(), "StrictHostKeyChecking=accept-new".to_string(),
+            "-o".to_string(), "ConnectTimeout=10".to_string(),
+            "-o".to_string(), "ServerAliveInterval=30".to_string(),
+            "-s".to_string(),
+        ];
+        args.extend(target.ssh_args());
+        args.push(target.destination());
+        args.push("sftp".to_string());
+
+        let mut child = Command::new(super::ops::ssh_bin())
+            .args(&args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("SSH failed: {}", e))?;
+        let stdin = child.stdin.take().ok_or("SSH stdin unavailable")?;
+        let stdout = child.stdout.take().ok_or("SSH stdout unavailable")?;
+        let stderr = child.stderr.take().map(collect_stderr);
+
+        match SftpClient::new(stdout, stdin) {
+            Ok(client) => Ok(Self { client, child }),
+            Err(e) => {
+                child.kill().ok();
+                child.wait().ok();
+                let stderr = stderr
+                    .and_then(|(handle, text)| handle.join().ok().map(|_| text))
+                    .map(|text| text.lock().unwrap().trim().to_string())
+                    .unwrap_or_default();
+                Err(if stderr.is_empty() { e } else { format!("SFTP connection failed: {}", stderr) })
+            }
+        }
+    }
+}
+
+/// Keep reading ssh's stderr so it can't block on a full pipe; the text is
+/// kept (up to a limit) for error messages
+fn collect_stderr(mut stderr: std::process::ChildStderr) -> (JoinHandle<()>, Arc<Mutex<String>>) {
+    let text = Arc::new(Mutex::new(String::new()));
+    let sink = text.clone();
+    let handle = thread::spawn(move || {
+        let mut buf = [0u8; 4096];
+        while let Ok(n) = stderr.read(&mut buf) {
+            if n == 0 {
+                break;
+            }
+            let mut text = sink.lock().unwrap();
+            if text.len() < 16 * 1024 {
+                text.push_str(&String::from_utf8_lossy(&buf[..n]));
+            }
+        }
+    });
+    (handle, text)
+}
+
+impl Deref for SftpSession {
+    type Target = SftpClient<ChildStdout, ChildStdin>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.client
+    }
+}
+
+impl DerefMut for SftpSession {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.client
+    }
+}
+
+impl Drop for SftpSession {
+    fn drop(&mut self) {
+        self.child.kill().ok();
+        self.child.wait().ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn reply(kind: u8, body: &Encoder) -> Vec<u8> {
+        let mut frame = ((body.0.len() + 1) as u32).to_be_bytes().to_vec();
+        frame.push(kind);
+        frame.extend_from_slice(&body.0);
+        frame
+    }
+
+    #[test]
+    fn test_read_dir_and_attrs() {
+        let mut script = reply(SSH_FXP_VERSION, Encoder::new().u32(3).str("posix-rename@openssh.com").str("1"));
+        script.extend(reply(SSH_FXP_HANDLE, Encoder::new().u32(0).bytes(b"h1")));
+        let mut name = Encoder::new();
+        name.u32(1).u32(2);
+        name.str("a|b\nc").str("-rw-r--r--    1 alice    lab  5 Jan  1 00:00 a|b");
+        FileAttrs { size: Some(5), uid: Some(1000), gid: Some(100), permissions: Some(0o100644), atime: Some(1), mtime: Some(2) }
+            .encode(&mut name);
+        name.str("link").str("");
+        FileAttrs { permissions: Some(0o120777), ..FileAttrs::default() }.encode(&mut name);
+        script.extend(reply(SSH_FXP_NAME, &name));
+        script.extend(reply(SSH_FXP_STATUS, Encoder::new().u32(2).u32(SSH_FX_EOF).str("").str("")));
+        script.extend(reply(SSH_FXP_STATUS, Encoder::new().u32(3).u32(SSH_FX_OK).str("").str("")));
+
+        let mut sent = Vec::new();
+        let mut client = SftpClient::new(Cursor::new(script), &mut sent).unwrap();
+        assert!(client.has_extension("posix-rename@openssh.com"));
+        let entries = client.read_dir("/data").unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].name, "a|b\nc");
+        assert_eq!(entries[0].attrs.mtime, Some(2));
+        assert!(entries[0].attrs.is_file());
+        assert!(entries[1].attrs.is_symlink());
+        // INIT, OPENDIR, 2 x READDIR, CLOSE
+        assert_eq!(sent[4], SSH_FXP_INIT);
+    }
+
+    #[test]
+    fn test_mode_string() {
+        assert_eq!(mode_string(0o040755), "drwxr-xr-x");
+        assert_eq!(mode_string(0o100644), "-rw-r--r--");
+        assert_eq!(mode_string(0o104755), "-rwsr-xr-x");
+        assert_eq!(mode_string(0o041777), "drwxrwxrwt");
+        assert_eq!(mode_string(0o120777), "lrwxrwxrwx");
+    }
+}
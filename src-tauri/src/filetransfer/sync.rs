@@ -0,0 +1,642 @@
+//! One-way delta sync of a directory tree between this machine and a remote
+//! host.
+//!
+//! rsync does the work when both ends have it. Otherwise both trees are
+//! scanned and compared here, by size and mtime or by SHA-256, and only what
+//! differs is copied over SFTP. Either way a dry run returns the `SyncPlan`
+//! without changing anything, so it can be shown before the real run.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::process::Command;
+
+use super::ops::{self, join_remote, FileKind};
+use super::queue::{Direction, JobStatus};
+use super::sftp::SftpSession;
+use super::tree::{self, Filter, Reporter, TreeEntry, TreePlan};
+use crate::audit::{self, AuditAction};
+use crate::ssh_target::SshTarget;
+use crate::terminal::startup::quote;
+
+/// Paths per remote checksum command
+const CHECKSUM_BATCH: usize = 200;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Compare {
+    /// A file differs if its size or modification time does
+    #[default]
+    SizeMtime,
+    /// A file differs if its size or SHA-256 does
+    Checksum,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncEngine {
+    /// rsync if both ends have it, else built-in
+    #[default]
+    Auto,
+    Rsync,
+    Builtin,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SyncOptions {
+    /// Upload makes the remote tree match the local one, download the reverse
+    pub direction: Direction,
+    #[serde(default)]
+    pub compare: Compare,
+    /// Remove destination files the source doesn't have. Paths excluded by
+    /// the filters are never removed.
+    #[serde(default)]
+    pub delete: bool,
+    #[serde(default)]
+    pub include: Vec<String>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    #[serde(default)]
+    pub engine: SyncEngine,
+    /// Only work out and return the plan
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncAction {
+    /// New on the destination
+    Create,
+    /// Changed content
+    Update,
+    /// Something of another kind is in the way, e.g. a file where the source
+    /// has a directory
+    Replace,
+    /// New or changed symlink
+    Link,
+    Mkdir,
+    Delete,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SyncItem {
+    /// Relative to the tree roots
+    pub path: String,
+    pub action: SyncAction,
+    pub kind: FileKind,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncPlan {
+    /// `rsync` or `builtin`, whichever made the plan
+    pub engine: SyncEngine,
+    pub direction: Direction,
+    pub items: Vec<SyncItem>,
+    /// Size of the files to copy; rsync's delta transfer may send less
+    pub bytes: u64,
+    /// Files found identical; only counted by the built-in engine
+    pub unchanged: usize,
+    /// Attributes, links or deletions that could not be applied
+    pub warnings: Vec<String>,
+}
+
+/// Sync `local_root` and `remote_root` in `options.direction`
+pub fn sync(
+    target: &SshTarget,
+    local_root: &str,
+    remote_root: &str,
+    options: &SyncOptions,
+    app: &tauri::AppHandle,
+) -> Result<SyncPlan, String> {
+    let use_rsync = match options.engine {
+        SyncEngine::Rsync => true,
+        SyncEngine::Builtin => false,
+        // rsync for Windows expects its own path syntax, so it isn't tried there
+        SyncEngine::Auto => cfg!(unix) && rsync_available(target),
+    };
+    let (action, source, dest) = match options.direction {
+        Direction::Upload => (AuditAction::SftpUpload, local_root, remote_root),
+        Direction::Download => (AuditAction::SftpDownload, remote_root, local_root),
+    };
+    if options.dry_run {
+        return match use_rsync {
+            true => run_rsync(target, local_root, remote_root, options),
+            false => builtin(target, local_root, remote_root, options, None),
+        };
+    }
+    audit::track(action, target, Some(remote_root), || {
+        log::info!("Syncing {} to {} ({})", source, dest, if use_rsync { "rsync" } else { "built-in" });
+        let plan = match use_rsync {
+            true => run_rsync(target, local_root, remote_root, options)?,
+            false => builtin(target, local_root, remote_root, options, Some(app))?,
+        };
+        Reporter::new(app, options.direction, plan.bytes).emit(source, plan.bytes, JobStatus::Completed);
+        log::info!("Sync complete: {} changes", plan.items.len());
+        Ok(plan)
+    })
+}
+
+fn rsync_available(target: &SshTarget) -> bool {
+    let local = Command::new("rsync")
+        .arg("--version")
+        .output()
+        .is_ok_and(|o| o.status.success());
+    local
+        && ops::ssh_exec(target, "command -v rsync")
+            .is_ok_and(|out| !out.trim().is_empty())
+}
+
+/// `[user@]host:path` for rsync, bracketing IPv6 literals
+fn rsync_remote(target: &SshTarget, path: &str) -> String {
+    let host = match target.host.contains(':') && !target.host.starts_with('[') {
+        true => format!("[{}]", target.host),
+        false => target.host.clone(),
+    };
+    format!("{}@{}:{}", target.user, host, path)
+}
+
+/// A filter pattern in rsync's syntax: ours match paths with `/` from the
+/// tree root, which rsync spells with a leading `/`
+fn rsync_pattern(pattern: &str) -> String {
+    let pattern = pattern.trim().trim_matches('/');
+    match pattern.contains('/') {
+        true => format!("/{}", pattern),
+        false => pattern.to_string(),
+    }
+}
+
+fn rsync_args(target: &SshTarget, local_root: &str, remote_root: &str, options: &SyncOptions) -> Vec<String> {
+    let mut args: Vec<String> = ["--archive", "--protect-args", "--itemize-changes", "--out-format=%i %l %n%L"]
+        .map(String::from)
+        .to_vec();
+    if options.dry_run {
+        args.push("--dry-run".to_string());
+    }
+    if options.compare == Compare::Checksum {
+        args.push("--checksum".to_string());
+    }
+    if options.delete {
+        args.push("--delete".to_string());
+    }
+    // rsync uses the first matching rule, so exclusions go first and the
+    // include list ends with a catch-all exclude, letting every directory
+    // through to be searched
+    for pattern in &options.exclude {
+        args.push(format!("--exclude={}", rsync_pattern(pattern)));
+    }
+    if !options.include.is_empty() {
+        args.push("--include=*/".to_string());
+        for pattern in &options.include {
+            args.push(format!("--include={}", rsync_pattern(pattern)));
+        }
+        args.push("--exclude=*".to_string());
+    }
+    // rsync splits -e on spaces but keeps double-quoted words together
+    let ssh: Vec<String> = std::iter::once(ops::ssh_bin().to_string())
+        .chain(ops::ssh_connect_args(target))
+        .map(|a| if a.contains(char::is_whitespace) { format!("\"{}\"", a) } else { a })
+        .collect();
+    args.push("-e".to_string());
+    args.push(ssh.join(" "));
+
+    // Trailing slashes: sync the directories' contents, not the directories
+    let local = format!("{}/", local_root.trim_end_matches(['/', '\\']));
+    let remote = rsync_remote(target, &format!("{}/", remote_root.trim_end_matches('/')));
+    match options.direction {
+        Direction::Upload => args.extend([local, remote]),
+        Direction::Download => args.extend([remote, local]),
+    }
+    args
+}
+
+fn run_rsync(target: &SshTarget, local_root: &str, remote_root: &str, options: &SyncOptions) -> Result<SyncPlan, String> {
+    if options.direction == Direction::Download && !options.dry_run {
+        std::fs::create_dir_all(local_root).map_err(|e| format!("Cannot create {}: {}", local_root, e))?;
+    }
+    let output = Command::new("rsync")
+        .args(rsync_args(target, local_root, remote_root, options))
+        .output()
+        .map_err(|e| format!("Failed to run rsync: {}", e))?;
+    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    let mut warnings = Vec::new();
+    match output.status.code() {
+        Some(0) => {}
+        // Some files vanished before they could be copied
+        Some(24) => warnings.push(stderr),
+        _ => return Err(format!("rsync failed: {}", stderr)),
+    }
+
+    let items: Vec<SyncItem> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(parse_itemized)
+        .collect();
+    Ok(SyncPlan {
+        engine: SyncEngine::Rsync,
+        direction: options.direction,
+        bytes: copied_bytes(&items),
+        items,
+        unchanged: 0,
+        warnings,
+    })
+}
+
+fn copied_bytes(items: &[SyncItem]) -> u64 {
+    items
+        .iter()
+        .filter(|i| i.kind == FileKind::File && i.action != SyncAction::Delete)
+        .map(|i| i.size)
+        .sum()
+}
+
+/// One line of rsync's `--itemize-changes` output in our `--out-format`:
+/// an 11-character change code, the size and the name. Attribute-only
+/// changes are left out.
+fn parse_itemized(line: &str) -> Option<SyncItem> {
+    let (code, rest) = line.split_at_checked(11)?;
+    let rest = rest.trim_start();
+    let (size, name) = match rest.split_once(' ') {
+        Some((size, name)) if size.parse::<u64>().is_ok() => (size.parse().unwrap_or(0), name),
+        _ => (0, rest),
+    };
+    if code.starts_with("*deleting") {
+        let kind = if name.ends_with('/') { FileKind::Dir } else { FileKind::File };
+        let path = name.trim_end_matches('/').to_string();
+        return Some(SyncItem { path, action: SyncAction::Delete, kind, size: 0 });
+    }
+
+    let mut chars = code.chars();
+    let (change, kind) = (chars.next()?, chars.next()?);
+    let kind = match kind {
+        'f' => FileKind::File,
+        'd' => FileKind::Dir,
+        'L' => FileKind::Symlink,
+        _ => FileKind::Other,
+    };
+    let new = code[2..].chars().all(|c| c == '+');
+    let action = match (change, kind) {
+        ('<' | '>', FileKind::File) if new => SyncAction::Create,
+        ('<' | '>', FileKind::File) => SyncAction::Update,
+        ('c', FileKind::Dir) => SyncAction::Mkdir,
+        ('c', FileKind::Symlink) => SyncAction::Link,
+        ('c', FileKind::File) => SyncAction::Create,
+        _ => return None,
+    };
+    let name = match kind {
+        FileKind::Symlink => name.split(" -> ").next().unwrap_or(name),
+        _ => name,
+    };
+    Some(SyncItem {
+        path: name.trim_end_matches('/').to_string(),
+        action,
+        kind,
+        size: if kind == FileKind::File { size } else { 0 },
+    })
+}
+
+/// Compare the trees here and, unless it's a dry run (`app` is None), copy
+/// the differences over SFTP
+fn builtin(
+    target: &SshTarget,
+    local_root: &str,
+    remote_root: &str,
+    options: &SyncOptions,
+    app: Option<&tauri::AppHandle>,
+) -> Result<SyncPlan, String> {
+    let filter = Filter::new(&options.include, &options.exclude)?;
+    let mut sftp = SftpSession::connect(target)?;
+    let local = match Path::new(local_root).exists() {
+        true => tree::scan_local(Path::new(local_root), &filter)?,
+        false => TreePlan::default(),
+    };
+    let remote = match sftp.try_stat(remote_root)? {
+        Some(_) => tree::scan_remote(&mut sftp, remote_root, &filter)?,
+        None => TreePlan::default(),
+    };
+    let (source, dest) = match options.direction {
+        Direction::Upload => (&local.entries, &remote.entries),
+        Direction::Download => (&remote.entries, &local.entries),
+    };
+
+    let differing = match options.compare {
+        Compare::SizeMtime => None,
+        Compare::Checksum => Some(differing_checksums(target, &mut sftp, local_root, remote_root, source, dest)?),
+    };
+    let (items, unchanged) = diff(source, dest, differing.as_ref(), options.delete);
+    let mut plan = SyncPlan {
+        engine: SyncEngine::Builtin,
+        direction: options.direction,
+        bytes: copied_bytes(&items),
+        items,
+        unchanged,
+        warnings: Vec::new(),
+    };
+    let Some(app) = app else { return Ok(plan) };
+
+    // Every source directory goes along so its times are restored after
+    // its contents change
+    let changed: HashSet<&str> = plan.items.iter().map(|i| i.path.as_str()).collect();
+    let copies: Vec<TreeEntry> = source
+        .iter()
+        .filter(|e| e.kind == FileKind::Dir || changed.contains(e.path.as_str()))
+        .cloned()
+        .collect();
+    let replaced = plan.items.iter().filter(|i| i.action == SyncAction::Replace);
+    let deleted = plan.items.iter().filter(|i| i.action == SyncAction::Delete).rev();
+    let mut reporter = Reporter::new(app, options.direction, plan.bytes);
+
+    match options.direction {
+        Direction::Upload => {
+            ops::make_dir_all(&mut sftp, remote_root)?;
+            for item in replaced {
+                ops::remove_all(&mut sftp, &join_remote(remote_root, &item.path))?;
+            }
+            plan.warnings = tree::push_entries(&mut sftp, local_root, remote_root, &copies, &mut reporter)?;
+            for item in deleted {
+                if let Err(e) = ops::remove_all(&mut sftp, &join_remote(remote_root, &item.path)) {
+                    plan.warnings.push(format!("{}: not deleted: {}", item.path, e));
+                }
+            }
+        }
+        Direction::Download => {
+            std::fs::create_dir_all(local_root).map_err(|e| format!("Cannot create {}: {}", local_root, e))?;
+            for item in replaced {
+                remove_local(&tree::local_path(local_root, &item.path)?)?;
+            }
+            plan.warnings = tree::pull_entries(&mut sftp, remote_root, local_root, &copies, &mut reporter)?;
+            for item in deleted {
+                let removed = tree::local_path(local_root, &item.path).and_then(|path| remove_local(&path));
+                if let Err(e) = removed {
+                    plan.warnings.push(format!("{}: not deleted: {}", item.path, e));
+                }
+            }
+        }
+    }
+    Ok(plan)
+}
+
+/// What it takes to make `dest` match `source`, and how many files already
+/// do. `differing` holds the same-sized files whose checksums differ, or is
+/// None to compare modification times instead.
+fn diff(
+    source: &[TreeEntry],
+    dest: &[TreeEntry],
+    differing: Option<&HashSet<String>>,
+    delete: bool,
+) -> (Vec<SyncItem>, usize) {
+    let dest_by_path: HashMap<&str, &TreeEntry> = dest.iter().map(|e| (e.path.as_str(), e)).collect();
+    let mut items = Vec::new();
+    let mut unchanged = 0;
+    for entry in source {
+        let action = match dest_by_path.get(entry.path.as_str()) {
+            None if entry.kind == FileKind::Dir => Some(SyncAction::Mkdir),
+            None if entry.kind == FileKind::Symlink => Some(SyncAction::Link),
+            None => Some(SyncAction::Create),
+            Some(existing) if existing.kind != entry.kind => Some(SyncAction::Replace),
+            Some(existing) => match entry.kind {
+                FileKind::File => {
+                    let same = existing.size == entry.size
+                        && match differing {
+                            Some(differing) => !differing.contains(&entry.path),
+                            None => existing.mtime == entry.mtime,
+                        };
+                    (!same).then_some(SyncAction::Update)
+                }
+                FileKind::Symlink => (existing.link_target != entry.link_target).then_some(SyncAction::Link),
+                _ => None,
+            },
+        };
+        match action {
+            Some(action) => items.push(SyncItem {
+                path: entry.path.clone(),
+                action,
+                kind: entry.kind,
+                size: entry.size,
+            }),
+            None if entry.kind != FileKind::Dir => unchanged += 1,
+            None => {}
+        }
+    }
+
+    if delete {
+        let source_paths: HashSet<&str> = source.iter().map(|e| e.path.as_str()).collect();
+        // Entries come parents first, so a deleted directory is seen before
+        // its contents, which go with it. So do the contents of a directory
+        // replaced by a file.
+        let mut deleted_dirs: Vec<&str> = items
+            .iter()
+            .filter(|i| i.action == SyncAction::Replace)
+            .filter_map(|i| dest_by_path.get(i.path.as_str()))
+            .filter(|e| e.kind == FileKind::Dir)
+            .map(|e| e.path.as_str())
+            .collect();
+        for entry in dest {
+            let path = entry.path.as_str();
+            let inside_deleted = deleted_dirs
+                .iter()
+                .any(|dir| path.strip_prefix(dir).is_some_and(|rest| rest.starts_with('/')));
+            if source_paths.contains(path) || inside_deleted {
+                continue;
+            }
+            if entry.kind == FileKind::Dir {
+                deleted_dirs.push(path);
+            }
+            items.push(SyncItem {
+                path: entry.path.clone(),
+                action: SyncAction::Delete,
+                kind: entry.kind,
+                size: 0,
+            });
+        }
+    }
+    (items, unchanged)
+}
+
+/// Paths of the files present on both ends with the same size whose
+/// SHA-256 differs (or could not be computed)
+fn differing_checksums(
+    target: &SshTarget,
+    sftp: &mut SftpSession,
+    local_root: &str,
+    remote_root: &str,
+    source: &[TreeEntry],
+    dest: &[TreeEntry],
+) -> Result<HashSet<String>, String> {
+    let dest_sizes: HashMap<&str, u64> = dest
+        .iter()
+        .filter(|e| e.kind == FileKind::File)
+        .map(|e| (e.path.as_str(), e.size))
+        .collect();
+    let candidates: Vec<&str> = source
+        .iter()
+        .filter(|e| e.kind == FileKind::File && dest_sizes.get(e.path.as_str()) == Some(&e.size))
+        .map(|e| e.path.as_str())
+        .collect();
+    if candidates.is_empty() {
+        return Ok(HashSet::new());
+    }
+
+    // Relative paths resolve against the login directory, like SFTP's
+    let remote_dir = match remote_root.starts_with('/') {
+        true => remote_root.to_string(),
+        false => sftp.realpath(remote_root)?,
+    };
+    let mut remote = HashMap::new();
+    for batch in candidates.chunks(CHECKSUM_BATCH) {
+        let paths: Vec<String> = batch.iter().map(|p| quote(p)).collect();
+        let script = format!(
+            "cd -- {} || exit 1\n\
+             if command -v sha256sum >/dev/null 2>&1; then sha256sum -- {paths}; \
+             else shasum -a 256 -- {paths}; fi\n",
+            quote(&remote_dir),
+            paths = paths.join(" ")
+        );
+        for line in ops::ssh_exec(target, &script)?.lines() {
+            // Names with unusual characters come back escaped; those are
+            // simply treated as different
+            if let Some((hash, path)) = line.split_once("  ") {
+                remote.insert(path.to_string(), hash.to_string());
+            }
+        }
+    }
+
+    Ok(candidates
+        .into_iter()
+        .filter(|path| {
+            let local = tree::local_path(local_root, path).ok().and_then(|p| local_sha256(&p));
+            local.is_none() || local.as_ref() != remote.get(*path)
+        })
+        .map(str::to_string)
+        .collect())
+}
+
+fn local_sha256(path: &Path) -> Option<String> {
+    let mut file = std::fs::File::open(path).ok()?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher).ok()?;
+    Some(format!("{:x}", hasher.finalize()))
+}
+
+/// Remove a local file, symlink or directory tree
+fn remove_local(path: &Path) -> Result<(), String> {
+    let meta = std::fs::symlink_metadata(path).map_err(|e| e.to_string())?;
+    let removed = match meta.is_dir() {
+        true => std::fs::remove_dir_all(path),
+        false => std::fs::remove_file(path),
+    };
+    removed.map_err(|e| format!("Cannot remove {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, kind: FileKind, size: u64, mtime: u64) -> TreeEntry {
+        TreeEntry {
+            path: path.to_string(),
+            kind,
+            size,
+            mode: Some(0o644),
+            atime: Some(mtime),
+            mtime: Some(mtime),
+            link_target: None,
+        }
+    }
+
+    #[test]
+    fn test_diff_by_size_and_mtime() {
+        let source = vec![
+            entry("data", FileKind::Dir, 0, 1),
+            entry("data/a.npy", FileKind::File, 10, 100),
+            entry("data/b.npy", FileKind::File, 10, 200),
+            entry("data/c.npy", FileKind::File, 5, 100),
+            entry("run.sh", FileKind::File, 3, 100),
+        ];
+        let dest = vec![
+            entry("data", FileKind::Dir, 0, 1),
+            entry("data/a.npy", FileKind::File, 10, 100),
+            entry("data/b.npy", FileKind::File, 10, 100),
+            entry("old", FileKind::Dir, 0, 1),
+            entry("old/x", FileKind::File, 1, 1),
+            entry("run.sh", FileKind::Dir, 0, 1),
+            // Goes with the directory run.sh replaces
+            entry("run.sh/log", FileKind::File, 1, 1),
+        ];
+        let (items, unchanged) = diff(&source, &dest, None, true);
+        let summary: Vec<(&str, SyncAction)> = items.iter().map(|i| (i.path.as_str(), i.action)).collect();
+        assert_eq!(
+            summary,
+            [
+                ("data/b.npy", SyncAction::Update),
+                ("data/c.npy", SyncAction::Create),
+                ("run.sh", SyncAction::Replace),
+                ("old", SyncAction::Delete),
+            ]
+        );
+        assert_eq!(unchanged, 1);
+
+        // By checksum, a differing mtime alone doesn't matter
+        let differing = HashSet::from(["data/a.npy".to_string()]);
+        let (items, _) = diff(&source, &dest, Some(&differing), false);
+        let paths: Vec<&str> = items.iter().map(|i| i.path.as_str()).collect();
+        assert_eq!(paths, ["data/a.npy", "data/c.npy", "run.sh"]);
+    }
+
+    #[test]
+    fn test_parse_itemized() {
+        let item = |path: &str, action, kind, size| SyncItem { path: path.to_string(), action, kind, size };
+        assert_eq!(
+            parse_itemized("<f+++++++++ 1024 data/new file.bin"),
+            Some(item("data/new file.bin", SyncAction::Create, FileKind::File, 1024))
+        );
+        assert_eq!(
+            parse_itemized(">f.st...... 77 run.sh"),
+            Some(item("run.sh", SyncAction::Update, FileKind::File, 77))
+        );
+        assert_eq!(
+            parse_itemized("cd+++++++++ 4096 logs/"),
+            Some(item("logs", SyncAction::Mkdir, FileKind::Dir, 0))
+        );
+        assert_eq!(
+            parse_itemized("cL+++++++++ 6 latest -> run-3"),
+            Some(item("latest", SyncAction::Link, FileKind::Symlink, 0))
+        );
+        assert_eq!(
+            parse_itemized("*deleting   0 old/"),
+            Some(item("old", SyncAction::Delete, FileKind::Dir, 0))
+        );
+        assert_eq!(parse_itemized(".d..t...... 4096 ./"), None);
+
+        let mut target = SshTarget::new("fe80::1", "lab", Some(2222));
+        target.identity_file = Some("/keys/my key".into());
+        let options = SyncOptions {
+            direction: Direction::Upload,
+            compare: Compare::Checksum,
+            delete: true,
+            include: vec!["*.py".into()],
+            exclude: vec!["__pycache__".into(), "logs/*.tmp".into()],
+            engine: SyncEngine::Rsync,
+            dry_run: true,
+        };
+        let args = rsync_args(&target, "/src/proj/", "proj", &options);
+        let tail: Vec<&str> = args[4..].iter().map(String::as_str).collect();
+        assert_eq!(
+            &tail[..9],
+            [
+                "--dry-run",
+                "--checksum",
+                "--delete",
+                "--exclude=__pycache__",
+                "--exclude=/logs/*.tmp",
+                "--include=*/",
+                "--include=*.py",
+                "--exclude=*",
+                "-e",
+            ]
+        );
+        assert!(tail[9].contains("-i \"/keys/my key\" -p 2222"));
+        assert_eq!(&tail[10..], ["/src/proj/", "lab@[fe80::1]:proj/"]);
+    }
+}
@@ -0,0 +1,639 @@
+//! Recursive transfers of directory trees, filtered by glob rules.
+//!
+//! A transfer first scans the source into a `TreePlan`, which is also what a
+//! dry run returns. Symlinks are recreated rather than followed, and
+//! permissions and access/modification times are copied once the data is in
+//! place. Directories get their times last, since filling them changes them.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::Emitter;
+
+use super::ops::{self, join_remote, FileKind};
+use super::queue::{Direction, JobStatus, Throughput, TransferProgress};
+use super::sftp::{FileAttrs, SftpSession};
+use crate::audit::{self, AuditAction};
+use crate::ssh_target::SshTarget;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TreeOptions {
+    /// Only files matching one of these are transferred; all when empty
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// Files and directories matching one of these are skipped
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// Only scan and return the plan
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Include/exclude rules. A pattern without `/` matches a name at any depth
+/// (`__pycache__`, `*.ckpt`); one with `/` matches the path from the tree
+/// root (`logs/**/*.txt`). `*` and `?` stop at `/`, `**` does not.
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    include: Vec<String>,
+    exclude: Vec<String>,
+}
+
+impl Filter {
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self, String> {
+        let clean = |patterns: &[String]| -> Result<Vec<String>, String> {
+            patterns
+                .iter()
+                .map(|p| p.trim().trim_matches('/').to_string())
+                .map(|p| match p.is_empty() {
+                    true => Err("Empty filter pattern".to_string()),
+                    false => Ok(p),
+                })
+                .collect()
+        };
+        Ok(Self { include: clean(include)?, exclude: clean(exclude)? })
+    }
+
+    fn excluded(&self, path: &str) -> bool {
+        self.exclude.iter().any(|p| pattern_matches(p, path))
+    }
+
+    /// Whether to transfer the file (or symlink) at `path`
+    pub fn wants_file(&self, path: &str) -> bool {
+        !self.excluded(path)
+            && (self.include.is_empty() || self.include.iter().any(|p| pattern_matches(p, path)))
+    }
+
+    /// Whether to descend into the directory at `path`
+    pub fn wants_dir(&self, path: &str) -> bool {
+        !self.excluded(path)
+    }
+}
+
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let subject = match pattern.contains('/') {
+        true => path,
+        false => path.rsplit('/').next().unwrap_or(path),
+    };
+    let pattern: Vec<char> = pattern.chars().collect();
+    let subject: Vec<char> = subject.chars().collect();
+    glob_match(&pattern, &subject)
+}
+
+fn glob_match(pattern: &[char], text: &[char]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some('*') if pattern.get(1) == Some(&'*') => {
+            let rest = &pattern[2..];
+            // `**/` also matches no directories at all
+            if rest.first() == Some(&'/') && glob_match(&rest[1..], text) {
+                return true;
+            }
+            (0..=text.len()).any(|i| glob_match(rest, &text[i..]))
+        }
+        Some('*') => (0..=text.len())
+            .take_while(|&i| i == 0 || text[i - 1] != '/')
+            .any(|i| glob_match(&pattern[1..], &text[i..])),
+        Some('?') => text.first().is_some_and(|&c| c != '/') && glob_match(&pattern[1..], &text[1..]),
+        Some(c) => text.first() == Some(c) && glob_match(&pattern[1..], &text[1..]),
+    }
+}
+
+/// One file, directory or symlink of a tree
+#[derive(Debug, Clone, Serialize)]
+pub struct TreeEntry {
+    /// Relative to the tree root, `/`-separated
+    pub path: String,
+    pub kind: FileKind,
+    pub size: u64,
+    /// Permission bits, without the file type
+    pub mode: Option<u32>,
+    /// Unix time in seconds
+    pub atime: Option<u64>,
+    pub mtime: Option<u64>,
+    pub link_target: Option<String>,
+}
+
+/// What a tree transfer copies (or would copy, for a dry run)
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TreePlan {
+    /// Parents before their contents
+    pub entries: Vec<TreeEntry>,
+    /// Paths left out by the filters, and special files that can't be copied
+    pub skipped: Vec<String>,
+    pub files: usize,
+    pub dirs: usize,
+    pub symlinks: usize,
+    pub bytes: u64,
+    /// Attributes or links that could not be applied; the data was copied
+    pub warnings: Vec<String>,
+}
+
+impl TreePlan {
+    fn add(&mut self, entry: TreeEntry) {
+        match entry.kind {
+            FileKind::File => {
+                self.files += 1;
+                self.bytes += entry.size;
+            }
+            FileKind::Dir => self.dirs += 1,
+            FileKind::Symlink => self.symlinks += 1,
+            FileKind::Other => {}
+        }
+        self.entries.push(entry);
+    }
+}
+
+fn relative(parent: &str, name: &str) -> String {
+    if parent.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", parent, name)
+    }
+}
+
+pub(super) fn epoch_secs(time: std::io::Result<SystemTime>) -> Option<u64> {
+    time.ok()?.duration_since(UNIX_EPOCH).ok().map(|d| d.as_secs())
+}
+
+/// Scan a local directory tree without following symlinks
+pub fn scan_local(root: &Path, filter: &Filter) -> Result<TreePlan, String> {
+    let meta = std::fs::metadata(root).map_err(|e| format!("Cannot read {}: {}", root.display(), e))?;
+    if !meta.is_dir() {
+        return Err(format!("Not a directory: {}", root.display()));
+    }
+    let mut plan = TreePlan::default();
+    scan_local_dir(root, "", filter, &mut plan)?;
+    Ok(plan)
+}
+
+fn scan_local_dir(dir: &Path, rel: &str, filter: &Filter, plan: &mut TreePlan) -> Result<(), String> {
+    let mut names: Vec<_> = std::fs::read_dir(dir)
+        .map_err(|e| format!("Cannot list {}: {}", dir.display(), e))?
+        .filter_map(|e| e.ok())
+        .map(|e| e.file_name())
+        .collect();
+    names.sort();
+    for name in names {
+        let path = dir.join(&name);
+        let rel = relative(rel, &name.to_string_lossy());
+        let meta = std::fs::symlink_metadata(&path)
+            .map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+        let kind = if meta.is_dir() {
+            FileKind::Dir
+        } else if meta.file_type().is_symlink() {
+            FileKind::Symlink
+        } else if meta.is_file() {
+            FileKind::File
+        } else {
+            FileKind::Other
+        };
+        let wanted = match kind {
+            FileKind::Dir => filter.wants_dir(&rel),
+            FileKind::File | FileKind::Symlink => filter.wants_file(&rel),
+            FileKind::Other => false,
+        };
+        if !wanted {
+            plan.skipped.push(rel);
+            continue;
+        }
+        plan.add(TreeEntry {
+            path: rel.clone(),
+            kind,
+            size: if kind == FileKind::File { meta.len() } else { 0 },
+            mode: (kind != FileKind::Symlink).then(|| ops::local_mode(&meta)),
+            atime: epoch_secs(meta.accessed()),
+            mtime: epoch_secs(meta.modified()),
+            link_target: match kind {
+                FileKind::Symlink => std::fs::read_link(&path)
+                    .ok()
+                    .map(|t| t.to_string_lossy().to_string()),
+                _ => None,
+            },
+        });
+        if kind == FileKind::Dir {
+            scan_local_dir(&path, &rel, filter, plan)?;
+        }
+    }
+    Ok(())
+}
+
+/// Scan a remote directory tree without following symlinks
+pub fn scan_remote(sftp: &mut SftpSession, root: &str, filter: &Filter) -> Result<TreePlan, String> {
+    if !sftp.stat(root)?.is_dir() {
+        return Err(format!("Not a directory: {}", root));
+    }
+    let mut plan = TreePlan::default();
+    scan_remote_dir(sftp, root, "", filter, &mut plan)?;
+    Ok(plan)
+}
+
+fn scan_remote_dir(
+    sftp: &mut SftpSession,
+    dir: &str,
+    rel: &str,
+    filter: &Filter,
+    plan: &mut TreePlan,
+) -> Result<(), String> {
+    let mut entries = sftp.read_dir(dir)?;
+    entries.retain(|e| e.name != "." && e.name != "..");
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    // Names come from the server and become local paths on download, so a
+    // hostile one must not reach outside the target directory. A name listed
+    // twice could be a symlink first and a directory to write through second.
+    for (i, entry) in entries.iter().enumerate() {
+        check_entry_name(&entry.name).map_err(|e| format!("{}: {}", dir, e))?;
+        if i > 0 && entries[i - 1].name == entry.name {
+            return Err(format!("{}: '{}' is listed twice", dir, entry.name));
+        }
+    }
+    for entry in entries {
+        let path = join_remote(dir, &entry.name);
+        let rel = relative(rel, &entry.name);
+        let attrs = &entry.attrs;
+        let kind = if attrs.is_dir() {
+            FileKind::Dir
+        } else if attrs.is_symlink() {
+            FileKind::Symlink
+        } else if attrs.is_file() {
+            FileKind::File
+        } else {
+            FileKind::Other
+        };
+        let wanted = match kind {
+            FileKind::Dir => filter.wants_dir(&rel),
+            FileKind::File | FileKind::Symlink => filter.wants_file(&rel),
+            FileKind::Other => false,
+        };
+        if !wanted {
+            plan.skipped.push(rel);
+            continue;
+        }
+        plan.add(TreeEntry {
+            path: rel.clone(),
+            kind,
+            size: if kind == FileKind::File { attrs.size.unwrap_or(0) } else { 0 },
+            mode: attrs.permissions.filter(|_| kind != FileKind::Symlink).map(|p| p & 0o7777),
+            atime: attrs.atime.map(u64::from),
+            mtime: attrs.mtime.map(u64::from),
+            link_target: match kind {
+                FileKind::Symlink => Some(sftp.readlink(&path)?),
+                _ => None,
+            },
+        });
+        if kind == FileKind::Dir {
+            scan_remote_dir(sftp, &path, &rel, filter, plan)?;
+        }
+    }
+    Ok(())
+}
+
+/// `file-transfer-progress` events for a whole tree, by bytes copied
+pub(super) struct Reporter<'a> {
+    app: &'a tauri::AppHandle,
+    id: String,
+    direction: Direction,
+    total: u64,
+    /// Bytes of the files already finished
+    finished: u64,
+    rate: Throughput,
+}
+
+impl<'a> Reporter<'a> {
+    pub(super) fn new(app: &'a tauri::AppHandle, direction: Direction, total: u64) -> Self {
+        Self {
+            app,
+            id: uuid::Uuid::new_v4().to_string(),
+            direction,
+            total,
+            finished: 0,
+            rate: Throughput::new(0),
+        }
+    }
+
+    fn update(&mut self, filename: &str, done: u64) {
+        let bytes = self.finished + done;
+        if self.rate.update(bytes) {
+            self.emit(filename, bytes, JobStatus::Running);
+        }
+    }
+
+    fn file_done(&mut self, size: u64) {
+        self.finished += size;
+    }
+
+    pub(super) fn emit(&self, filename: &str, bytes: u64, status: JobStatus) {
+        let bytes_per_sec = self.rate.bytes_per_sec;
+        let percent = match status {
+            JobStatus::Completed => 100,
+            _ => (bytes.min(self.total) * 100).checked_div(self.total).unwrap_or(0).min(99),
+        };
+        let progress = TransferProgress {
+            id: self.id.clone(),
+            filename: filename.to_string(),
+            direction: self.direction,
+            status,
+            bytes,
+            total: self.total,
+            percent,
+            bytes_per_sec: bytes_per_sec as u64,
+            eta_secs: (bytes_per_sec > 0.0)
+                .then(|| (self.total.saturating_sub(bytes) as f64 / bytes_per_sec).ceil() as u64),
+        };
+        self.app.emit("file-transfer-progress", progress).ok();
+    }
+}
+
+/// Copy a local directory tree into `remote_root`, creating it if needed
+pub fn upload_tree(
+    target: &SshTarget,
+    local_root: &str,
+    remote_root: &str,
+    options: &TreeOptions,
+    app: &tauri::AppHandle,
+) -> Result<TreePlan, String> {
+    let filter = Filter::new(&options.include, &options.exclude)?;
+    let mut plan = scan_local(Path::new(local_root), &filter)?;
+    if options.dry_run {
+        return Ok(plan);
+    }
+    plan.warnings = audit::track(AuditAction::SftpUpload, target, Some(remote_root), || {
+        log::info!("Uploading tree {} to {} ({} files)", local_root, remote_root, plan.files);
+        let mut sftp = SftpSession::connect(target)?;
+        ops::make_dir_all(&mut sftp, remote_root)?;
+        let mut reporter = Reporter::new(app, Direction::Upload, plan.bytes);
+        let warnings = push_entries(&mut sftp, local_root, remote_root, &plan.entries, &mut reporter)?;
+        reporter.emit(local_root, plan.bytes, JobStatus::Completed);
+        log::info!("Tree upload complete: {}", remote_root);
+        Ok(warnings)
+    })?;
+    Ok(plan)
+}
+
+/// Copy a remote directory tree into `local_root`, creating it if needed
+pub fn download_tree(
+    target: &SshTarget,
+    remote_root: &str,
+    local_root: &str,
+    options: &TreeOptions,
+    app: &tauri::AppHandle,
+) -> Result<TreePlan, String> {
+    let filter = Filter::new(&options.include, &options.exclude)?;
+    let mut sftp = SftpSession::connect(target)?;
+    let mut plan = scan_remote(&mut sftp, remote_root, &filter)?;
+    if options.dry_run {
+        return Ok(plan);
+    }
+    plan.warnings = audit::track(AuditAction::SftpDownload, target, Some(remote_root), || {
+        log::info!("Downloading tree {} to {} ({} files)", remote_root, local_root, plan.files);
+        std::fs::create_dir_all(local_root)
+            .map_err(|e| format!("Cannot create {}: {}", local_root, e))?;
+        let mut reporter = Reporter::new(app, Direction::Download, plan.bytes);
+        let warnings = pull_entries(&mut sftp, remote_root, local_root, &plan.entries, &mut reporter)?;
+        reporter.emit(remote_root, plan.bytes, JobStatus::Completed);
+        log::info!("Tree download complete: {}", local_root);
+        Ok(warnings)
+    })?;
+    Ok(plan)
+}
+
+/// Upload `entries` of the tree at `local_root` into the existing
+/// `remote_root`. Returns the attributes or links that could not be applied.
+pub(super) fn push_entries(
+    sftp: &mut SftpSession,
+    local_root: &str,
+    remote_root: &str,
+    entries: &[TreeEntry],
+    reporter: &mut Reporter,
+) -> Result<Vec<String>, String> {
+    let mut warnings = Vec::new();
+    for entry in entries {
+        let remote = join_remote(remote_root, &entry.path);
+        match entry.kind {
+            FileKind::Dir => {
+                if !sftp.try_lstat(&remote)?.is_some_and(|a| a.is_dir()) {
+                    sftp.mkdir(&remote, None)?;
+                }
+            }
+            FileKind::File => {
+                let local = local_path(local_root, &entry.path)?;
+                ops::upload(sftp, &local.to_string_lossy(), &remote, 0, |done, _| {
+                    reporter.update(&entry.path, done);
+                    true
+                })?;
+                reporter.file_done(entry.size);
+                if let Err(e) = sftp.setstat(&remote, &remote_attrs(entry)) {
+                    warnings.push(format!("{}: {}", entry.path, e));
+                }
+            }
+            FileKind::Symlink => {
+                let link_target = entry.link_target.as_deref().unwrap_or_default();
+                let linked = match sftp.try_lstat(&remote)? {
+                    Some(existing) if existing.is_dir() => Err("a directory is in the way".to_string()),
+                    Some(_) => sftp.remove(&remote).and_then(|_| sftp.symlink(&remote, link_target)),
+                    None => sftp.symlink(&remote, link_target),
+                };
+                if let Err(e) = linked {
+                    warnings.push(format!("{}: symlink not created: {}", entry.path, e));
+                }
+            }
+            FileKind::Other => {}
+        }
+    }
+    for entry in entries.iter().rev().filter(|e| e.kind == FileKind::Dir) {
+        if let Err(e) = sftp.setstat(&join_remote(remote_root, &entry.path), &remote_attrs(entry)) {
+            warnings.push(format!("{}: {}", entry.path, e));
+        }
+    }
+    Ok(warnings)
+}
+
+/// Download `entries` of the tree at `remote_root` into the existing
+/// `local_root`, like `push_entries`
+pub(super) fn pull_entries(
+    sftp: &mut SftpSession,
+    remote_root: &str,
+    local_root: &str,
+    entries: &[TreeEntry],
+    reporter: &mut Reporter,
+) -> Result<Vec<String>, String> {
+    let mut warnings = Vec::new();
+    for entry in entries {
+        let local = local_path(local_root, &entry.path)?;
+        match entry.kind {
+            FileKind::Dir => std::fs::create_dir_all(&local)
+                .map_err(|e| format!("Cannot create {}: {}", local.display(), e))?,
+            FileKind::File => {
+                let remote = join_remote(remote_root, &entry.path);
+                ops::download(sftp, &remote, &local.to_string_lossy(), 0, |done, _| {
+                    reporter.update(&entry.path, done);
+                    true
+                })?;
+                reporter.file_done(entry.size);
+                if let Err(e) = set_local_attrs(&local, entry) {
+                    warnings.push(format!("{}: {}", entry.path, e));
+                }
+            }
+            FileKind::Symlink => {
+                let link_target = entry.link_target.as_deref().unwrap_or_default();
+                if let Err(e) = make_local_symlink(&local, link_target) {
+                    warnings.push(format!("{}: symlink not created: {}", entry.path, e));
+                }
+            }
+            FileKind::Other => {}
+        }
+    }
+    for entry in entries.iter().rev().filter(|e| e.kind == FileKind::Dir) {
+        let attrs = local_path(local_root, &entry.path).and_then(|local| set_local_attrs(&local, entry));
+        if let Err(e) = attrs {
+            warnings.push(format!("{}: {}", entry.path, e));
+        }
+    }
+    Ok(warnings)
+}
+
+/// A single directory entry name from the remote: not empty, `.` or `..`,
+/// and without separators or NUL
+fn check_entry_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\', '\0']) {
+        return Err(format!("refusing unsafe file name {:?}", name));
+    }
+    Ok(())
+}
+
+/// `root` joined with a `/`-separated relative path. Fails for a path that
+/// would lead outside `root`.
+pub(super) fn local_path(root: &str, rel: &str) -> Result<PathBuf, String> {
+    let mut path = PathBuf::from(root);
+    for part in rel.split('/') {
+        check_entry_name(part).map_err(|e| format!("{}: {}", rel, e))?;
+        // Also rules out drive prefixes and roots on Windows
+        let mut components = Path::new(part).components();
+        match (components.next(), components.next()) {
+            (Some(std::path::Component::Normal(_)), None) => path.push(part),
+            _ => return Err(format!("{}: refusing unsafe file name {:?}", rel, part)),
+        }
+    }
+    Ok(path)
+}
+
+/// Mode and times of `entry` for SETSTAT; times are only sent together
+fn remote_attrs(entry: &TreeEntry) -> FileAttrs {
+    let (atime, mtime) = match (entry.atime.or(entry.mtime), entry.mtime) {
+        (Some(atime), Some(mtime)) => (Some(atime as u32), Some(mtime as u32)),
+        _ => (None, None),
+    };
+    FileAttrs { permissions: entry.mode, atime, mtime, ..FileAttrs::default() }
+}
+
+/// Copy `entry`'s times, then its mode (which may drop our own read access)
+fn set_local_attrs(path: &Path, entry: &TreeEntry) -> Result<(), String> {
+    if let Some(mtime) = entry.mtime {
+        let at = |secs: u64| UNIX_EPOCH + std::time::Duration::from_secs(secs);
+        let times = std::fs::FileTimes::new()
+            .set_modified(at(mtime))
+            .set_accessed(at(entry.atime.unwrap_or(mtime)));
+        std::fs::File::open(path)
+            .and_then(|f| f.set_times(times))
+            .map_err(|e| format!("Cannot set times: {}", e))?;
+    }
+    if let Some(mode) = entry.mode {
+        std::fs::set_permissions(path, local_permissions(path, mode)?)
+            .map_err(|e| format!("Cannot set permissions: {}", e))?;
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn local_permissions(_path: &Path, mode: u32) -> Result<std::fs::Permissions, String> {
+    use std::os::unix::fs::PermissionsExt;
+    Ok(std::fs::Permissions::from_mode(mode))
+}
+
+#[cfg(not(unix))]
+fn local_permissions(path: &Path, mode: u32) -> Result<std::fs::Permissions, String> {
+    let mut permissions = std::fs::metadata(path)
+        .map_err(|e| format!("Cannot read {}: {}", path.display(), e))?
+        .permissions();
+    permissions.set_readonly(mode & 0o200 == 0);
+    Ok(permissions)
+}
+
+/// Create a symlink, replacing a file or link already at `path`
+fn make_local_symlink(path: &Path, target: &str) -> Result<(), String> {
+    if let Ok(existing) = std::fs::symlink_metadata(path) {
+        if existing.is_dir() {
+            return Err("a directory is in the way".to_string());
+        }
+        std::fs::remove_file(path).map_err(|e| e.to_string())?;
+    }
+    #[cfg(unix)]
+    return std::os::unix::fs::symlink(target, path).map_err(|e| e.to_string());
+    #[cfg(not(unix))]
+    return Err(format!("symlinks are not supported here (-> {})", target));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(include: &[&str], exclude: &[&str]) -> Filter {
+        let strings = |p: &[&str]| p.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        Filter::new(&strings(include), &strings(exclude)).unwrap()
+    }
+
+    #[test]
+    fn test_filter_patterns() {
+        let f = filter(&[], &["__pycache__", "*.ckpt", "logs/**/*.tmp"]);
+        assert!(!f.wants_dir("src/__pycache__"));
+        assert!(!f.wants_file("runs/1/model.ckpt"));
+        assert!(!f.wants_file("logs/a/b/x.tmp"));
+        assert!(!f.wants_file("logs/x.tmp"));
+        assert!(f.wants_file("data/x.tmp"));
+        assert!(f.wants_dir("src"));
+
+        let f = filter(&["*.py", "configs/*.yaml"], &["test_*"]);
+        assert!(f.wants_file("pkg/train.py"));
+        assert!(!f.wants_file("pkg/test_train.py"));
+        assert!(f.wants_file("configs/base.yaml"));
+        assert!(!f.wants_file("configs/old/base.yaml"));
+        assert!(!f.wants_file("README.md"));
+        // Include rules don't stop directories from being searched
+        assert!(f.wants_dir("pkg"));
+    }
+
+    #[test]
+    fn test_scan_local_skips_and_orders() {
+        let root = std::env::temp_dir().join(format!("remotelab-tree-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(root.join("b/__pycache__")).unwrap();
+        std::fs::write(root.join("a.txt"), "hello").unwrap();
+        std::fs::write(root.join("b/c.py"), "print()").unwrap();
+        std::fs::write(root.join("b/__pycache__/c.pyc"), "x").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink("a.txt", root.join("link")).unwrap();
+
+        let plan = scan_local(&root, &filter(&[], &["__pycache__"])).unwrap();
+        let paths: Vec<&str> = plan.entries.iter().map(|e| e.path.as_str()).collect();
+        #[cfg(unix)]
+        {
+            assert_eq!(paths, ["a.txt", "b", "b/c.py", "link"]);
+            assert_eq!(plan.entries[3].link_target.as_deref(), Some("a.txt"));
+            assert_eq!(plan.symlinks, 1);
+        }
+        #[cfg(not(unix))]
+        assert_eq!(paths, ["a.txt", "b", "b/c.py"]);
+        assert_eq!(plan.skipped, ["b/__pycache__"]);
+        assert_eq!((plan.files, plan.dirs, plan.bytes), (2, 1, 12));
+        std::fs::remove_dir_all(root).ok();
+    }
+
+    #[test]
+    fn test_local_path_stays_under_root() {
+        assert_eq!(local_path("/dl", "a/b.txt").unwrap(), Path::new("/dl/a/b.txt"));
+        for hostile in ["../../.bashrc", "a/../../x", "a//b", ".", "a/./b", "a\\..\\x", "a\0b", ""] {
+            assert!(local_path("/dl", hostile).is_err(), "{:?}", hostile);
+        }
+        assert!(check_entry_name("notes.txt").is_ok());
+        assert!(check_entry_name("..").is_err());
+        assert!(check_entry_name("x/y").is_err());
+    }
+}
@@ -0,0 +1,229 @@
+pub mod probe;
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::{Notify, Semaphore};
+
+use crate::config::{ConfigState, Device};
+use crate::ssh_target::SshTarget;
+use probe::ProbeResult;
+
+/// Time between probe rounds
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Upper bound on probes in flight, so a large lab doesn't spawn hundreds
+/// of ping processes at once
+const MAX_CONCURRENT_PROBES: usize = 32;
+
+/// Last known reachability of a device. Also the `device-status` event payload.
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceHealth {
+    pub device_id: String,
+    pub online: bool,
+    /// Round-trip time of the last successful probe
+    pub latency_ms: Option<f64>,
+    /// Why the last probe failed, if it did
+    pub error: Option<String>,
+    /// Unix time in milliseconds of the last probe
+    pub checked_at_ms: u64,
+    /// Unix time in milliseconds of the last online/offline transition
+    pub changed_at_ms: u64,
+}
+
+/// Probes every device on a schedule and caches the results, so listing
+/// devices never waits on the network
+pub struct HealthMonitor {
+    cache: Mutex<HashMap<String, DeviceHealth>>,
+    interval: Mutex<Duration>,
+    wake: Notify,
+}
+
+impl Default for HealthMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HealthMonitor {
+    pub fn new() -> Self {
+        Self {
+            cache: Mutex::new(HashMap::new()),
+            interval: Mutex::new(DEFAULT_INTERVAL),
+            wake: Notify::new(),
+        }
+    }
+
+    /// Start the background probe loop. Call once, after the monitor and
+    /// `ConfigState` are managed.
+    pub fn start(app: AppHandle) {
+        tauri::async_runtime::spawn(async move {
+            loop {
+                let monitor = app.state::<HealthMonitor>();
+                monitor.probe_all(&app).await;
+                let interval = *monitor.interval.lock().unwrap();
+                tokio::select! {
+                    _ = tokio::time::sleep(interval) => {}
+                    _ = monitor.wake.notified() => {}
+                }
+            }
+        });
+    }
+
+    /// Cut the current wait short, e.g. after a device was added or edited
+    pub fn refresh(&self) {
+        self.wake.notify_one();
+    }
+
+    pub fn set_interval(&self, interval: Duration) {
+        *self.interval.lock().unwrap() = interval;
+        self.refresh();
+    }
+
+    pub fn get(&self, device_id: &str) -> Option<DeviceHealth> {
+        self.cache.lock().unwrap().get(device_id).cloned()
+    }
+
+    /// Probe all configured devices concurrently, update the cache and emit
+    /// `device-status` for every device whose reachability changed
+    pub async fn probe_all(&self, app: &AppHandle) -> Vec<DeviceHealth> {
+        // Each with its SSH route, for devices reached through jump hosts
+        let devices: Vec<(Device, Option<SshTarget>)> = {
+            let cfg = app.state::<ConfigState>();
+            let cfg = cfg.0.lock().unwrap();
+            cfg.devices
+                .iter()
+                .map(|d| (cfg.effective_device(d).0, cfg.device_target(&d.id).ok()))
+                .collect()
+        };
+
+        let limit = Arc::new(Semaphore::new(MAX_CONCURRENT_PROBES));
+        let mut probes = tokio::task::JoinSet::new();
+        for (device, route) in devices.iter().cloned() {
+            let limit = limit.clone();
+            probes.spawn(async move {
+                let _permit = limit.acquire_owned().await;
+                let result = probe::probe_device(&device, route.as_ref()).await;
+                (device.id, result)
+            });
+        }
+
+        let mut results = Vec::new();
+        while let Some(joined) = probes.join_next().await {
+            match joined {
+                Ok(result) => results.push(result),
+                Err(e) => log::warn!("Health probe task failed: {}", e),
+            }
+        }
+
+        let now = now_ms();
+        let mut changed = Vec::new();
+        let snapshot = {
+            let mut cache = self.cache.lock().unwrap();
+            cache.retain(|id, _| devices.iter().any(|(d, _)| &d.id == id));
+            for (id, result) in results {
+                if let Some(health) = apply_probe(&mut cache, id, result, now) {
+                    changed.push(health);
+                }
+            }
+            cache.values().cloned().collect()
+        };
+
+        for health in &changed {
+            announce(app, health);
+        }
+        snapshot
+    }
+
+    /// Probe a single device right away, updating the cache like a
+    /// scheduled round would. `route` is how SSH reaches it, see
+    /// `probe::probe_device`.
+    pub async fn check_device(
+        &self,
+        app: &AppHandle,
+        device: &Device,
+        route: Option<&SshTarget>,
+    ) -> DeviceHealth {
+        let result = probe::probe_device(device, route).await;
+        let (health, changed) = {
+            let mut cache = self.cache.lock().unwrap();
+            let changed = apply_probe(&mut cache, device.id.clone(), result, now_ms());
+            (cache[&device.id].clone(), changed.is_some())
+        };
+        if changed {
+            announce(app, &health);
+        }
+        health
+    }
+}
+
+fn announce(app: &AppHandle, health: &DeviceHealth) {
+    log::info!(
+        "Device {} is now {}",
+        health.device_id,
+        if health.online { "online" } else { "offline" }
+    );
+    let _ = app.emit("device-status", health);
+}
+
+/// Record a probe result. Returns the new entry if the device changed state
+/// (including the first time it is seen).
+fn apply_probe(
+    cache: &mut HashMap<String, DeviceHealth>,
+    device_id: String,
+    result: ProbeResult,
+    now: u64,
+) -> Option<DeviceHealth> {
+    let previous = cache.get(&device_id);
+    let changed = previous.is_none_or(|p| p.online != result.online);
+    let changed_at_ms = match previous {
+        Some(p) if !changed => p.changed_at_ms,
+        _ => now,
+    };
+    let health = DeviceHealth {
+        device_id: device_id.clone(),
+        online: result.online,
+        latency_ms: result.latency_ms,
+        error: result.error,
+        checked_at_ms: now,
+        changed_at_ms,
+    };
+    cache.insert(device_id, health.clone());
+    changed.then_some(health)
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(online: bool) -> ProbeResult {
+        ProbeResult {
+            online,
+            latency_ms: online.then_some(1.5),
+            error: (!online).then(|| "No reply".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_only_transitions_are_reported() {
+        let mut cache = HashMap::new();
+        assert!(apply_probe(&mut cache, "a".into(), result(true), 1000).is_some());
+        assert!(apply_probe(&mut cache, "a".into(), result(true), 2000).is_none());
+        assert_eq!(cache["a"].checked_at_ms, 2000);
+        assert_eq!(cache["a"].changed_at_ms, 1000);
+
+        let down = apply_probe(&mut cache, "a".into(), result(false), 3000).unwrap();
+        assert!(!down.online);
+        assert_eq!(down.changed_at_ms, 3000);
+        assert_eq!(down.latency_ms, None);
+    }
+}
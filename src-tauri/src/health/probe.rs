@@ -0,0 +1,174 @@
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+use tokio::process::Command;
+
+use crate::config::Device;
+use crate::filetransfer::ops;
+use crate::ssh_target::SshTarget;
+
+/// How long a single probe may take before the host counts as offline
+pub const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+/// Same for a probe through jump hosts, which connects to every hop
+pub const CHAIN_PROBE_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Outcome of probing one device
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProbeResult {
+    pub online: bool,
+    pub latency_ms: Option<f64>,
+    pub error: Option<String>,
+}
+
+impl ProbeResult {
+    fn offline(error: String) -> Self {
+        Self {
+            online: false,
+            latency_ms: None,
+            error: Some(error),
+        }
+    }
+}
+
+/// Probe a device the way it is reached: devices behind jump hosts with an
+/// SSH connection through `route`, devices with an SSH host or port override
+/// with a TCP connect, VPN peers with ICMP ping
+pub async fn probe_device(device: &Device, route: Option<&SshTarget>) -> ProbeResult {
+    if let Some(route) = route.filter(|r| !r.jumps.is_empty()) {
+        probe_ssh(route).await
+    } else if device.ssh_port.is_some() || device.ssh_host.is_some() {
+        probe_tcp(device.ssh_address(), device.ssh_port.unwrap_or(22)).await
+    } else {
+        probe_ping(&device.vpn_ip).await
+    }
+}
+
+pub async fn probe_tcp(host: &str, port: u16) -> ProbeResult {
+    let start = Instant::now();
+    match tokio::time::timeout(PROBE_TIMEOUT, TcpStream::connect((host, port))).await {
+        Ok(Ok(_)) => ProbeResult {
+            online: true,
+            latency_ms: Some(start.elapsed().as_secs_f64() * 1000.0),
+            error: None,
+        },
+        Ok(Err(e)) => ProbeResult::offline(e.to_string()),
+        Err(_) => ProbeResult::offline("Connection timed out".to_string()),
+    }
+}
+
+/// Connect with ssh (through the target's jump chain) and run `true`.
+/// Never prompts; the target turning down our credentials still means it is up.
+pub async fn probe_ssh(target: &SshTarget) -> ProbeResult {
+    let mut args = vec![
+        "-o".to_string(),
+        "BatchMode=yes".to_string(),
+        "-o".to_string(),
+        "ConnectTimeout=5".to_string(),
+    ];
+    args.extend(ops::ssh_connect_args(target));
+    args.push(target.destination());
+    args.push("true".to_string());
+
+    let start = Instant::now();
+    let output = Command::new(ops::ssh_bin())
+        .args(&args)
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    match tokio::time::timeout(CHAIN_PROBE_TIMEOUT, output).await {
+        Ok(Ok(out)) => {
+            let stderr = String::from_utf8_lossy(&out.stderr);
+            if out.status.success() || target_refused_auth(&stderr, target) {
+                ProbeResult {
+                    online: true,
+                    latency_ms: Some(start.elapsed().as_secs_f64() * 1000.0),
+                    error: None,
+                }
+            } else {
+                let reason = stderr.lines().map(str::trim).rfind(|l| !l.is_empty());
+                ProbeResult::offline(reason.unwrap_or("SSH connection failed").to_string())
+            }
+        }
+        Ok(Err(e)) => ProbeResult::offline(format!("Failed to run ssh: {}", e)),
+        Err(_) => ProbeResult::offline("SSH probe timed out".to_string()),
+    }
+}
+
+/// Whether ssh got as far as the target itself, which then rejected the
+/// login. A jump host rejecting us names the jump host instead.
+fn target_refused_auth(stderr: &str, target: &SshTarget) -> bool {
+    let host = target.host.trim_start_matches('[').trim_end_matches(']');
+    stderr.contains(&format!("{}@{}: Permission denied", target.user, host))
+}
+
+pub async fn probe_ping(ip: &str) -> ProbeResult {
+    #[cfg(unix)]
+    let args = ["-c", "1", "-W", "2", ip];
+    #[cfg(windows)]
+    let args = ["-n", "1", "-w", "2000", ip];
+
+    let start = Instant::now();
+    let output = Command::new("ping").args(args).kill_on_drop(true).output();
+    match tokio::time::timeout(PROBE_TIMEOUT, output).await {
+        Ok(Ok(out)) if out.status.success() => {
+            let stdout = String::from_utf8_lossy(&out.stdout);
+            ProbeResult {
+                online: true,
+                // Process startup would otherwise dominate the number
+                latency_ms: parse_ping_time(&stdout)
+                    .or_else(|| Some(start.elapsed().as_secs_f64() * 1000.0)),
+                error: None,
+            }
+        }
+        Ok(Ok(_)) => ProbeResult::offline("No reply".to_string()),
+        Ok(Err(e)) => ProbeResult::offline(format!("Failed to run ping: {}", e)),
+        Err(_) => ProbeResult::offline("Ping timed out".to_string()),
+    }
+}
+
+/// Round-trip time from ping output (`time=0.42 ms`, `time<1ms`)
+pub fn parse_ping_time(output: &str) -> Option<f64> {
+    let idx = output.find("time=").map(|i| i + 5).or_else(|| output.find("time<").map(|i| i + 5))?;
+    let number: String = output[idx..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '.')
+        .collect();
+    number.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ping_time() {
+        let linux = "64 bytes from 10.0.0.2: icmp_seq=1 ttl=64 time=0.421 ms";
+        assert_eq!(parse_ping_time(linux), Some(0.421));
+        let windows = "Reply from 10.0.0.2: bytes=32 time<1ms TTL=128";
+        assert_eq!(parse_ping_time(windows), Some(1.0));
+        assert_eq!(parse_ping_time("Request timed out."), None);
+    }
+
+    #[test]
+    fn test_auth_refusal_by_target_counts_as_reachable() {
+        let target = SshTarget::new("10.0.1.5", "lab", None);
+        assert!(target_refused_auth("lab@10.0.1.5: Permission denied (publickey).\n", &target));
+        // The bastion turned us away; the target was never reached
+        assert!(!target_refused_auth("ops@bastion: Permission denied (publickey).\n", &target));
+        assert!(!target_refused_auth("ssh: connect to host 10.0.1.5 port 22: No route to host", &target));
+    }
+
+    #[tokio::test]
+    async fn test_probe_tcp() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let up = probe_tcp("127.0.0.1", port).await;
+        assert!(up.online);
+        assert!(up.latency_ms.is_some());
+
+        drop(listener);
+        let down = probe_tcp("127.0.0.1", port).await;
+        assert!(!down.online);
+        assert!(down.error.is_some());
+    }
+}
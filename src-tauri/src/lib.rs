@@ -1,12 +1,17 @@
 mod commands;
+mod audit;
 mod config;
 mod crypto;
+mod ssh_target;
 mod tray;
 mod vpn;
+mod wol;
 mod terminal;
 mod desktop;
 mod filetransfer;
+mod health;
 mod sshkeys;
+mod sshconfig;
 mod transport;
 mod quality;
 mod capture;
@@ -49,6 +54,7 @@ pub fn run() {
             config::init_config(app)?;
             tray::setup_tray(app)?;
             app.manage(terminal::TerminalManager::new());
+            app.manage(terminal::share::ShareManager::new());
             let vpn = vpn::VpnManager::new();
             {
                 let cfg = app.state::<config::ConfigState>();
@@ -57,6 +63,11 @@ pub fn run() {
             }
             app.manage(vpn);
             app.manage(desktop::VncProxy::new());
+            app.manage(health::HealthMonitor::new());
+            app.manage(filetransfer::queue::TransferManager::load(filetransfer::queue::queue_path()));
+            // Jobs left over from the last run pick up where they stopped
+            app.state::<filetransfer::queue::TransferManager>().schedule(app.handle());
+            health::HealthMonitor::start(app.handle().clone());
             
             // 初始化流媒体管道
             let app_handle = app.handle().clone();
@@ -79,16 +90,62 @@ pub fn run() {
             commands::vpn::vpn_has_config,
             // SSH
             commands::ssh::ssh_open,
+            commands::ssh::local_open,
+            commands::ssh::serial_open,
+            commands::ssh::serial_ports_list,
             commands::ssh::ssh_write,
             commands::ssh::ssh_resize,
             commands::ssh::ssh_close,
+            commands::ssh::share_invite,
+            commands::ssh::share_list,
+            commands::ssh::share_set_control,
+            commands::ssh::share_disconnect,
+            commands::ssh::share_stop,
+            commands::ssh::ssh_subscribe,
+            commands::ssh::ssh_ack,
+            commands::ssh::ssh_replay,
+            commands::ssh::ssh_sessions,
+            commands::ssh::broadcast_list,
+            commands::ssh::broadcast_create,
+            commands::ssh::broadcast_join,
+            commands::ssh::broadcast_leave,
+            commands::ssh::broadcast_exclude,
+            commands::ssh::broadcast_dissolve,
+            commands::ssh::ssh_attach,
+            commands::ssh::remote_sessions_list,
+            commands::ssh::ssh_record_start,
+            commands::ssh::ssh_record_stop,
+            commands::ssh::ssh_recordings_list,
+            commands::ssh::terminal_triggers_get,
+            commands::ssh::terminal_triggers_set,
+            commands::ssh::ssh_backend_get,
+            commands::ssh::ssh_backend_set,
             // Devices
             commands::devices::list_devices,
             commands::devices::add_device,
+            commands::devices::update_device,
             commands::devices::remove_device,
             commands::devices::ping_device,
+            commands::devices::refresh_device_status,
+            commands::devices::set_health_interval,
+            commands::wol::wake_device,
             commands::devices::export_config,
             commands::devices::import_config,
+            commands::devices::import_config_preview,
+            commands::devices::import_config_merge,
+            commands::devices::ssh_config_preview,
+            commands::devices::ssh_config_import,
+            commands::devices::ssh_config_export,
+            // Device groups
+            commands::groups::list_groups,
+            commands::groups::add_group,
+            commands::groups::update_group,
+            commands::groups::remove_group,
+            // Snippets
+            commands::snippets::snippets_list,
+            commands::snippets::snippet_save,
+            commands::snippets::snippet_delete,
+            commands::snippets::snippet_send,
             // Desktop (smart auto-detect + embedded)
             commands::desktop::desktop_connect,
             commands::desktop::detect_gpu,
@@ -99,10 +156,25 @@ pub fn run() {
             commands::desktop::launch_moonlight,
             // File transfer
             commands::files::sftp_list,
+            commands::files::sftp_stat,
             commands::files::sftp_upload,
             commands::files::sftp_download,
+            commands::files::sftp_upload_dir,
+            commands::files::sftp_download_dir,
+            commands::files::sftp_sync,
             commands::files::sftp_mkdir,
             commands::files::sftp_delete,
+            commands::files::sftp_op,
+            commands::files::sftp_batch,
+            commands::files::transfer_enqueue,
+            commands::files::transfer_list,
+            commands::files::transfer_pause,
+            commands::files::transfer_resume,
+            commands::files::transfer_cancel,
+            commands::files::transfer_remove,
+            commands::files::transfer_clear_finished,
+            commands::files::transfer_get_parallel,
+            commands::files::transfer_set_parallel,
             // SSH Key Management
             commands::sshkeys::ssh_keys_list,
             commands::sshkeys::ssh_key_generate,
@@ -112,6 +184,13 @@ pub fn run() {
             commands::crypto::unlock_config,
             commands::crypto::set_config_password,
             commands::crypto::remove_config_password,
+            // Config backups
+            commands::config::config_backups_list,
+            commands::config::config_backup_restore,
+            // Audit log
+            commands::audit::audit_query,
+            commands::audit::audit_export,
+            commands::audit::recent_connections,
         ])
         .run(tauri::generate_context!())
         .expect("error while running RemoteLab");
@@ -0,0 +1,154 @@
+use serde::{Deserialize, Serialize};
+
+/// One hop of a ProxyJump chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JumpHost {
+    pub host: String,
+    pub user: String,
+    pub port: Option<u16>,
+}
+
+impl JumpHost {
+    /// Render as `user@host[:port]`, bracketing IPv6 literals
+    fn to_proxy_jump(&self) -> String {
+        let host = if self.host.contains(':') && !self.host.starts_with('[') {
+            format!("[{}]", self.host)
+        } else {
+            self.host.clone()
+        };
+        match self.port {
+            Some(p) => format!("{}@{}:{}", self.user, host, p),
+            None => format!("{}@{}", self.user, host),
+        }
+    }
+}
+
+/// Everything needed to reach a remote host over SSH, including the
+/// ordered list of bastions to hop through (outermost first).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SshTarget {
+    pub host: String,
+    pub user: String,
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub jumps: Vec<JumpHost>,
+    /// Private key passed with `-i`
+    #[serde(default)]
+    pub identity_file: Option<String>,
+    /// Configured device this target was resolved from, if any
+    #[serde(default)]
+    pub device_id: Option<String>,
+}
+
+impl SshTarget {
+    pub fn new(host: &str, user: &str, port: Option<u16>) -> Self {
+        Self {
+            host: host.to_string(),
+            user: user.to_string(),
+            port,
+            jumps: Vec::new(),
+            identity_file: None,
+            device_id: None,
+        }
+    }
+
+    pub fn with_jumps(mut self, jumps: Vec<JumpHost>) -> Self {
+        self.jumps = jumps;
+        self
+    }
+
+    pub fn with_identity(mut self, identity_file: Option<String>) -> Self {
+        self.identity_file = identity_file;
+        self
+    }
+
+    pub fn with_device(mut self, device_id: Option<String>) -> Self {
+        self.device_id = device_id;
+        self
+    }
+
+    /// `user@host` destination argument
+    pub fn destination(&self) -> String {
+        format!("{}@{}", self.user, self.host)
+    }
+
+    /// ProxyJump value, or None for a direct connection
+    pub fn proxy_jump(&self) -> Option<String> {
+        if self.jumps.is_empty() {
+            return None;
+        }
+        Some(
+            self.jumps
+                .iter()
+                .map(JumpHost::to_proxy_jump)
+                .collect::<Vec<_>>()
+                .join(","),
+        )
+    }
+
+    /// Connection args for `ssh` (port, key, jump chain), without the destination
+    pub fn ssh_args(&self) -> Vec<String> {
+        let mut args = self.identity_args();
+        if let Some(jump) = self.proxy_jump() {
+            args.push("-o".to_string());
+            args.push(format!("ProxyJump={}", jump));
+        }
+        if let Some(p) = self.port {
+            args.push("-p".to_string());
+            args.push(p.to_string());
+        }
+        args
+    }
+
+    /// Connection args for `scp`, which spells the port flag `-P`
+    pub fn scp_args(&self) -> Vec<String> {
+        let mut args = self.identity_args();
+        if let Some(jump) = self.proxy_jump() {
+            args.push("-o".to_string());
+            args.push(format!("ProxyJump={}", jump));
+        }
+        if let Some(p) = self.port {
+            args.push("-P".to_string());
+            args.push(p.to_string());
+        }
+        args
+    }
+
+    fn identity_args(&self) -> Vec<String> {
+        match &self.identity_file {
+            Some(key) => vec!["-i".to_string(), key.clone()],
+            None => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_direct_target_has_no_proxy_jump() {
+        let target = SshTarget::new("10.0.0.5", "root", Some(2222));
+        assert_eq!(target.proxy_jump(), None);
+        assert_eq!(target.ssh_args(), vec!["-p", "2222"]);
+        assert_eq!(target.scp_args(), vec!["-P", "2222"]);
+        assert_eq!(target.destination(), "root@10.0.0.5");
+    }
+
+    #[test]
+    fn test_jump_chain_rendering() {
+        let target = SshTarget::new("gpu1", "lab", None).with_jumps(vec![
+            JumpHost { host: "bastion.example.com".into(), user: "ops".into(), port: Some(2200) },
+            JumpHost { host: "fd00::1".into(), user: "lab".into(), port: None },
+        ]);
+        assert_eq!(
+            target.proxy_jump().as_deref(),
+            Some("ops@bastion.example.com:2200,lab@[fd00::1]")
+        );
+        assert_eq!(target.ssh_args()[0], "-o");
+        assert_eq!(
+            target.ssh_args()[1],
+            "ProxyJump=ops@bastion.example.com:2200,lab@[fd00::1]"
+        );
+    }
+}
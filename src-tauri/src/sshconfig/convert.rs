@@ -0,0 +1,278 @@
+use serde::Serialize;
+use std::collections::HashMap;
+
+use super::parser::SshHostEntry;
+use crate::config::{AppConfig, Device};
+
+/// A device proposed by an ssh_config import, shown to the user before merging
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportCandidate {
+    /// ssh_config alias (or jump host name) this device came from
+    pub alias: String,
+    /// `device.id` (and jump host ids pointing at other candidates) are
+    /// provisional until the import assigns real ones
+    pub device: Device,
+    /// Id of an existing device this would replace, if any
+    pub existing_id: Option<String>,
+    /// Created only because another entry jumps through it
+    pub implicit: bool,
+}
+
+/// Turn an ssh_config alias or device name into a Host-safe token
+pub fn alias_to_id(alias: &str) -> String {
+    alias
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '_' { c } else { '-' })
+        .collect()
+}
+
+/// One hop of a ProxyJump spec: `[user@]host[:port]`
+fn parse_jump_spec(spec: &str) -> (Option<String>, String, Option<u16>) {
+    let (user, rest) = match spec.split_once('@') {
+        Some((u, r)) => (Some(u.to_string()), r),
+        None => (None, spec),
+    };
+    // [v6]:port or [v6]
+    if let Some(inner) = rest.strip_prefix('[') {
+        if let Some((host, tail)) = inner.split_once(']') {
+            let port = tail.strip_prefix(':').and_then(|p| p.parse().ok());
+            return (user, host.to_string(), port);
+        }
+    }
+    match rest.rsplit_once(':') {
+        Some((host, port)) if !host.contains(':') => {
+            (user, host.to_string(), port.parse().ok())
+        }
+        _ => (user, rest.to_string(), None),
+    }
+}
+
+/// Map parsed entries to devices, resolving ProxyJump hops to device ids.
+/// Hops that are neither another alias nor an existing device become
+/// implicit candidates so the chain survives the import. `ProxyJump none`
+/// becomes an explicit direct connection; no ProxyJump leaves the jump hosts
+/// to the device's groups.
+pub fn entries_to_candidates(entries: &[SshHostEntry], config: &AppConfig) -> Vec<ImportCandidate> {
+    let mut candidates: Vec<ImportCandidate> = entries
+        .iter()
+        .map(|entry| {
+            let address = entry.host_name.clone().unwrap_or_else(|| entry.alias.clone());
+            let device = Device {
+                id: alias_to_id(&entry.alias),
+                name: entry.alias.clone(),
+                vpn_ip: address,
+                ssh_user: entry
+                    .user
+                    .clone()
+                    .unwrap_or_else(|| config.default_ssh_user.clone()),
+                ssh_port: entry.port.filter(|p| *p != 22),
+                identity_file: entry.identity_file.clone(),
+                ..Default::default()
+            };
+            ImportCandidate {
+                alias: entry.alias.clone(),
+                existing_id: find_existing(config, &device),
+                device,
+                implicit: false,
+            }
+        })
+        .collect();
+
+    for (idx, entry) in entries.iter().enumerate() {
+        let spec = match entry.proxy_jump.as_deref() {
+            Some(spec) if spec.eq_ignore_ascii_case("none") => {
+                candidates[idx].device.jump_hosts = Some(Vec::new());
+                continue;
+            }
+            Some(spec) => spec,
+            None => continue,
+        };
+        let mut jump_ids = Vec::new();
+        for hop in spec.split(',').map(str::trim).filter(|h| !h.is_empty()) {
+            let (user, host, port) = parse_jump_spec(hop);
+            let id = if let Some(c) = candidates.iter().find(|c| c.alias == host) {
+                c.device.id.clone()
+            } else if let Some(d) = config
+                .devices
+                .iter()
+                .find(|d| {
+                    d.name.eq_ignore_ascii_case(&host)
+                        || (d.ssh_address() == host && d.ssh_port == port)
+                })
+            {
+                d.id.clone()
+            } else {
+                let device = Device {
+                    id: alias_to_id(&host),
+                    name: host.clone(),
+                    vpn_ip: host.clone(),
+                    ssh_user: user.unwrap_or_else(|| config.default_ssh_user.clone()),
+                    ssh_port: port.filter(|p| *p != 22),
+                    ..Default::default()
+                };
+                let id = device.id.clone();
+                candidates.push(ImportCandidate {
+                    alias: host.clone(),
+                    existing_id: None,
+                    device,
+                    implicit: true,
+                });
+                id
+            };
+            // A host cannot be its own bastion (a catch-all `Host *` ProxyJump)
+            if id != candidates[idx].device.id {
+                jump_ids.push(id);
+            }
+        }
+        candidates[idx].device.jump_hosts = Some(jump_ids);
+    }
+
+    candidates
+}
+
+/// Overwrite the connection settings of an existing device with those of an
+/// imported one: address, user, port, jump hosts and identity file
+pub fn apply_connection(existing: &mut Device, imported: &Device) {
+    let address = imported.ssh_address().to_string();
+    // A device reached through a separate SSH host keeps its VPN address
+    match existing.ssh_host {
+        Some(_) => existing.ssh_host = Some(address),
+        None => existing.vpn_ip = address,
+    }
+    existing.ssh_user = imported.ssh_user.clone();
+    existing.ssh_port = imported.ssh_port;
+    existing.jump_hosts = imported.jump_hosts.clone();
+    existing.identity_file = imported.identity_file.clone();
+}
+
+fn find_existing(config: &AppConfig, device: &Device) -> Option<String> {
+    config
+        .devices
+        .iter()
+        .find(|d| {
+            d.name.eq_ignore_ascii_case(&device.name)
+                || (d.ssh_address() == device.ssh_address() && d.ssh_port == device.ssh_port)
+        })
+        .map(|d| d.id.clone())
+}
+
+/// Render devices as an ssh_config fragment. Aliases are derived from device
+/// names, and ProxyJump lines refer to other exported hosts by that alias.
+pub fn devices_to_ssh_config(devices: &[&Device]) -> String {
+    let mut aliases: HashMap<&str, String> = HashMap::new();
+    for device in devices {
+        let base = alias_to_id(&device.name);
+        let mut alias = base.clone();
+        let mut n = 2;
+        while aliases.values().any(|a| *a == alias) {
+            alias = format!("{}-{}", base, n);
+            n += 1;
+        }
+        aliases.insert(device.id.as_str(), alias);
+    }
+
+    let mut out = String::from("# Generated by RemoteLab\n");
+    for device in devices {
+        let alias = &aliases[device.id.as_str()];
+        out.push('\n');
+        if device.name != *alias {
+            out.push_str(&format!("# {}\n", device.name));
+        }
+        out.push_str(&format!("Host {}\n", alias));
+        out.push_str(&format!("    HostName {}\n", device.ssh_address()));
+        out.push_str(&format!("    User {}\n", device.ssh_user));
+        if let Some(port) = device.ssh_port {
+            out.push_str(&format!("    Port {}\n", port));
+        }
+        if let Some(key) = &device.identity_file {
+            out.push_str(&format!("    IdentityFile \"{}\"\n", key));
+        }
+        match device.jump_hosts.as_deref() {
+            // Explicitly direct, whatever a `Host *` block says
+            Some([]) => out.push_str("    ProxyJump none\n"),
+            Some(jumps) => {
+                // Fall back to the id for jump hosts that were not exported
+                let hops: Vec<&str> = jumps
+                    .iter()
+                    .map(|j| aliases.get(j.as_str()).map(String::as_str).unwrap_or(j))
+                    .collect();
+                out.push_str(&format!("    ProxyJump {}\n", hops.join(",")));
+            }
+            None => {}
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sshconfig::parser::parse_str;
+    use std::path::Path;
+
+    #[test]
+    fn test_proxy_jump_resolves_to_aliases_and_implicit_hosts() {
+        let content = r#"
+Host bastion
+    HostName 203.0.113.10
+    User ops
+Host gpu1
+    HostName 10.0.1.5
+    ProxyJump bastion,admin@edge.example.com:2200
+"#;
+        let entries = parse_str(content, Path::new("/nonexistent")).unwrap();
+        let candidates = entries_to_candidates(&entries, &AppConfig::default());
+        assert_eq!(candidates.len(), 3);
+
+        let gpu1 = &candidates[1].device;
+        assert_eq!(gpu1.jumps(), ["bastion", "edge.example.com"]);
+
+        let edge = &candidates[2];
+        assert!(edge.implicit);
+        assert_eq!(edge.device.ssh_user, "admin");
+        assert_eq!(edge.device.ssh_port, Some(2200));
+    }
+
+    #[test]
+    fn test_export_round_trip() {
+        let content = "Host gpu1\n  HostName 10.0.1.5\n  User lab\n  Port 2222\n  ProxyJump bastion\nHost bastion\n  HostName 203.0.113.10\n  ProxyJump none\nHost lab\n  HostName 10.0.1.6\n";
+        let entries = parse_str(content, Path::new("/nonexistent")).unwrap();
+        let candidates = entries_to_candidates(&entries, &AppConfig::default());
+        let devices: Vec<&Device> = candidates.iter().map(|c| &c.device).collect();
+
+        let exported = devices_to_ssh_config(&devices);
+        let reparsed = parse_str(&exported, Path::new("/nonexistent")).unwrap();
+        assert_eq!(reparsed.len(), 3);
+        assert_eq!(reparsed[0].host_name.as_deref(), Some("10.0.1.5"));
+        assert_eq!(reparsed[0].port, Some(2222));
+        assert_eq!(reparsed[0].proxy_jump.as_deref(), Some("bastion"));
+        // An explicit direct connection survives, an unset one stays unset
+        assert_eq!(reparsed[1].proxy_jump.as_deref(), Some("none"));
+        assert_eq!(reparsed[2].proxy_jump, None);
+        let again = entries_to_candidates(&reparsed, &AppConfig::default());
+        assert_eq!(again[1].device.jump_hosts, Some(Vec::new()));
+        assert_eq!(again[2].device.jump_hosts, None);
+    }
+
+    #[test]
+    fn test_apply_connection_keeps_device_settings() {
+        let content = "Host gpu1\n  HostName 10.0.1.9\n  User lab\n  Port 2222\n";
+        let entries = parse_str(content, Path::new("/nonexistent")).unwrap();
+        let imported = &entries_to_candidates(&entries, &AppConfig::default())[0].device;
+
+        let mut existing = imported.clone();
+        existing.id = "gpu1-id".into();
+        existing.vpn_ip = "10.0.1.5".into();
+        existing.ssh_user = "old".into();
+        existing.group = Some("lab".into());
+        existing.tags = vec!["gpu".into()];
+        apply_connection(&mut existing, imported);
+
+        assert_eq!(existing.id, "gpu1-id");
+        assert_eq!(existing.vpn_ip, "10.0.1.9");
+        assert_eq!((existing.ssh_user.as_str(), existing.ssh_port), ("lab", Some(2222)));
+        assert_eq!(existing.group.as_deref(), Some("lab"));
+        assert_eq!(existing.tags, vec!["gpu"]);
+    }
+}
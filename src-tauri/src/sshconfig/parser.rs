@@ -0,0 +1,352 @@
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Guard against Include cycles
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// A concrete host alias from ssh_config with its effective options
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SshHostEntry {
+    pub alias: String,
+    pub host_name: Option<String>,
+    pub user: Option<String>,
+    pub port: Option<u16>,
+    pub identity_file: Option<String>,
+    pub proxy_jump: Option<String>,
+}
+
+/// A `Host` block: its patterns and the options set inside it, in file order
+#[derive(Debug, Clone, Default)]
+struct HostBlock {
+    patterns: Vec<String>,
+    options: Vec<(String, String)>,
+}
+
+/// Default location of the user's OpenSSH client config
+pub fn default_config_path() -> Result<PathBuf, String> {
+    dirs::home_dir()
+        .map(|h| h.join(".ssh").join("config"))
+        .ok_or_else(|| "Could not determine home directory".to_string())
+}
+
+/// Parse an ssh_config file, following Include directives
+pub fn parse_file(path: &Path) -> Result<Vec<SshHostEntry>, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let base_dir = dirs::home_dir()
+        .map(|h| h.join(".ssh"))
+        .unwrap_or_else(|| path.parent().map(Path::to_path_buf).unwrap_or_default());
+    parse_str(&content, &base_dir)
+}
+
+/// Parse ssh_config text. Relative Include paths resolve against `base_dir`.
+///
+/// Returns one entry per concrete alias (no wildcards or negation), with
+/// options resolved the way ssh does: every matching block contributes,
+/// and the first value obtained for a keyword wins.
+pub fn parse_str(content: &str, base_dir: &Path) -> Result<Vec<SshHostEntry>, String> {
+    // Options before the first Host line apply to every host
+    let mut blocks = vec![HostBlock {
+        patterns: vec!["*".to_string()],
+        options: Vec::new(),
+    }];
+    collect_blocks(content, base_dir, 0, &mut blocks)?;
+
+    let mut aliases: Vec<String> = Vec::new();
+    for block in blocks.iter().skip(1) {
+        for pattern in &block.patterns {
+            let concrete = !pattern.contains(['*', '?', '!']);
+            if concrete && !aliases.contains(pattern) {
+                aliases.push(pattern.clone());
+            }
+        }
+    }
+
+    Ok(aliases
+        .into_iter()
+        .map(|alias| resolve_entry(&blocks, alias))
+        .collect())
+}
+
+fn collect_blocks(
+    content: &str,
+    base_dir: &Path,
+    depth: usize,
+    blocks: &mut Vec<HostBlock>,
+) -> Result<(), String> {
+    if depth > MAX_INCLUDE_DEPTH {
+        return Err("ssh_config Include nesting too deep".to_string());
+    }
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (key, value) = split_keyword(line);
+        match key.as_str() {
+            "host" => blocks.push(HostBlock {
+                patterns: split_args(value),
+                options: Vec::new(),
+            }),
+            // Match criteria are not evaluated; treat the block as never matching
+            "match" => blocks.push(HostBlock::default()),
+            "include" => {
+                let outer_patterns = blocks.last().map(|b| b.patterns.clone()).unwrap_or_default();
+                let before = blocks.len();
+                for pattern in split_args(value) {
+                    for path in expand_include(&pattern, base_dir) {
+                        if let Ok(included) = fs::read_to_string(&path) {
+                            collect_blocks(&included, base_dir, depth + 1, blocks)?;
+                        }
+                    }
+                }
+                // Lines after the Include still belong to the enclosing block
+                if blocks.len() != before {
+                    blocks.push(HostBlock {
+                        patterns: outer_patterns,
+                        options: Vec::new(),
+                    });
+                }
+            }
+            _ => {
+                if let Some(block) = blocks.last_mut() {
+                    block.options.push((key, unquote(value)));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn resolve_entry(blocks: &[HostBlock], alias: String) -> SshHostEntry {
+    let mut entry = SshHostEntry {
+        alias,
+        ..Default::default()
+    };
+    for block in blocks.iter().filter(|b| block_matches(&b.patterns, &entry.alias)) {
+        for (key, value) in &block.options {
+            match key.as_str() {
+                "hostname" if entry.host_name.is_none() => entry.host_name = Some(value.clone()),
+                "user" if entry.user.is_none() => entry.user = Some(value.clone()),
+                "port" if entry.port.is_none() => entry.port = value.parse().ok(),
+                "identityfile" if entry.identity_file.is_none() => {
+                    entry.identity_file = Some(expand_tilde(value));
+                }
+                "proxyjump" if entry.proxy_jump.is_none() => {
+                    entry.proxy_jump = Some(value.clone());
+                }
+                _ => {}
+            }
+        }
+    }
+    // HostName may reference the alias itself, e.g. `HostName %h.lab.internal`
+    if let Some(host_name) = entry.host_name.as_mut() {
+        *host_name = host_name.replace("%h", &entry.alias).replace("%%", "%");
+    }
+    entry
+}
+
+/// A host matches a pattern list if any positive pattern matches and no
+/// negated (`!pattern`) one does
+fn block_matches(patterns: &[String], host: &str) -> bool {
+    let mut matched = false;
+    for pattern in patterns {
+        if let Some(negated) = pattern.strip_prefix('!') {
+            if wildcard_match(negated, host) {
+                return false;
+            }
+        } else if wildcard_match(pattern, host) {
+            matched = true;
+        }
+    }
+    matched
+}
+
+/// ssh-style glob: `*` matches any run of characters, `?` exactly one.
+/// Hostnames compare case-insensitively.
+pub fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.to_lowercase().chars().collect();
+    let t: Vec<char> = text.to_lowercase().chars().collect();
+    let (mut pi, mut ti) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == t[ti]) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, ti));
+            pi += 1;
+        } else if let Some((sp, st)) = star {
+            pi = sp + 1;
+            ti = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|&c| c == '*')
+}
+
+/// Split `Keyword value` / `Keyword=value` into a lowercased keyword and the rest
+fn split_keyword(line: &str) -> (String, &str) {
+    match line.find(|c: char| c.is_whitespace() || c == '=') {
+        Some(idx) => {
+            let value = line[idx..].trim_start();
+            let value = value.strip_prefix('=').unwrap_or(value).trim();
+            (line[..idx].to_lowercase(), value)
+        }
+        None => (line.to_lowercase(), ""),
+    }
+}
+
+/// Split a whitespace-separated argument list, honouring double quotes
+fn split_args(value: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    for c in value.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    args.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        args.push(current);
+    }
+    args
+}
+
+fn unquote(value: &str) -> String {
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value)
+        .to_string()
+}
+
+fn expand_tilde(path: &str) -> String {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest).to_string_lossy().to_string(),
+        _ => path.to_string(),
+    }
+}
+
+/// Resolve an Include argument to the files it names, sorted like glob(3)
+fn expand_include(pattern: &str, base_dir: &Path) -> Vec<PathBuf> {
+    let expanded = PathBuf::from(expand_tilde(pattern));
+    let path = if expanded.is_absolute() {
+        expanded
+    } else {
+        base_dir.join(expanded)
+    };
+
+    let file_pattern = match path.file_name() {
+        Some(name) => name.to_string_lossy().to_string(),
+        None => return Vec::new(),
+    };
+    if !file_pattern.contains(['*', '?']) {
+        return vec![path];
+    }
+
+    let dir = path.parent().unwrap_or(base_dir);
+    let mut matches: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .map(|e| e.path())
+                .filter(|p| p.is_file())
+                .filter(|p| {
+                    p.file_name()
+                        .map(|n| wildcard_match(&file_pattern, &n.to_string_lossy()))
+                        .unwrap_or(false)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    matches.sort();
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_value_wins_across_wildcard_blocks() {
+        let content = r#"
+Host gpu1 gpu2
+    HostName 10.0.1.%h
+    User lab
+
+Host gpu1
+    User ignored
+    Port 2222
+
+Host *
+    User fallback
+    IdentityFile ~/.ssh/id_lab
+    ProxyJump bastion
+
+Host bastion
+    HostName bastion.example.com
+    ProxyJump none
+"#;
+        let entries = parse_str(content, Path::new("/nonexistent")).unwrap();
+        let aliases: Vec<_> = entries.iter().map(|e| e.alias.as_str()).collect();
+        assert_eq!(aliases, vec!["gpu1", "gpu2", "bastion"]);
+
+        let gpu1 = &entries[0];
+        assert_eq!(gpu1.user.as_deref(), Some("lab"));
+        assert_eq!(gpu1.port, Some(2222));
+        assert_eq!(gpu1.proxy_jump.as_deref(), Some("bastion"));
+        assert!(gpu1.identity_file.as_deref().unwrap().ends_with(".ssh/id_lab"));
+
+        // Host * comes before the bastion block, so its ProxyJump wins there too
+        let bastion = &entries[2];
+        assert_eq!(bastion.user.as_deref(), Some("fallback"));
+        assert_eq!(bastion.proxy_jump.as_deref(), Some("bastion"));
+    }
+
+    #[test]
+    fn test_negated_patterns_and_equals_syntax() {
+        let content = "Host=* !secret\n  Port=2200\nHost secret other\n  HostName \"10.0.0.9\"\n";
+        let entries = parse_str(content, Path::new("/nonexistent")).unwrap();
+        assert_eq!(entries[0].alias, "secret");
+        assert_eq!(entries[0].port, None);
+        assert_eq!(entries[0].host_name.as_deref(), Some("10.0.0.9"));
+        assert_eq!(entries[1].port, Some(2200));
+    }
+
+    #[test]
+    fn test_include_glob() {
+        let dir = std::env::temp_dir().join(format!("remotelab-sshcfg-{}", std::process::id()));
+        fs::create_dir_all(dir.join("conf.d")).unwrap();
+        fs::write(dir.join("conf.d/a.conf"), "Host node-a\n  HostName 10.1.0.1\n").unwrap();
+        fs::write(dir.join("conf.d/b.conf"), "Host node-b\n  HostName 10.1.0.2\n").unwrap();
+
+        let content = "Host top\n  Include conf.d/*.conf\n  User shared\n";
+        let entries = parse_str(content, &dir).unwrap();
+        let aliases: Vec<_> = entries.iter().map(|e| e.alias.as_str()).collect();
+        assert_eq!(aliases, vec!["top", "node-a", "node-b"]);
+        assert_eq!(entries[0].user.as_deref(), Some("shared"));
+        assert_eq!(entries[1].user, None);
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("*.lab", "gpu1.LAB"));
+        assert!(wildcard_match("gpu?", "gpu7"));
+        assert!(!wildcard_match("gpu?", "gpu17"));
+        assert!(wildcard_match("*", ""));
+    }
+}
@@ -2,6 +2,9 @@ use std::fs;
 use std::path::PathBuf;
 use std::process::{Command, Stdio};
 
+use crate::audit::{self, AuditAction};
+use crate::ssh_target::SshTarget;
+
 #[cfg(unix)]
 fn ssh_bin() -> &'static str { "/usr/bin/ssh" }
 #[cfg(windows)]
@@ -268,12 +271,13 @@ pub fn generate_key(name: &str, passphrase: &str) -> Result<SshKeyInfo, String>
 }
 
 /// Copy a public key to a remote host's authorized_keys
-pub fn copy_key_to_remote(
-    key_path: &str,
-    host: &str,
-    user: &str,
-    port: Option<u16>,
-) -> Result<(), String> {
+pub fn copy_key_to_remote(key_path: &str, target: &SshTarget) -> Result<(), String> {
+    audit::track(AuditAction::KeyDeploy, target, Some(key_path), || {
+        copy_key(key_path, target)
+    })
+}
+
+fn copy_key(key_path: &str, target: &SshTarget) -> Result<(), String> {
     let pub_path = if key_path.ends_with(".pub") {
         PathBuf::from(key_path)
     } else {
@@ -303,11 +307,8 @@ pub fn copy_key_to_remote(
         "-o".to_string(),
         "ConnectTimeout=10".to_string(),
     ];
-    if let Some(p) = port {
-        args.push("-p".to_string());
-        args.push(p.to_string());
-    }
-    args.push(format!("{}@{}", user, host));
+    args.extend(target.ssh_args());
+    args.push(target.destination());
 
     // Remote command: create ~/.ssh if needed, append key if not already present
     let escaped_key = public_key.replace('\'', "'\\''");
@@ -319,7 +320,7 @@ pub fn copy_key_to_remote(
     );
     args.push(remote_cmd);
 
-    log::info!("Copying SSH key to {}@{}", user, host);
+    log::info!("Copying SSH key to {}", target.destination());
 
     let output = Command::new(ssh_bin())
         .args(&args)
@@ -334,6 +335,6 @@ pub fn copy_key_to_remote(
         return Err(format!("Failed to copy key to remote: {}", stderr.trim()));
     }
 
-    log::info!("SSH key copied to {}@{}", user, host);
+    log::info!("SSH key copied to {}", target.destination());
     Ok(())
 }
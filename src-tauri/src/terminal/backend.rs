@@ -0,0 +1,152 @@
+use portable_pty::{Child, CommandBuilder, MasterPty, PtySize};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+
+use crate::ssh_target::SshTarget;
+use super::serial::SerialConfig;
+
+/// What a terminal session is connected to
+#[derive(Debug, Clone)]
+pub enum SessionKind {
+    Ssh(SshTarget),
+    Local(LocalShell),
+    Serial(SerialConfig),
+}
+
+/// `SessionKind` without its settings, as reported by `list_sessions`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionKindName {
+    Ssh,
+    Local,
+    Serial,
+}
+
+impl SessionKind {
+    pub fn name(&self) -> SessionKindName {
+        match self {
+            Self::Ssh(_) => SessionKindName::Ssh,
+            Self::Local(_) => SessionKindName::Local,
+            Self::Serial(_) => SessionKindName::Serial,
+        }
+    }
+
+    /// Where the session runs: the remote host, "localhost" or the serial device
+    pub fn host(&self) -> String {
+        match self {
+            Self::Ssh(target) => target.host.clone(),
+            Self::Local(_) => "localhost".to_string(),
+            Self::Serial(serial) => serial.path.clone(),
+        }
+    }
+
+    /// Tab and recording title
+    pub fn title(&self) -> String {
+        match self {
+            Self::Ssh(target) => target.destination(),
+            Self::Local(shell) => shell.program(),
+            Self::Serial(serial) => format!("{} @ {}", serial.path, serial.baud_rate),
+        }
+    }
+
+    pub fn ssh_target(&self) -> Option<&SshTarget> {
+        match self {
+            Self::Ssh(target) => Some(target),
+            _ => None,
+        }
+    }
+}
+
+/// The running side of a session. Output is pumped by each backend's own
+/// thread or task into the shared `OutputSink`; input goes through the
+/// session's writer. This covers what else `TerminalManager` drives.
+pub trait SessionBackend: Send {
+    fn resize(&mut self, cols: u16, rows: u16) -> Result<(), String>;
+
+    /// The frontend closed the session; stop it so the output pump ends
+    fn close(&mut self) {}
+}
+
+/// A process (`ssh` or a local shell) running in a local PTY
+pub struct PtyBackend {
+    pub master: Box<dyn MasterPty + Send>,
+    pub child: Arc<Mutex<Box<dyn Child + Send + Sync>>>,
+}
+
+impl SessionBackend for PtyBackend {
+    fn resize(&mut self, cols: u16, rows: u16) -> Result<(), String> {
+        self.master
+            .resize(PtySize {
+                rows,
+                cols,
+                pixel_width: 0,
+                pixel_height: 0,
+            })
+            .map_err(|e| format!("Resize failed: {}", e))
+    }
+
+    fn close(&mut self) {
+        // If the lock is taken, the reader thread is already waiting for the
+        // process to exit
+        if let Ok(mut child) = self.child.try_lock() {
+            child.kill().ok();
+        }
+    }
+}
+
+/// A shell on this machine
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LocalShell {
+    /// Defaults to $SHELL (COMSPEC on Windows)
+    #[serde(default)]
+    pub shell: Option<String>,
+    /// Defaults to the home directory
+    #[serde(default)]
+    pub cwd: Option<String>,
+}
+
+impl LocalShell {
+    pub fn program(&self) -> String {
+        if let Some(shell) = self.shell.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+            return shell.to_string();
+        }
+        #[cfg(windows)]
+        let fallback = std::env::var("COMSPEC").unwrap_or_else(|_| "powershell.exe".to_string());
+        #[cfg(not(windows))]
+        let fallback = std::env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string());
+        fallback
+    }
+
+    pub fn command(&self) -> Result<CommandBuilder, String> {
+        let cwd = match self.cwd.as_deref().filter(|c| !c.trim().is_empty()) {
+            Some(cwd) => std::path::PathBuf::from(cwd),
+            None => dirs::home_dir().ok_or("Cannot determine home directory")?,
+        };
+        if !cwd.is_dir() {
+            return Err(format!("Not a directory: {}", cwd.display()));
+        }
+        let mut cmd = CommandBuilder::new(self.program());
+        #[cfg(unix)]
+        cmd.arg("-l");
+        cmd.env("TERM", "xterm-256color");
+        cmd.cwd(cwd);
+        Ok(cmd)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_local_shell_defaults() {
+        let shell = LocalShell { shell: Some("  ".into()), cwd: None };
+        assert!(!shell.program().trim().is_empty());
+        let fish = LocalShell { shell: Some("/usr/bin/fish".into()), cwd: None };
+        assert_eq!(SessionKind::Local(fish.clone()).title(), "/usr/bin/fish");
+        assert_eq!(SessionKind::Local(fish).host(), "localhost");
+
+        let missing = LocalShell { shell: None, cwd: Some("/nonexistent/remotelab".into()) };
+        assert!(missing.command().is_err());
+    }
+}
@@ -0,0 +1,174 @@
+use serde::Serialize;
+
+/// Sessions whose keyboard input is mirrored to each other
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BroadcastGroup {
+    pub id: String,
+    pub name: String,
+    pub members: Vec<String>,
+    /// Members that stay in the group but neither send nor receive
+    pub excluded: Vec<String>,
+}
+
+/// Payload of `terminal-broadcast-{session}`, so the tab can show that it is
+/// part of a group and whether it currently receives broadcast keystrokes.
+/// Sent as null when the session leaves its group.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BroadcastMarker {
+    pub group_id: String,
+    pub name: String,
+    pub receiving: bool,
+}
+
+/// Broadcast groups of a `TerminalManager`. A session is in at most one group.
+#[derive(Debug, Default)]
+pub struct Broadcasts {
+    groups: Vec<BroadcastGroup>,
+}
+
+impl Broadcasts {
+    pub fn list(&self) -> Vec<BroadcastGroup> {
+        self.groups.clone()
+    }
+
+    pub fn group_of(&self, session_id: &str) -> Option<&BroadcastGroup> {
+        self.groups
+            .iter()
+            .find(|g| g.members.iter().any(|m| m == session_id))
+    }
+
+    /// New group of `members`, taking them out of any group they were in
+    pub fn create(&mut self, name: &str, members: &[String]) -> BroadcastGroup {
+        for member in members {
+            self.remove(member);
+        }
+        let mut unique: Vec<String> = Vec::new();
+        for member in members {
+            if !unique.contains(member) {
+                unique.push(member.clone());
+            }
+        }
+        let group = BroadcastGroup {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.trim().to_string(),
+            members: unique,
+            excluded: Vec::new(),
+        };
+        self.groups.push(group.clone());
+        group
+    }
+
+    pub fn add(&mut self, group_id: &str, session_id: &str) -> Result<(), String> {
+        if !self.groups.iter().any(|g| g.id == group_id) {
+            return Err(format!("Broadcast group '{}' not found", group_id));
+        }
+        if self.group_of(session_id).is_some_and(|g| g.id == group_id) {
+            return Ok(());
+        }
+        self.remove(session_id);
+        if let Some(group) = self.groups.iter_mut().find(|g| g.id == group_id) {
+            group.members.push(session_id.to_string());
+        }
+        Ok(())
+    }
+
+    /// Take a session out of its group. Groups left empty are dropped.
+    /// Returns the group it was in, if any.
+    pub fn remove(&mut self, session_id: &str) -> Option<String> {
+        let group = self
+            .groups
+            .iter_mut()
+            .find(|g| g.members.iter().any(|m| m == session_id))?;
+        group.members.retain(|m| m != session_id);
+        group.excluded.retain(|m| m != session_id);
+        let id = group.id.clone();
+        self.groups.retain(|g| !g.members.is_empty());
+        Some(id)
+    }
+
+    /// Opt a member out of (or back into) its group's broadcast
+    pub fn set_excluded(&mut self, session_id: &str, excluded: bool) -> Result<(), String> {
+        let group = self
+            .groups
+            .iter_mut()
+            .find(|g| g.members.iter().any(|m| m == session_id))
+            .ok_or("Session is not in a broadcast group")?;
+        group.excluded.retain(|m| m != session_id);
+        if excluded {
+            group.excluded.push(session_id.to_string());
+        }
+        Ok(())
+    }
+
+    /// Remove a group, returning its former members
+    pub fn dissolve(&mut self, group_id: &str) -> Result<Vec<String>, String> {
+        let index = self
+            .groups
+            .iter()
+            .position(|g| g.id == group_id)
+            .ok_or_else(|| format!("Broadcast group '{}' not found", group_id))?;
+        Ok(self.groups.remove(index).members)
+    }
+
+    /// Sessions that input typed into `session_id` should go to: the active
+    /// members of its group, or just itself when it isn't broadcasting
+    pub fn fan_out(&self, session_id: &str) -> Vec<String> {
+        match self.group_of(session_id) {
+            Some(group) if !group.excluded.iter().any(|m| m == session_id) => group
+                .members
+                .iter()
+                .filter(|m| !group.excluded.contains(m))
+                .cloned()
+                .collect(),
+            _ => vec![session_id.to_string()],
+        }
+    }
+
+    pub fn marker(&self, session_id: &str) -> Option<BroadcastMarker> {
+        let group = self.group_of(session_id)?;
+        Some(BroadcastMarker {
+            group_id: group.id.clone(),
+            name: group.name.clone(),
+            receiving: !group.excluded.iter().any(|m| m == session_id),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_fan_out_respects_exclusion() {
+        let mut b = Broadcasts::default();
+        let group = b.create("nodes", &ids(&["a", "b", "c", "a"]));
+        assert_eq!(group.members, ids(&["a", "b", "c"]));
+        assert_eq!(b.fan_out("a"), ids(&["a", "b", "c"]));
+
+        b.set_excluded("b", true).unwrap();
+        assert_eq!(b.fan_out("a"), ids(&["a", "c"]));
+        // An excluded session's own input stays local
+        assert_eq!(b.fan_out("b"), ids(&["b"]));
+        assert!(!b.marker("b").unwrap().receiving);
+        assert_eq!(b.fan_out("z"), ids(&["z"]));
+    }
+
+    #[test]
+    fn test_membership_moves_and_empty_groups_drop() {
+        let mut b = Broadcasts::default();
+        let first = b.create("one", &ids(&["a", "b"]));
+        let second = b.create("two", &ids(&["b", "c"]));
+        assert_eq!(b.group_of("a").unwrap().id, first.id);
+        assert_eq!(b.group_of("b").unwrap().id, second.id);
+
+        b.add(&second.id, "a").unwrap();
+        assert_eq!(b.list().len(), 1);
+        assert_eq!(b.remove("c"), Some(second.id.clone()));
+        assert_eq!(b.dissolve(&second.id).unwrap(), ids(&["b", "a"]));
+        assert!(b.marker("a").is_none());
+    }
+}
@@ -1,3 +1,15 @@
+pub mod backend;
+pub mod broadcast;
+#[cfg(feature = "russh")]
+pub mod native;
+pub mod multiplexer;
+pub mod output;
 pub mod pty;
+pub mod recording;
+pub mod scrollback;
+pub mod serial;
+pub mod share;
+pub mod startup;
+pub mod triggers;
 
 pub use pty::TerminalManager;
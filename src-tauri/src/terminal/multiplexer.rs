@@ -0,0 +1,164 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Remote terminal multiplexer that keeps a shell alive across disconnects
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Multiplexer {
+    Tmux,
+    Screen,
+}
+
+/// A named multiplexer session to attach to, created if it doesn't exist
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Attach {
+    pub multiplexer: Multiplexer,
+    pub name: String,
+}
+
+/// A multiplexer session found on a remote host
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RemoteSession {
+    pub multiplexer: Multiplexer,
+    pub name: String,
+    /// tmux only
+    pub windows: Option<u32>,
+    /// Some client is currently attached
+    pub attached: bool,
+    /// Unix time in milliseconds, tmux only
+    pub created_ms: Option<u64>,
+}
+
+/// Reconnect attempts after a dropped connection before giving up
+pub const MAX_RECONNECT_ATTEMPTS: u32 = 10;
+
+/// Delay before reconnect attempt `attempt` (0-based): 1s, 2s, 4s, … capped at 30s
+pub fn backoff_delay(attempt: u32) -> Duration {
+    Duration::from_secs((1u64 << attempt.min(5)).min(30))
+}
+
+impl Attach {
+    pub fn validate(&self) -> Result<(), String> {
+        let name = self.name.trim();
+        if name.is_empty() {
+            return Err("Session name is required".to_string());
+        }
+        if name.len() > 64 {
+            return Err("Session name is too long".to_string());
+        }
+        if name.chars().any(|c| c.is_whitespace() || c.is_control() || c == '\'') {
+            return Err(format!("Invalid session name: {}", name));
+        }
+        if self.multiplexer == Multiplexer::Tmux && name.contains(['.', ':']) {
+            return Err("tmux session names cannot contain '.' or ':'".to_string());
+        }
+        Ok(())
+    }
+
+    /// Remote command that attaches to the session, creating it if needed.
+    /// Without the multiplexer installed, falls back to a plain login shell.
+    pub fn remote_command(&self) -> String {
+        let (bin, attach) = match self.multiplexer {
+            Multiplexer::Tmux => ("tmux", format!("tmux new-session -A -s '{}'", self.name)),
+            // -D detaches a client left over from a dropped connection
+            Multiplexer::Screen => ("screen", format!("screen -D -R -S '{}'", self.name)),
+        };
+        format!(
+            "if command -v {bin} >/dev/null 2>&1; then exec {attach}; \
+             else echo '{bin} is not installed on this host, starting a plain shell'; \
+             exec \"${{SHELL:-/bin/sh}}\" -l; fi"
+        )
+    }
+}
+
+/// Remote script listing tmux and screen sessions, parsed by `parse_sessions`
+pub const LIST_SCRIPT: &str = r#"
+if command -v tmux >/dev/null 2>&1; then
+    tmux list-sessions -F 'TMUX|#{session_name}|#{session_windows}|#{session_attached}|#{session_created}' 2>/dev/null
+fi
+if command -v screen >/dev/null 2>&1; then
+    echo "SCREEN_LS"
+    screen -ls 2>/dev/null
+fi
+true
+"#;
+
+pub fn parse_sessions(output: &str) -> Vec<RemoteSession> {
+    let mut sessions = Vec::new();
+    let mut in_screen = false;
+    for line in output.lines() {
+        if line == "SCREEN_LS" {
+            in_screen = true;
+            continue;
+        }
+        if let Some(rest) = line.strip_prefix("TMUX|") {
+            let parts: Vec<&str> = rest.splitn(4, '|').collect();
+            if parts.len() == 4 {
+                sessions.push(RemoteSession {
+                    multiplexer: Multiplexer::Tmux,
+                    name: parts[0].to_string(),
+                    windows: parts[1].trim().parse().ok(),
+                    attached: parts[2].trim().parse::<u32>().is_ok_and(|n| n > 0),
+                    created_ms: parts[3].trim().parse::<u64>().ok().map(|s| s * 1000),
+                });
+            }
+        } else if in_screen && line.starts_with(|c: char| c.is_whitespace()) {
+            // "\t12345.name\t(Detached)" — the name is everything after the pid
+            let mut fields = line.split_whitespace();
+            let Some(id) = fields.next() else { continue };
+            let Some((pid, name)) = id.split_once('.') else { continue };
+            if pid.is_empty() || !pid.chars().all(|c| c.is_ascii_digit()) {
+                continue;
+            }
+            sessions.push(RemoteSession {
+                multiplexer: Multiplexer::Screen,
+                name: name.to_string(),
+                windows: None,
+                attached: line.contains("(Attached)"),
+                created_ms: None,
+            });
+        }
+    }
+    sessions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tmux_and_screen() {
+        let output = "TMUX|train|3|1|1700000000\n\
+                      TMUX|notebook|1|0|1700000100\n\
+                      SCREEN_LS\n\
+                      There are screens on:\n\
+                      \t4242.build\t(06/01/2024 10:00:00 AM)\t(Detached)\n\
+                      \t4243.pts-0.gpu1\t(Attached)\n\
+                      2 Sockets in /run/screen/S-lab.\n";
+        let sessions = parse_sessions(output);
+        assert_eq!(sessions.len(), 4);
+        assert_eq!(sessions[0].name, "train");
+        assert_eq!(sessions[0].windows, Some(3));
+        assert!(sessions[0].attached);
+        assert_eq!(sessions[1].created_ms, Some(1_700_000_100_000));
+        assert_eq!(sessions[2].multiplexer, Multiplexer::Screen);
+        assert_eq!(sessions[2].name, "build");
+        assert!(!sessions[2].attached);
+        assert_eq!(sessions[3].name, "pts-0.gpu1");
+        assert!(sessions[3].attached);
+    }
+
+    #[test]
+    fn test_attach_validation_and_backoff() {
+        let tmux = |name: &str| Attach { multiplexer: Multiplexer::Tmux, name: name.into() };
+        assert!(tmux("train").validate().is_ok());
+        assert!(tmux("a.b").validate().is_err());
+        assert!(tmux("it's").validate().is_err());
+        assert!(tmux("").validate().is_err());
+        assert!(tmux("train").remote_command().contains("tmux new-session -A -s 'train'"));
+
+        assert_eq!(backoff_delay(0), Duration::from_secs(1));
+        assert_eq!(backoff_delay(3), Duration::from_secs(8));
+        assert_eq!(backoff_delay(9), Duration::from_secs(30));
+    }
+}
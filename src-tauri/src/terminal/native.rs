@@ -0,0 +1,347 @@
+//! In-process SSH client for terminal sessions, used instead of spawning
+//! `ssh` when the `russh` feature is enabled.
+
+use russh::client::{self, Handle, Msg};
+use russh::keys::{self, known_hosts, PrivateKeyWithHashAlg, PublicKey};
+use russh::{Channel, ChannelMsg, Disconnect};
+use serde::Serialize;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use crate::ssh_target::SshTarget;
+use super::backend::SessionBackend;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Why a native connection could not be set up. Sent to the frontend as
+/// `terminal-error-{session}` so it can tell a bad key from a dead host.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ConnectError {
+    Connect { host: String, message: String },
+    /// The server's key differs from the one in known_hosts
+    HostKeyMismatch { host: String, port: u16 },
+    /// No key was accepted. `tried` lists the keys offered, in order.
+    AuthFailed { host: String, user: String, tried: Vec<String> },
+    Channel { message: String },
+}
+
+impl std::fmt::Display for ConnectError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Connect { host, message } => write!(f, "Failed to connect to {}: {}", host, message),
+            Self::HostKeyMismatch { host, port } => write!(
+                f,
+                "Host key for {}:{} does not match known_hosts; refusing to connect",
+                host, port
+            ),
+            Self::AuthFailed { host, user, tried } if tried.is_empty() => write!(
+                f,
+                "Authentication failed for {}@{}: no usable keys (load one into ssh-agent or set an identity file)",
+                user, host
+            ),
+            Self::AuthFailed { host, user, tried } => write!(
+                f,
+                "Authentication failed for {}@{} (tried {})",
+                user,
+                host,
+                tried.join(", ")
+            ),
+            Self::Channel { message } => write!(f, "Failed to open shell: {}", message),
+        }
+    }
+}
+
+/// Accepts unknown hosts and records them, like `StrictHostKeyChecking=accept-new`
+pub struct Client {
+    host: String,
+    port: u16,
+}
+
+impl Client {
+    pub fn new(host: &str, port: u16) -> Self {
+        Self { host: host.to_string(), port }
+    }
+}
+
+impl client::Handler for Client {
+    type Error = russh::Error;
+
+    async fn check_server_key(&mut self, server_public_key: &PublicKey) -> Result<bool, Self::Error> {
+        match known_hosts::check_known_hosts(&self.host, self.port, server_public_key) {
+            Ok(true) => Ok(true),
+            Ok(false) => {
+                if let Err(e) = known_hosts::learn_known_hosts(&self.host, self.port, server_public_key) {
+                    log::warn!("Could not add {} to known_hosts: {}", self.host, e);
+                }
+                Ok(true)
+            }
+            Err(e) => {
+                log::error!("Host key check failed for {}: {}", self.host, e);
+                Ok(false)
+            }
+        }
+    }
+}
+
+/// An authenticated session to the target. The jump host sessions carry the
+/// forwarded connection and must outlive it.
+pub struct Connection {
+    handle: Handle<Client>,
+    _jumps: Vec<Handle<Client>>,
+}
+
+/// Connect through the target's jump chain and authenticate at every hop
+pub async fn connect(target: &SshTarget) -> Result<Connection, ConnectError> {
+    let config = Arc::new(client::Config {
+        keepalive_interval: Some(Duration::from_secs(30)),
+        ..Default::default()
+    });
+
+    let mut hops: Vec<(String, String, u16)> = target
+        .jumps
+        .iter()
+        .map(|j| (j.host.clone(), j.user.clone(), j.port.unwrap_or(22)))
+        .collect();
+    hops.push((target.host.clone(), target.user.clone(), target.port.unwrap_or(22)));
+
+    let mut jumps: Vec<Handle<Client>> = Vec::new();
+    for (host, user, port) in hops {
+        let handler = Client::new(&host, port);
+        let connecting = async {
+            match jumps.last() {
+                None => client::connect(config.clone(), (host.as_str(), port), handler).await,
+                Some(via) => {
+                    let channel = via
+                        .channel_open_direct_tcpip(host.as_str(), port as u32, "127.0.0.1", 0)
+                        .await?;
+                    client::connect_stream(config.clone(), channel.into_stream(), handler).await
+                }
+            }
+        };
+        let mut handle = match tokio::time::timeout(CONNECT_TIMEOUT, connecting).await {
+            Ok(Ok(handle)) => handle,
+            Ok(Err(russh::Error::UnknownKey)) => {
+                return Err(ConnectError::HostKeyMismatch { host, port });
+            }
+            Ok(Err(e)) => {
+                return Err(ConnectError::Connect { host, message: e.to_string() });
+            }
+            Err(_) => {
+                return Err(ConnectError::Connect { host, message: "connection timed out".into() });
+            }
+        };
+        authenticate(&mut handle, &host, &user, target.identity_file.as_deref()).await?;
+        jumps.push(handle);
+    }
+
+    let handle = jumps.pop().expect("at least the target hop");
+    Ok(Connection { handle, _jumps: jumps })
+}
+
+/// Public key auth, trying the configured identity file, then ssh-agent,
+/// then the default key files (the latter only without an identity file)
+async fn authenticate(
+    handle: &mut Handle<Client>,
+    host: &str,
+    user: &str,
+    identity_file: Option<&str>,
+) -> Result<(), ConnectError> {
+    let failed = |tried: Vec<String>| ConnectError::AuthFailed {
+        host: host.to_string(),
+        user: user.to_string(),
+        tried,
+    };
+    let hash_alg = handle.best_supported_rsa_hash().await.ok().flatten().flatten();
+    let mut tried: Vec<String> = Vec::new();
+
+    if let Some(path) = identity_file {
+        if try_key_file(handle, user, &expand_home(path), hash_alg, &mut tried).await {
+            return Ok(());
+        }
+    }
+
+    #[cfg(unix)]
+    if let Ok(mut agent) = keys::agent::client::AgentClient::connect_env().await {
+        let identities = agent.request_identities().await.unwrap_or_default();
+        for key in identities {
+            let label = format!("agent:{}", key.fingerprint(Default::default()));
+            tried.push(label);
+            let accepted = handle
+                .authenticate_publickey_with(user, key, hash_alg, &mut agent)
+                .await
+                .is_ok_and(|r| r.success());
+            if accepted {
+                return Ok(());
+            }
+        }
+    }
+
+    if identity_file.is_none() {
+        let ssh_dir = dirs::home_dir().unwrap_or_default().join(".ssh");
+        for name in ["id_ed25519", "id_ecdsa", "id_rsa"] {
+            let path = ssh_dir.join(name);
+            if path.exists() && try_key_file(handle, user, &path, hash_alg, &mut tried).await {
+                return Ok(());
+            }
+        }
+    }
+
+    Err(failed(tried))
+}
+
+async fn try_key_file(
+    handle: &mut Handle<Client>,
+    user: &str,
+    path: &std::path::Path,
+    hash_alg: Option<keys::HashAlg>,
+    tried: &mut Vec<String>,
+) -> bool {
+    let key = match keys::load_secret_key(path, None) {
+        Ok(key) => key,
+        Err(e) => {
+            // Passphrase-protected keys have to come through the agent
+            log::warn!("Skipping key {}: {}", path.display(), e);
+            tried.push(format!("{} (unreadable: {})", path.display(), e));
+            return false;
+        }
+    };
+    tried.push(path.display().to_string());
+    handle
+        .authenticate_publickey(user, PrivateKeyWithHashAlg::new(Arc::new(key), hash_alg))
+        .await
+        .is_ok_and(|r| r.success())
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir().unwrap_or_default().join(rest),
+        None => PathBuf::from(path),
+    }
+}
+
+/// Open a channel with a PTY of the given size, running `command` or, if
+/// None, the login shell
+pub async fn open_shell(
+    conn: &Connection,
+    cols: u16,
+    rows: u16,
+    command: Option<&str>,
+) -> Result<Channel<Msg>, ConnectError> {
+    let channel_err = |e: russh::Error| ConnectError::Channel { message: e.to_string() };
+    let channel = conn.handle.channel_open_session().await.map_err(channel_err)?;
+    channel
+        .request_pty(false, "xterm-256color", cols as u32, rows as u32, 0, 0, &[])
+        .await
+        .map_err(channel_err)?;
+    if let Ok(lang) = std::env::var("LANG") {
+        channel.set_env(false, "LANG", lang).await.ok();
+    }
+    match command {
+        Some(command) => channel.exec(true, command).await.map_err(channel_err)?,
+        None => channel.request_shell(true).await.map_err(channel_err)?,
+    }
+    Ok(channel)
+}
+
+/// Requests from the session owner to the shell task
+pub enum Input {
+    Data(Vec<u8>),
+    Resize(u16, u16),
+}
+
+/// `Write` half of a native session: bytes are handed to the shell task
+pub struct ChannelWriter(pub mpsc::UnboundedSender<Input>);
+
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0
+            .send(Input::Data(buf.to_vec()))
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::BrokenPipe, "session closed"))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Backend of a native session. The shell task ends once this and the
+/// session's `ChannelWriter` are dropped.
+pub struct ShellBackend(pub mpsc::UnboundedSender<Input>);
+
+impl SessionBackend for ShellBackend {
+    fn resize(&mut self, cols: u16, rows: u16) -> Result<(), String> {
+        self.0
+            .send(Input::Resize(cols, rows))
+            .map_err(|_| "Resize failed: session closed".to_string())
+    }
+}
+
+/// Pump a shell channel until the remote side closes it or every input
+/// sender is dropped. `on_output` returning false also ends the session.
+/// Returns the remote exit status when the server sent one.
+pub async fn run_shell(
+    conn: Connection,
+    mut channel: Channel<Msg>,
+    mut input: mpsc::UnboundedReceiver<Input>,
+    mut on_output: impl FnMut(&[u8]) -> bool,
+) -> Result<Option<u32>, String> {
+    let mut exit_status = None;
+    let result = loop {
+        tokio::select! {
+            request = input.recv() => match request {
+                Some(Input::Data(data)) => {
+                    if let Err(e) = channel.data(&data[..]).await {
+                        break Err(format!("Write failed: {}", e));
+                    }
+                }
+                Some(Input::Resize(cols, rows)) => {
+                    channel.window_change(cols as u32, rows as u32, 0, 0).await.ok();
+                }
+                None => {
+                    channel.eof().await.ok();
+                    break Ok(());
+                }
+            },
+            msg = channel.wait() => match msg {
+                Some(ChannelMsg::Data { ref data }) | Some(ChannelMsg::ExtendedData { ref data, .. }) => {
+                    if !on_output(data) {
+                        break Ok(());
+                    }
+                }
+                Some(ChannelMsg::ExitStatus { exit_status: code }) => exit_status = Some(code),
+                Some(ChannelMsg::Close) | None => break Ok(()),
+                Some(_) => {}
+            },
+        }
+    };
+    conn.handle
+        .disconnect(Disconnect::ByApplication, "", "en")
+        .await
+        .ok();
+    result.map(|_| exit_status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auth_error_lists_tried_keys() {
+        let err = ConnectError::AuthFailed {
+            host: "gpu1".into(),
+            user: "lab".into(),
+            tried: vec!["/home/me/.ssh/id_ed25519".into()],
+        };
+        assert_eq!(
+            err.to_string(),
+            "Authentication failed for lab@gpu1 (tried /home/me/.ssh/id_ed25519)"
+        );
+        let json = serde_json::to_value(&err).unwrap();
+        assert_eq!(json["kind"], "auth_failed");
+    }
+}
@@ -0,0 +1,214 @@
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Output arriving within this long of the first pending byte is sent as one batch
+pub const BATCH_WINDOW: Duration = Duration::from_millis(8);
+/// A batch is sent early once it reaches this size
+pub const MAX_BATCH: usize = 64 * 1024;
+/// Delivered but unacknowledged bytes at which delivery pauses. The queue
+/// behind it then fills up, the reader blocks, and the PTY or SSH channel
+/// throttles the remote program.
+pub const HIGH_WATER: u64 = 1024 * 1024;
+/// How long delivery waits for an ack before assuming the frontend stalled
+const ACK_TIMEOUT: Duration = Duration::from_secs(5);
+/// Reads queued between a session's reader and its delivery thread
+const QUEUE_DEPTH: usize = 32;
+
+/// Receives output batches; returns false once the receiving end is gone
+pub type Deliver = Box<dyn FnMut(&[u8]) -> bool + Send>;
+
+struct Subscriber {
+    deliver: Deliver,
+    /// Stream offset the subscriber starts at; earlier bytes came via replay
+    from: u64,
+}
+
+struct Flow {
+    subscriber: Option<Subscriber>,
+    /// Read-only viewers of a shared session. They never hold delivery back;
+    /// one whose `deliver` refuses a batch is dropped.
+    observers: Vec<Subscriber>,
+    /// Stream offset just past the last byte handed to the subscriber
+    delivered: u64,
+    /// Stream offset the subscriber has confirmed rendering up to
+    acked: u64,
+}
+
+struct Shared {
+    flow: Mutex<Flow>,
+    acked: Condvar,
+}
+
+/// Coalesces a session's output into batches on a delivery thread, and
+/// holds the reader back while the subscriber is more than `HIGH_WATER`
+/// bytes behind. Offsets count every byte pushed, like `Scrollback::total`.
+pub struct OutputStream {
+    tx: SyncSender<Vec<u8>>,
+    shared: Arc<Shared>,
+}
+
+impl OutputStream {
+    pub fn new(session_id: &str) -> Self {
+        let (tx, rx) = mpsc::sync_channel(QUEUE_DEPTH);
+        let shared = Arc::new(Shared {
+            flow: Mutex::new(Flow { subscriber: None, observers: Vec::new(), delivered: 0, acked: 0 }),
+            acked: Condvar::new(),
+        });
+        let worker = shared.clone();
+        let sid = session_id.to_string();
+        thread::spawn(move || {
+            run(rx, &worker);
+            log::info!("Output delivery for {} stopped", sid);
+        });
+        Self { tx, shared }
+    }
+
+    /// Queue a read. Blocks while the queue is full; false once the
+    /// delivery thread is gone.
+    pub fn push(&self, data: &[u8]) -> bool {
+        self.tx.send(data.to_vec()).is_ok()
+    }
+
+    /// Send output from stream offset `from` on to `deliver`, replacing the
+    /// previous subscriber
+    pub fn subscribe(&self, from: u64, deliver: Deliver) {
+        let mut flow = self.shared.flow.lock().unwrap();
+        flow.subscriber = Some(Subscriber { deliver, from });
+        flow.delivered = from;
+        flow.acked = from;
+        self.shared.acked.notify_all();
+    }
+
+    /// Also send output from stream offset `from` on to `deliver`, alongside
+    /// the subscriber
+    pub fn observe(&self, from: u64, deliver: Deliver) {
+        self.shared.flow.lock().unwrap().observers.push(Subscriber { deliver, from });
+    }
+
+    /// The subscriber has rendered everything before `offset`
+    pub fn ack(&self, offset: u64) {
+        let mut flow = self.shared.flow.lock().unwrap();
+        flow.acked = flow.acked.max(offset);
+        self.shared.acked.notify_all();
+    }
+}
+
+fn run(rx: Receiver<Vec<u8>>, shared: &Shared) {
+    let mut offset = 0u64;
+    while let Ok(mut batch) = rx.recv() {
+        let deadline = Instant::now() + BATCH_WINDOW;
+        let mut open = true;
+        while batch.len() < MAX_BATCH {
+            match rx.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(more) => batch.extend_from_slice(&more),
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => {
+                    open = false;
+                    break;
+                }
+            }
+        }
+        shared.deliver(offset, &batch);
+        offset += batch.len() as u64;
+        if !open {
+            break;
+        }
+    }
+}
+
+impl Shared {
+    /// Hand `batch`, which starts at stream offset `offset`, to the subscriber
+    fn deliver(&self, offset: u64, batch: &[u8]) {
+        let mut flow = self.flow.lock().unwrap();
+        let waiting = Instant::now();
+        while flow.subscriber.is_some() && flow.delivered.saturating_sub(flow.acked) >= HIGH_WATER {
+            let left = ACK_TIMEOUT.saturating_sub(waiting.elapsed());
+            if left.is_zero() {
+                log::warn!("No output ack for {:?}, resuming delivery", ACK_TIMEOUT);
+                flow.acked = flow.delivered;
+                break;
+            }
+            flow = self.acked.wait_timeout(flow, left).unwrap().0;
+        }
+        flow.observers.retain_mut(|observer| observer.send(offset, batch));
+        // Without a subscriber the scrollback keeps the output for replay
+        if let Some(subscriber) = flow.subscriber.as_mut() {
+            if subscriber.send(offset, batch) {
+                flow.delivered = offset + batch.len() as u64;
+            } else {
+                flow.subscriber = None;
+            }
+        }
+    }
+}
+
+impl Subscriber {
+    /// Hand over the part of `batch` at or after `from`; false once the
+    /// receiving end is gone
+    fn send(&mut self, offset: u64, batch: &[u8]) -> bool {
+        let skip = self.from.saturating_sub(offset).min(batch.len() as u64) as usize;
+        skip == batch.len() || (self.deliver)(&batch[skip..])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subscriber_skips_replayed_bytes() {
+        let shared = Shared {
+            flow: Mutex::new(Flow { subscriber: None, observers: Vec::new(), delivered: 0, acked: 0 }),
+            acked: Condvar::new(),
+        };
+        let got = Arc::new(Mutex::new(Vec::new()));
+        let sink = got.clone();
+        shared.flow.lock().unwrap().subscriber = Some(Subscriber {
+            deliver: Box::new(move |b| {
+                sink.lock().unwrap().extend_from_slice(b);
+                true
+            }),
+            from: 3,
+        });
+        shared.deliver(0, b"hel");
+        shared.deliver(3, b"lo");
+        shared.deliver(5, b" world");
+        assert_eq!(got.lock().unwrap().as_slice(), b"lo world");
+        assert_eq!(shared.flow.lock().unwrap().delivered, 11);
+    }
+
+    /// The reader -> batch -> deliver path under sustained output with an
+    /// acking consumer. Pushes 64 MiB, so it only runs with `--ignored`.
+    #[test]
+    #[ignore]
+    fn test_throughput_benchmark() {
+        const CHUNK: usize = 4096;
+        const TOTAL: usize = 64 * 1024 * 1024;
+
+        let stream = Arc::new(OutputStream::new("bench"));
+        let (batches_tx, batches_rx) = mpsc::channel::<usize>();
+        stream.subscribe(
+            0,
+            Box::new(move |batch| batches_tx.send(batch.len()).is_ok()),
+        );
+        let acker = stream.clone();
+        let consumer = thread::spawn(move || {
+            let (mut received, mut batches) = (0usize, 0usize);
+            while received < TOTAL {
+                received += batches_rx.recv().unwrap();
+                batches += 1;
+                acker.ack(received as u64);
+            }
+            batches
+        });
+
+        let chunk = vec![b'x'; CHUNK];
+        for _ in 0..TOTAL / CHUNK {
+            assert!(stream.push(&chunk));
+        }
+        let batches = consumer.join().unwrap();
+        assert!(batches * 4 <= TOTAL / CHUNK, "output was not coalesced");
+    }
+}
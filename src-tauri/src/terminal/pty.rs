@@ -1,50 +1,491 @@
-use portable_pty::{native_pty_system, CommandBuilder, MasterPty, PtySize};
+use portable_pty::{native_pty_system, CommandBuilder, PtySize};
 use std::collections::HashMap;
 use std::io::{Read, Write};
 use std::sync::{Arc, Mutex};
+use serde::Serialize;
 use std::thread;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 use tauri::{Emitter, Manager};
 
+use crate::audit::{AuditAction, AuditEntry};
+use crate::config::{OutputTrigger, SshBackend};
+use crate::ssh_target::SshTarget;
+use super::backend::{LocalShell, PtyBackend, SessionBackend, SessionKind, SessionKindName};
+use super::broadcast::{BroadcastGroup, Broadcasts};
+use super::multiplexer::{self, Attach};
+use super::output::{Deliver, OutputStream};
+#[cfg(feature = "russh")]
+use super::native;
+use super::recording::{self, Recorder};
+use super::scrollback::{Scrollback, SCROLLBACK_BYTES};
+use super::serial::{self, SerialConfig};
+use super::share::ShareManager;
+use super::startup::Startup;
+use super::triggers::{self, TriggerSet};
+
+/// Lifecycle of a session as reported by `list_sessions`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionStatus {
+    Running,
+    /// Connection dropped; a persistent session is being reopened
+    Reconnecting,
+    /// Ended but not yet closed by the frontend
+    Exited,
+}
+
+/// Per-session state shared with the output pump and kept across reconnects
+struct SessionState {
+    scrollback: Scrollback,
+    status: SessionStatus,
+    started_ms: u64,
+    bytes_sent: u64,
+    exit_code: Option<u32>,
+    /// Reconnects tried since the session last produced output
+    reconnect_attempts: u32,
+}
+
+impl SessionState {
+    fn new() -> Self {
+        Self {
+            scrollback: Scrollback::new(SCROLLBACK_BYTES),
+            status: SessionStatus::Running,
+            started_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+            bytes_sent: 0,
+            exit_code: None,
+            reconnect_attempts: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionInfo {
+    pub session_id: String,
+    pub kind: SessionKindName,
+    pub title: String,
+    /// Remote host, "localhost" or the serial device
+    pub host: String,
+    /// Remote or local account; empty for serial consoles
+    pub user: String,
+    pub device_id: Option<String>,
+    pub status: SessionStatus,
+    /// Unix time in milliseconds of the first connect
+    pub started_ms: u64,
+    /// Output received from the remote, across reconnects
+    pub bytes_received: u64,
+    pub bytes_sent: u64,
+    pub exit_code: Option<u32>,
+    pub recording: bool,
+    pub attach: Option<Attach>,
+}
+
+/// Buffered output for a re-subscribing frontend
+#[derive(Debug, Clone, Serialize)]
+pub struct Replay {
+    /// Base64
+    pub data: String,
+    /// Stream offsets of the first and one past the last replayed byte.
+    /// Pass `end` as `from` next time to fetch only what came after.
+    pub start: u64,
+    pub end: u64,
+}
+
+/// What a new viewer of a session starts from
+pub struct Observed {
+    pub title: String,
+    pub cols: u16,
+    pub rows: u16,
+    pub replay: Vec<u8>,
+}
+
 struct PtySession {
-    master: Arc<Mutex<Box<dyn MasterPty + Send>>>,
+    backend: Box<dyn SessionBackend>,
     writer: Arc<Mutex<Box<dyn Write + Send>>>,
-    _child: Box<dyn portable_pty::Child + Send + Sync>,
+    recorder: Arc<Mutex<Option<Recorder>>>,
+    state: Arc<Mutex<SessionState>>,
+    output: Arc<OutputStream>,
+    triggers: Arc<Mutex<TriggerSet>>,
+    kind: SessionKind,
+    attach: Option<Attach>,
+    cols: u16,
+    rows: u16,
+}
+
+impl PtySession {
+    fn start_recording(&self, session_id: &str, record_input: bool) -> Result<String, String> {
+        let mut recorder = self.recorder.lock().unwrap();
+        if let Some(active) = recorder.as_ref() {
+            return Ok(active.path().to_string_lossy().to_string());
+        }
+        let path = recording::recording_path(&recording::recordings_dir(), &self.kind.host(), session_id);
+        let title = self.kind.title();
+        let rec = Recorder::create(&path, self.cols, self.rows, Some(&title), record_input)?;
+        log::info!("Recording session {} to {}", session_id, path.display());
+        *recorder = Some(rec);
+        Ok(path.to_string_lossy().to_string())
+    }
+}
+
+/// Feed an event to the session's recorder, if any. A recorder that fails
+/// to write is dropped so the session itself keeps going.
+fn record(recorder: &Mutex<Option<Recorder>>, f: impl FnOnce(&mut Recorder) -> Result<(), String>) {
+    let mut guard = recorder.lock().unwrap();
+    if let Some(rec) = guard.as_mut() {
+        if let Err(e) = f(rec) {
+            log::warn!("Stopping recording {}: {}", rec.path().display(), e);
+            *guard = None;
+        }
+    }
+}
+
+/// Forwards a session's output to the frontend and its recording, and logs
+/// SSH sessions to the audit log when they end. Shared by all backends.
+struct OutputSink {
+    sid: String,
+    app_handle: tauri::AppHandle,
+    recorder: Arc<Mutex<Option<Recorder>>>,
+    state: Arc<Mutex<SessionState>>,
+    output: Arc<OutputStream>,
+    triggers: Arc<Mutex<TriggerSet>>,
+    /// For trigger notifications
+    title: String,
+    audit_entry: Option<AuditEntry>,
+    started: Instant,
+    total_bytes: usize,
+    cols: u16,
+    rows: u16,
+    /// Set for persistent sessions: what to reopen after a dropped connection
+    reconnect: Option<(SshTarget, SessionOptions)>,
+    /// For a recording started with the session
+    record_input: bool,
+    /// This is a reopened session that hasn't produced output yet. A spawned
+    /// ssh process isn't a connection, so the reconnect only counts once the
+    /// remote answers.
+    reconnecting: bool,
+}
+
+impl OutputSink {
+    /// Keep a read in the scrollback and recording and queue it for the
+    /// frontend. Blocks while the frontend is too far behind; returns false
+    /// if output can no longer be delivered.
+    fn chunk(&mut self, data: &[u8]) -> bool {
+        self.total_bytes += data.len();
+        self.state.lock().unwrap().scrollback.push(data);
+        record(&self.recorder, |rec| rec.output(data));
+        let hits = self.triggers.lock().unwrap().feed(data, Instant::now());
+        for hit in &hits {
+            triggers::fire(&self.app_handle, &self.sid, &self.title, hit);
+        }
+        if !self.output.push(data) {
+            log::error!("Output delivery for {} is gone, stopping reader", self.sid);
+            return false;
+        }
+        if std::mem::take(&mut self.reconnecting) {
+            self.state.lock().unwrap().reconnect_attempts = 0;
+            log::info!("Reconnected session {}", self.sid);
+            self.app_handle
+                .emit(&format!("terminal-reconnected-{}", self.sid), ())
+                .ok();
+        }
+        true
+    }
+
+    /// Setup failed before any output; only the audit log hears about it
+    fn fail(self, err: String) -> Result<(), String> {
+        if let Some(entry) = self.audit_entry {
+            entry.finish(self.started, &Err::<(), _>(err.clone()));
+        }
+        Err(err)
+    }
+
+    /// `dropped` means the connection was lost rather than the remote shell
+    /// exiting. Persistent sessions that the user hasn't closed are then
+    /// reconnected instead of reported as exited.
+    fn finish(self, outcome: Result<(), String>, exit_status: Option<u32>, dropped: bool) {
+        log::info!("Session {} ended (total {} bytes)", self.sid, self.total_bytes);
+        let still_open = self.app_handle.state::<TerminalManager>().has_session(&self.sid);
+        if let (true, true, Some((target, options))) = (dropped, still_open, self.reconnect) {
+            let lost = outcome.and(Err::<(), _>("Connection lost".to_string()));
+            if let Some(entry) = self.audit_entry {
+                entry.finish(self.started, &lost);
+            }
+            self.state.lock().unwrap().status = SessionStatus::Reconnecting;
+            schedule_reconnect(self.app_handle, self.sid, self.state, target, options);
+            return;
+        }
+        {
+            let mut state = self.state.lock().unwrap();
+            state.status = SessionStatus::Exited;
+            state.exit_code = exit_status;
+        }
+        // Observers are told the session is over and the listener closes
+        self.app_handle.state::<ShareManager>().stop(&self.sid, &self.app_handle);
+        self.app_handle
+            .emit(&format!("terminal-exit-{}", self.sid), exit_status)
+            .ok();
+        if let Some(entry) = self.audit_entry {
+            entry.finish(self.started, &outcome);
+        }
+    }
+}
+
+/// Forward everything `reader` yields to `sink` until EOF, a read error or
+/// the frontend going away
+fn pump(mut reader: impl Read, sink: &mut OutputSink) -> Result<(), String> {
+    let mut buf = [0u8; 4096];
+    loop {
+        match reader.read(&mut buf) {
+            Ok(0) => {
+                log::info!("EOF for {}", sink.sid);
+                return Ok(());
+            }
+            Ok(n) => {
+                if !sink.chunk(&buf[..n]) {
+                    return Ok(());
+                }
+            }
+            Err(e) => {
+                log::error!("Read error for {}: {}", sink.sid, e);
+                return Err(format!("Read error: {}", e));
+            }
+        }
+    }
+}
+
+/// Reopen a dropped persistent session under the same id, backing off
+/// between attempts. Output resumes on the same event channel, and the
+/// multiplexer on the remote restores the screen. The attempt count lives in
+/// the session state, so a reopened session that drops again before any
+/// output keeps backing off instead of starting over.
+fn schedule_reconnect(
+    app: tauri::AppHandle,
+    sid: String,
+    state: Arc<Mutex<SessionState>>,
+    target: SshTarget,
+    options: SessionOptions,
+) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let attempt = {
+                let mut state = state.lock().unwrap();
+                let attempt = state.reconnect_attempts;
+                if attempt >= multiplexer::MAX_RECONNECT_ATTEMPTS {
+                    break;
+                }
+                state.reconnect_attempts += 1;
+                attempt
+            };
+            let delay = multiplexer::backoff_delay(attempt);
+            app.emit(
+                &format!("terminal-reconnecting-{}", sid),
+                serde_json::json!({
+                    "attempt": attempt + 1,
+                    "delay_ms": delay.as_millis() as u64,
+                }),
+            )
+            .ok();
+            tokio::time::sleep(delay).await;
+
+            let manager = app.state::<TerminalManager>();
+            if !manager.has_session(&sid) {
+                return;
+            }
+            match manager.open(&sid, &target, options.clone(), app.clone()).await {
+                // terminal-reconnected follows with the first output
+                Ok(()) => {
+                    log::info!("Reopened session {} (attempt {})", sid, attempt + 1);
+                    return;
+                }
+                // Closed while this attempt was connecting
+                Err(_) if !manager.has_session(&sid) => return,
+                Err(e) => log::warn!("Reconnect {} for {} failed: {}", attempt + 1, sid, e),
+            }
+        }
+        log::error!("Giving up on reconnecting {}", sid);
+        app.state::<TerminalManager>().mark_exited(&sid);
+        app.state::<ShareManager>().stop(&sid, &app);
+        app.emit(&format!("terminal-exit-{}", sid), None::<u32>).ok();
+    });
+}
+
+/// How to open a terminal session
+#[derive(Debug, Clone, Default)]
+pub struct SessionOptions {
+    /// Record to an asciicast file
+    pub record: bool,
+    /// Keep keyboard input in the recording too, passwords included
+    pub record_input: bool,
+    /// Already resolved; `Auto` means the process backend
+    pub backend: SshBackend,
+    /// Run inside a tmux/screen session on the remote. Such sessions are
+    /// reconnected automatically when the connection drops.
+    pub attach: Option<Attach>,
+    /// Output triggers to start with, usually the device's
+    pub triggers: Vec<OutputTrigger>,
+    /// Directory, environment, startup command and ssh options
+    pub startup: Startup,
 }
 
 pub struct TerminalManager {
     sessions: Mutex<HashMap<String, PtySession>>,
+    broadcasts: Mutex<Broadcasts>,
 }
 
 impl TerminalManager {
     pub fn new() -> Self {
         Self {
             sessions: Mutex::new(HashMap::new()),
+            broadcasts: Mutex::new(Broadcasts::default()),
         }
     }
 
-    pub fn open_session(
+    /// Open (or, for an id that is already registered, reopen) a session
+    /// with the backend in `options`
+    pub async fn open(
         &self,
         session_id: &str,
-        host: &str,
-        user: &str,
-        port: Option<u16>,
+        target: &SshTarget,
+        options: SessionOptions,
         app_handle: tauri::AppHandle,
     ) -> Result<(), String> {
-        log::info!("Opening SSH session {} to {}@{}:{}", session_id, user, host, port.unwrap_or(22));
+        if let Some(attach) = &options.attach {
+            attach.validate()?;
+        }
+        match options.backend {
+            SshBackend::Native => {
+                self.open_native_session(session_id, target, options, app_handle)
+                    .await
+            }
+            _ => self.open_session(session_id, target, options, app_handle),
+        }
+    }
 
-        let pty_system = native_pty_system();
+    pub fn has_session(&self, session_id: &str) -> bool {
+        self.sessions.lock().unwrap().contains_key(session_id)
+    }
 
-        let pair = pty_system
-            .openpty(PtySize {
-                rows: 24,
-                cols: 80,
-                pixel_width: 0,
-                pixel_height: 0,
-            })
-            .map_err(|e| {
-                log::error!("Failed to open PTY: {}", e);
-                format!("Failed to open PTY: {}", e)
-            })?;
+    fn mark_exited(&self, session_id: &str) {
+        if let Some(session) = self.sessions.lock().unwrap().get(session_id) {
+            session.state.lock().unwrap().status = SessionStatus::Exited;
+        }
+    }
+
+    /// Output sink for a session being opened. When the id is already
+    /// registered (a reconnect), its recorder, scrollback and terminal size
+    /// carry over.
+    fn begin(
+        &self,
+        session_id: &str,
+        kind: &SessionKind,
+        options: &SessionOptions,
+        app_handle: tauri::AppHandle,
+    ) -> OutputSink {
+        let target = kind.ssh_target();
+        let sessions = self.sessions.lock().unwrap();
+        let reopening = sessions.contains_key(session_id);
+        let (recorder, state, output, triggers, cols, rows) = match sessions.get(session_id) {
+            Some(previous) => (
+                previous.recorder.clone(),
+                previous.state.clone(),
+                previous.output.clone(),
+                previous.triggers.clone(),
+                previous.cols,
+                previous.rows,
+            ),
+            None => (
+                Arc::new(Mutex::new(None)),
+                Arc::new(Mutex::new(SessionState::new())),
+                Arc::new(OutputStream::new(session_id)),
+                Arc::new(Mutex::new(TriggerSet::new(&options.triggers))),
+                80,
+                24,
+            ),
+        };
+        drop(sessions);
+        OutputSink {
+            sid: session_id.to_string(),
+            app_handle,
+            recorder,
+            state,
+            output,
+            triggers,
+            title: kind.title(),
+            audit_entry: target.map(|t| {
+                AuditEntry::new(AuditAction::TerminalSession, t).with_detail(session_id)
+            }),
+            started: Instant::now(),
+            total_bytes: 0,
+            cols,
+            rows,
+            // Reattaching returns to a shell that is already set up
+            reconnect: target.filter(|_| options.attach.is_some()).map(|t| {
+                let mut options = options.clone();
+                options.startup.command = None;
+                (t.clone(), options)
+            }),
+            reconnecting: reopening,
+            record_input: options.record_input,
+        }
+    }
+
+    /// Make the opened session reachable under its id. A reopen whose session
+    /// was closed in the meantime is stopped again instead of resurrected.
+    fn register(
+        &self,
+        sink: &OutputSink,
+        kind: SessionKind,
+        backend: Box<dyn SessionBackend>,
+        writer: Box<dyn Write + Send>,
+        record_session: bool,
+    ) -> Result<(), String> {
+        let mut session = PtySession {
+            backend,
+            writer: Arc::new(Mutex::new(writer)),
+            recorder: sink.recorder.clone(),
+            state: sink.state.clone(),
+            output: sink.output.clone(),
+            triggers: sink.triggers.clone(),
+            kind,
+            attach: sink.reconnect.as_ref().and_then(|(_, o)| o.attach.clone()),
+            cols: sink.cols,
+            rows: sink.rows,
+        };
+        let mut sessions = self.sessions.lock().unwrap();
+        if sink.reconnecting && !sessions.contains_key(&sink.sid) {
+            drop(sessions);
+            session.backend.close();
+            return Err(format!("Session {} was closed while reconnecting", sink.sid));
+        }
+        session.state.lock().unwrap().status = SessionStatus::Running;
+        if record_session {
+            if let Err(e) = session.start_recording(&sink.sid, sink.record_input) {
+                log::warn!("Could not start recording for {}: {}", sink.sid, e);
+            }
+        }
+        sessions.insert(sink.sid.clone(), session);
+        Ok(())
+    }
+
+    pub fn open_session(
+        &self,
+        session_id: &str,
+        target: &SshTarget,
+        options: SessionOptions,
+        app_handle: tauri::AppHandle,
+    ) -> Result<(), String> {
+        log::info!(
+            "Opening SSH session {} to {}:{}{}",
+            session_id,
+            target.destination(),
+            target.port.unwrap_or(22),
+            target.proxy_jump().map(|j| format!(" via {}", j)).unwrap_or_default()
+        );
+
+        let kind = SessionKind::Ssh(target.clone());
+        let sink = self.begin(session_id, &kind, &options, app_handle);
 
         let home_dir = dirs::home_dir()
             .map(|h| h.to_string_lossy().to_string())
@@ -61,11 +502,13 @@ impl TerminalManager {
             "-o".to_string(), "ConnectTimeout=10".to_string(),
             "-tt".to_string(),
         ];
-        if let Some(p) = port {
-            ssh_args.push("-p".to_string());
-            ssh_args.push(p.to_string());
+        ssh_args.extend(options.startup.ssh_args());
+        ssh_args.extend(target.ssh_args());
+        ssh_args.push(target.destination());
+        let attach = options.attach.as_ref().map(Attach::remote_command);
+        if let Some(command) = options.startup.remote_command(attach) {
+            ssh_args.push(command);
         }
-        ssh_args.push(format!("{}@{}", user, host));
         cmd.args(ssh_args.iter().map(|s| s.as_str()).collect::<Vec<_>>());
 
         cmd.env("HOME", &home_dir);
@@ -84,15 +527,95 @@ impl TerminalManager {
 
         cmd.cwd(&home_dir);
 
-        log::info!("Spawning SSH to {}@{}", user, host);
+        log::info!("Spawning SSH to {}", target.destination());
+
+        // ssh exits with 255 when the connection itself failed
+        self.spawn_pty(sink, kind, cmd, options.record, |status| status == Some(255))?;
+        log::info!("SSH session {} setup complete", session_id);
+        Ok(())
+    }
+
+    /// Open a shell on this machine
+    pub fn open_local_session(
+        &self,
+        session_id: &str,
+        shell: LocalShell,
+        record_session: bool,
+        app_handle: tauri::AppHandle,
+    ) -> Result<(), String> {
+        log::info!("Opening local shell {} ({})", session_id, shell.program());
+        let cmd = shell.command()?;
+        let kind = SessionKind::Local(shell);
+        let options = SessionOptions { record: record_session, ..Default::default() };
+        let sink = self.begin(session_id, &kind, &options, app_handle);
+        self.spawn_pty(sink, kind, cmd, record_session, |_| false)
+    }
+
+    /// Open a serial console. Input and output are passed through raw; the
+    /// terminal size is not communicated to the device.
+    pub fn open_serial_session(
+        &self,
+        session_id: &str,
+        config: SerialConfig,
+        record_session: bool,
+        app_handle: tauri::AppHandle,
+    ) -> Result<(), String> {
+        log::info!(
+            "Opening serial console {} on {} at {} baud",
+            session_id,
+            config.path,
+            config.baud_rate
+        );
+        let port = serial::open(&config)?;
+        let kind = SessionKind::Serial(config);
+        let options = SessionOptions { record: record_session, ..Default::default() };
+        let mut sink = self.begin(session_id, &kind, &options, app_handle);
+        let backend = Box::new(port.backend);
+        if let Err(e) = self.register(&sink, kind, backend, Box::new(port.writer), record_session) {
+            return sink.fail(e);
+        }
+
+        let reader = port.reader;
+        thread::spawn(move || {
+            let outcome = pump(reader, &mut sink);
+            sink.finish(outcome, None, false);
+        });
+        Ok(())
+    }
+
+    /// Run `cmd` in a new PTY and forward its output to `sink`.
+    /// `exit_dropped` tells from the exit status whether a connection was
+    /// lost, as opposed to the shell exiting.
+    fn spawn_pty(
+        &self,
+        mut sink: OutputSink,
+        kind: SessionKind,
+        cmd: CommandBuilder,
+        record_session: bool,
+        exit_dropped: fn(Option<u32>) -> bool,
+    ) -> Result<(), String> {
+        let pty_system = native_pty_system();
 
-        let child = pair
-            .slave
-            .spawn_command(cmd)
-            .map_err(|e| {
-                log::error!("Failed to spawn SSH: {}", e);
-                format!("Failed to spawn ssh: {}", e)
-            })?;
+        let pair = match pty_system.openpty(PtySize {
+            rows: sink.rows,
+            cols: sink.cols,
+            pixel_width: 0,
+            pixel_height: 0,
+        }) {
+            Ok(pair) => pair,
+            Err(e) => {
+                log::error!("Failed to open PTY: {}", e);
+                return sink.fail(format!("Failed to open PTY: {}", e));
+            }
+        };
+
+        let child = match pair.slave.spawn_command(cmd) {
+            Ok(child) => child,
+            Err(e) => {
+                log::error!("Failed to spawn {}: {}", kind.title(), e);
+                return sink.fail(format!("Failed to spawn {}: {}", kind.title(), e));
+            }
+        };
 
         drop(pair.slave);
 
@@ -101,81 +624,111 @@ impl TerminalManager {
             .take_writer()
             .map_err(|e| format!("Failed to get PTY writer: {}", e))?;
 
-        let mut reader = pair
+        let reader = pair
             .master
             .try_clone_reader()
             .map_err(|e| format!("Failed to get PTY reader: {}", e))?;
 
-        let master = Arc::new(Mutex::new(pair.master));
-        let writer = Arc::new(Mutex::new(writer));
+        let child = Arc::new(Mutex::new(child));
+        let backend = Box::new(PtyBackend { master: pair.master, child: child.clone() });
+        if let Err(e) = self.register(&sink, kind, backend, writer, record_session) {
+            return sink.fail(e);
+        }
+
+        // Spawn reader thread — forwards PTY output to the session's output stream
+        thread::spawn(move || {
+            log::info!("PTY reader thread started for {}", sink.sid);
+            let outcome = pump(reader, &mut sink);
+            let exit_status = child.lock().unwrap().wait().ok().map(|s| s.exit_code());
+            let dropped = outcome.is_err() || exit_dropped(exit_status);
+            sink.finish(outcome, exit_status, dropped);
+        });
+
+        Ok(())
+    }
+
+    /// Open a session with the in-process SSH client. Connection and auth
+    /// failures are also emitted as a structured `terminal-error-{id}` event.
+    #[cfg(feature = "russh")]
+    pub async fn open_native_session(
+        &self,
+        session_id: &str,
+        target: &SshTarget,
+        options: SessionOptions,
+        app_handle: tauri::AppHandle,
+    ) -> Result<(), String> {
+        log::info!(
+            "Opening native SSH session {} to {}:{}{}",
+            session_id,
+            target.destination(),
+            target.port.unwrap_or(22),
+            target.proxy_jump().map(|j| format!(" via {}", j)).unwrap_or_default()
+        );
+
+        let kind = SessionKind::Ssh(target.clone());
+        let mut sink = self.begin(session_id, &kind, &options, app_handle);
+        if !options.startup.ssh_args().is_empty() {
+            log::warn!("Native SSH ignores forwarding and ssh options for {}", session_id);
+        }
+        let attach = options.attach.as_ref().map(Attach::remote_command);
+        let command = options.startup.remote_command(attach);
 
-        let session = PtySession {
-            master: master.clone(),
-            writer: writer.clone(),
-            _child: child,
+        let opened = match native::connect(target).await {
+            Ok(conn) => native::open_shell(&conn, sink.cols, sink.rows, command.as_deref())
+                .await
+                .map(|channel| (conn, channel)),
+            Err(e) => Err(e),
+        };
+        let (conn, channel) = match opened {
+            Ok(opened) => opened,
+            Err(e) => {
+                log::error!("Native SSH to {} failed: {}", target.destination(), e);
+                sink.app_handle
+                    .emit(&format!("terminal-error-{}", session_id), &e)
+                    .ok();
+                return sink.fail(e.to_string());
+            }
         };
 
-        self.sessions
-            .lock()
-            .unwrap()
-            .insert(session_id.to_string(), session);
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let writer = Box::new(native::ChannelWriter(tx.clone()));
+        let backend = Box::new(native::ShellBackend(tx));
+        if let Err(e) = self.register(&sink, kind, backend, writer, options.record) {
+            return sink.fail(e);
+        }
 
-        // Spawn reader thread — forwards PTY output to frontend via Tauri events
-        let sid = session_id.to_string();
-        thread::spawn(move || {
-            log::info!("PTY reader thread started for {}", sid);
-            let mut buf = [0u8; 4096];
-            let mut total_bytes = 0usize;
-            loop {
-                match reader.read(&mut buf) {
-                    Ok(0) => {
-                        log::info!("PTY EOF for {} (total {} bytes)", sid, total_bytes);
-                        break;
-                    }
-                    Ok(n) => {
-                        total_bytes += n;
-                        // Encode as base64 to avoid JSON serialization issues with Vec<u8>
-                        use base64::Engine;
-                        let b64 = base64::engine::general_purpose::STANDARD.encode(&buf[..n]);
-                        let event_name = format!("terminal-output-{}", sid);
-                        // Try emit_to main window first, fall back to broadcast emit
-                        let emit_result = if let Some(window) = app_handle.get_webview_window("main") {
-                            window.emit(&event_name, &b64)
-                        } else {
-                            app_handle.emit(&event_name, &b64)
-                        };
-                        match emit_result {
-                            Ok(_) => {},
-                            Err(e) => {
-                                log::error!("emit failed for {} ({} bytes): {:?}", sid, n, e);
-                                std::thread::sleep(std::time::Duration::from_millis(100));
-                                // Retry once with broadcast
-                                if app_handle.emit(&event_name, &b64).is_err() {
-                                    log::error!("emit retry also failed, stopping reader for {}", sid);
-                                    break;
-                                }
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        log::error!("PTY read error for {}: {}", sid, e);
-                        break;
-                    }
-                }
+        tauri::async_runtime::spawn(async move {
+            // chunk() blocks under backpressure, so keep it off the async workers
+            let result = native::run_shell(conn, channel, rx, |data| {
+                tokio::task::block_in_place(|| sink.chunk(data))
+            })
+            .await;
+            match result {
+                // No exit status means the channel went away under the shell
+                Ok(exit_status) => sink.finish(Ok(()), exit_status, exit_status.is_none()),
+                Err(e) => sink.finish(Err(e), None, true),
             }
-            app_handle
-                .emit(&format!("terminal-exit-{}", sid), ())
-                .ok();
-            log::info!("PTY reader thread ended for {}", sid);
         });
 
-        log::info!("SSH session {} setup complete", session_id);
+        log::info!("Native SSH session {} setup complete", session_id);
         Ok(())
     }
 
+    #[cfg(not(feature = "russh"))]
+    pub async fn open_native_session(
+        &self,
+        _session_id: &str,
+        _target: &SshTarget,
+        _options: SessionOptions,
+        _app_handle: tauri::AppHandle,
+    ) -> Result<(), String> {
+        Err("This build has no native SSH backend (enable the `russh` feature)".to_string())
+    }
+
     pub fn write_session(&self, session_id: &str, data: &[u8]) -> Result<(), String> {
         let sessions = self.sessions.lock().unwrap();
         let session = sessions.get(session_id).ok_or("Session not found")?;
+        record(&session.recorder, |rec| rec.input(data));
         let mut writer = session.writer.lock().unwrap();
         writer
             .write_all(data)
@@ -183,28 +736,245 @@ impl TerminalManager {
         writer
             .flush()
             .map_err(|e| format!("Flush failed: {}", e))?;
+        session.state.lock().unwrap().bytes_sent += data.len() as u64;
         Ok(())
     }
 
-    pub fn resize_session(&self, session_id: &str, cols: u16, rows: u16) -> Result<(), String> {
+    /// Keyboard input typed into `session_id`: goes to every receiving member
+    /// of its broadcast group, or only to the session itself. Only a failure
+    /// on the originating session is an error.
+    pub fn write_input(&self, session_id: &str, data: &[u8]) -> Result<(), String> {
+        let targets = self.broadcasts.lock().unwrap().fan_out(session_id);
+        for target in targets.iter().filter(|t| *t != session_id) {
+            if let Err(e) = self.write_session(target, data) {
+                log::warn!("Broadcast from {} to {} failed: {}", session_id, target, e);
+            }
+        }
+        self.write_session(session_id, data)
+    }
+
+    pub fn broadcast_groups(&self) -> Vec<BroadcastGroup> {
+        self.broadcasts.lock().unwrap().list()
+    }
+
+    pub fn broadcast_create(
+        &self,
+        name: &str,
+        members: &[String],
+        app: &tauri::AppHandle,
+    ) -> Result<BroadcastGroup, String> {
+        if members.is_empty() {
+            return Err("A broadcast group needs at least one session".to_string());
+        }
+        if let Some(missing) = members.iter().find(|m| !self.has_session(m)) {
+            return Err(format!("Session '{}' not found", missing));
+        }
+        let group = self.broadcasts.lock().unwrap().create(name, members);
+        self.announce_broadcast(app, &group.members);
+        Ok(group)
+    }
+
+    pub fn broadcast_join(
+        &self,
+        group_id: &str,
+        session_id: &str,
+        app: &tauri::AppHandle,
+    ) -> Result<(), String> {
+        if !self.has_session(session_id) {
+            return Err("Session not found".to_string());
+        }
+        self.broadcasts.lock().unwrap().add(group_id, session_id)?;
+        self.announce_broadcast(app, &[session_id.to_string()]);
+        Ok(())
+    }
+
+    pub fn broadcast_leave(&self, session_id: &str, app: &tauri::AppHandle) {
+        if self.broadcasts.lock().unwrap().remove(session_id).is_some() {
+            self.announce_broadcast(app, &[session_id.to_string()]);
+        }
+    }
+
+    /// Per-session opt-out: the session stays in its group but stops
+    /// receiving (and sending) broadcast input
+    pub fn broadcast_exclude(
+        &self,
+        session_id: &str,
+        excluded: bool,
+        app: &tauri::AppHandle,
+    ) -> Result<(), String> {
+        self.broadcasts
+            .lock()
+            .unwrap()
+            .set_excluded(session_id, excluded)?;
+        self.announce_broadcast(app, &[session_id.to_string()]);
+        Ok(())
+    }
+
+    pub fn broadcast_dissolve(&self, group_id: &str, app: &tauri::AppHandle) -> Result<(), String> {
+        let members = self.broadcasts.lock().unwrap().dissolve(group_id)?;
+        self.announce_broadcast(app, &members);
+        Ok(())
+    }
+
+    /// Tell each session's tab whether it is part of a broadcast
+    fn announce_broadcast(&self, app: &tauri::AppHandle, sessions: &[String]) {
+        let broadcasts = self.broadcasts.lock().unwrap();
+        for sid in sessions {
+            app.emit(&format!("terminal-broadcast-{}", sid), broadcasts.marker(sid))
+                .ok();
+        }
+    }
+
+    /// Make `deliver` the receiver of the session's live output, replacing
+    /// any previous one. Returns the output held so far; live output picks
+    /// up exactly where it ends.
+    pub fn subscribe(&self, session_id: &str, deliver: Deliver) -> Result<Replay, String> {
+        let sessions = self.sessions.lock().unwrap();
+        let session = sessions.get(session_id).ok_or("Session not found")?;
+        // Holding the state lock keeps new reads out of the scrollback until
+        // the subscriber is in place
+        let state = session.state.lock().unwrap();
+        let scrollback = &state.scrollback;
+        session.output.subscribe(scrollback.total(), deliver);
+        use base64::Engine;
+        Ok(Replay {
+            data: base64::engine::general_purpose::STANDARD.encode(scrollback.since(0)),
+            start: scrollback.start(),
+            end: scrollback.total(),
+        })
+    }
+
+    /// Add a read-only viewer of the session's output. Returns the session's
+    /// title and size and the output buffered so far, which `deliver`
+    /// continues from.
+    pub fn observe(&self, session_id: &str, deliver: Deliver) -> Result<Observed, String> {
         let sessions = self.sessions.lock().unwrap();
         let session = sessions.get(session_id).ok_or("Session not found")?;
-        let master = session.master.lock().unwrap();
-        master
-            .resize(PtySize {
-                rows,
-                cols,
-                pixel_width: 0,
-                pixel_height: 0,
+        let state = session.state.lock().unwrap();
+        session.output.observe(state.scrollback.total(), deliver);
+        Ok(Observed {
+            title: session.kind.title(),
+            cols: session.cols,
+            rows: session.rows,
+            replay: state.scrollback.since(0),
+        })
+    }
+
+    /// The subscriber rendered output up to stream offset `offset`
+    pub fn ack_output(&self, session_id: &str, offset: u64) -> Result<(), String> {
+        let sessions = self.sessions.lock().unwrap();
+        let session = sessions.get(session_id).ok_or("Session not found")?;
+        session.output.ack(offset);
+        Ok(())
+    }
+
+    pub fn triggers(&self, session_id: &str) -> Result<Vec<OutputTrigger>, String> {
+        let sessions = self.sessions.lock().unwrap();
+        let session = sessions.get(session_id).ok_or("Session not found")?;
+        let triggers = session.triggers.lock().unwrap().triggers();
+        Ok(triggers)
+    }
+
+    /// Replace the session's triggers, e.g. to add one for a single run.
+    /// Survives reconnects but isn't saved to the device.
+    pub fn set_triggers(&self, session_id: &str, triggers: &[OutputTrigger]) -> Result<(), String> {
+        let sessions = self.sessions.lock().unwrap();
+        let session = sessions.get(session_id).ok_or("Session not found")?;
+        session.triggers.lock().unwrap().replace(triggers)?;
+        Ok(())
+    }
+
+    /// Buffered output from stream offset `from` (default: all that is held)
+    pub fn replay(&self, session_id: &str, from: Option<u64>) -> Result<Replay, String> {
+        let sessions = self.sessions.lock().unwrap();
+        let session = sessions.get(session_id).ok_or("Session not found")?;
+        let state = session.state.lock().unwrap();
+        let scrollback = &state.scrollback;
+        let start = from.unwrap_or(0).max(scrollback.start()).min(scrollback.total());
+        use base64::Engine;
+        Ok(Replay {
+            data: base64::engine::general_purpose::STANDARD.encode(scrollback.since(start)),
+            start,
+            end: scrollback.total(),
+        })
+    }
+
+    /// All sessions, including ended ones the frontend hasn't closed yet
+    pub fn list_sessions(&self) -> Vec<SessionInfo> {
+        let sessions = self.sessions.lock().unwrap();
+        let mut list: Vec<SessionInfo> = sessions
+            .iter()
+            .map(|(id, session)| {
+                let state = session.state.lock().unwrap();
+                let target = session.kind.ssh_target();
+                let user = match &session.kind {
+                    SessionKind::Ssh(target) => target.user.clone(),
+                    SessionKind::Local(_) => crate::audit::local_user(),
+                    SessionKind::Serial(_) => String::new(),
+                };
+                SessionInfo {
+                    session_id: id.clone(),
+                    kind: session.kind.name(),
+                    title: session.kind.title(),
+                    host: session.kind.host(),
+                    user,
+                    device_id: target.and_then(|t| t.device_id.clone()),
+                    status: state.status,
+                    started_ms: state.started_ms,
+                    bytes_received: state.scrollback.total(),
+                    bytes_sent: state.bytes_sent,
+                    exit_code: state.exit_code,
+                    recording: session.recorder.lock().unwrap().is_some(),
+                    attach: session.attach.clone(),
+                }
             })
-            .map_err(|e| format!("Resize failed: {}", e))?;
+            .collect();
+        list.sort_by_key(|s| s.started_ms);
+        list
+    }
+
+    pub fn resize_session(&self, session_id: &str, cols: u16, rows: u16) -> Result<(), String> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let session = sessions.get_mut(session_id).ok_or("Session not found")?;
+        session.backend.resize(cols, rows)?;
+        session.cols = cols;
+        session.rows = rows;
+        record(&session.recorder, |rec| rec.resize(cols, rows));
         Ok(())
     }
 
+    /// Start recording a running session, with keyboard input if
+    /// `record_input`. Returns the .cast file path; if the session is already
+    /// being recorded, the existing path.
+    pub fn start_recording(&self, session_id: &str, record_input: bool) -> Result<String, String> {
+        let sessions = self.sessions.lock().unwrap();
+        let session = sessions.get(session_id).ok_or("Session not found")?;
+        session.start_recording(session_id, record_input)
+    }
+
+    /// Stop recording. Returns the finished file, or None if the session
+    /// wasn't being recorded.
+    pub fn stop_recording(&self, session_id: &str) -> Result<Option<String>, String> {
+        let sessions = self.sessions.lock().unwrap();
+        let session = sessions.get(session_id).ok_or("Session not found")?;
+        let stopped = session.recorder.lock().unwrap().take();
+        Ok(stopped.map(|rec| rec.path().to_string_lossy().to_string()))
+    }
+
+    pub fn is_recording(&self, session_id: &str) -> bool {
+        self.sessions
+            .lock()
+            .unwrap()
+            .get(session_id)
+            .is_some_and(|s| s.recorder.lock().unwrap().is_some())
+    }
+
     pub fn close_session(&self, session_id: &str) -> Result<(), String> {
+        self.broadcasts.lock().unwrap().remove(session_id);
         let mut sessions = self.sessions.lock().unwrap();
-        if let Some(_session) = sessions.remove(session_id) {
-            log::info!("Closed SSH session {}", session_id);
+        if let Some(mut session) = sessions.remove(session_id) {
+            session.backend.close();
+            log::info!("Closed session {}", session_id);
         }
         Ok(())
     }
@@ -0,0 +1,247 @@
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Writes a terminal session as an asciicast v2 file
+/// (https://docs.asciinema.org/manual/asciicast/v2/): a JSON header line
+/// followed by one `[seconds, code, data]` line per event.
+///
+/// Input is only recorded when asked for, like asciinema's `--stdin`: it
+/// includes anything typed at a password prompt that the remote side
+/// doesn't echo. The file is readable by its owner only.
+pub struct Recorder {
+    out: BufWriter<File>,
+    path: PathBuf,
+    started: Instant,
+    record_input: bool,
+    /// Trailing bytes of a UTF-8 sequence split across reads
+    output_carry: Vec<u8>,
+    input_carry: Vec<u8>,
+}
+
+#[derive(Serialize)]
+struct Header<'a> {
+    version: u32,
+    width: u16,
+    height: u16,
+    timestamp: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<&'a str>,
+    env: serde_json::Value,
+}
+
+/// A finished or in-progress recording on disk
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordingInfo {
+    pub name: String,
+    pub path: String,
+    pub size: u64,
+    pub modified_ms: u64,
+}
+
+impl Recorder {
+    pub fn create(
+        path: &Path,
+        cols: u16,
+        rows: u16,
+        title: Option<&str>,
+        record_input: bool,
+    ) -> Result<Self, String> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create recordings dir: {}", e))?;
+        }
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let file = options
+            .open(path)
+            .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+        let mut recorder = Self {
+            out: BufWriter::new(file),
+            path: path.to_path_buf(),
+            started: Instant::now(),
+            record_input,
+            output_carry: Vec::new(),
+            input_carry: Vec::new(),
+        };
+        let header = Header {
+            version: 2,
+            width: cols,
+            height: rows,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            title,
+            env: serde_json::json!({ "TERM": "xterm-256color" }),
+        };
+        let line = serde_json::to_string(&header).map_err(|e| e.to_string())?;
+        recorder.write_line(&line)?;
+        Ok(recorder)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn output(&mut self, data: &[u8]) -> Result<(), String> {
+        let text = decode_utf8(&mut self.output_carry, data);
+        self.event("o", &text)
+    }
+
+    /// Keyboard input; ignored unless the recorder was created to keep it
+    pub fn input(&mut self, data: &[u8]) -> Result<(), String> {
+        if !self.record_input {
+            return Ok(());
+        }
+        let text = decode_utf8(&mut self.input_carry, data);
+        self.event("i", &text)
+    }
+
+    pub fn resize(&mut self, cols: u16, rows: u16) -> Result<(), String> {
+        self.event("r", &format!("{}x{}", cols, rows))
+    }
+
+    fn event(&mut self, code: &str, data: &str) -> Result<(), String> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let line = serde_json::to_string(&(elapsed, code, data)).map_err(|e| e.to_string())?;
+        self.write_line(&line)
+    }
+
+    fn write_line(&mut self, line: &str) -> Result<(), String> {
+        // Flushed per event so a crash loses at most the current chunk
+        writeln!(self.out, "{}", line)
+            .and_then(|_| self.out.flush())
+            .map_err(|e| format!("Failed to write recording: {}", e))
+    }
+}
+
+/// Decode `data` as UTF-8, holding back an incomplete trailing sequence
+/// until the next chunk arrives. Invalid bytes become U+FFFD.
+fn decode_utf8(carry: &mut Vec<u8>, data: &[u8]) -> String {
+    carry.extend_from_slice(data);
+    let keep = match std::str::from_utf8(carry) {
+        Ok(_) => 0,
+        Err(e) if e.error_len().is_none() => carry.len() - e.valid_up_to(),
+        Err(_) => 0,
+    };
+    let rest = carry.split_off(carry.len() - keep);
+    let text = String::from_utf8_lossy(carry).into_owned();
+    *carry = rest;
+    text
+}
+
+pub fn recordings_dir() -> PathBuf {
+    crate::config::config_path()
+        .parent()
+        .map(|p| p.join("recordings"))
+        .unwrap_or_else(|| PathBuf::from("recordings"))
+}
+
+/// `<unix ms>-<host>-<session>.cast`, with anything unsafe in a file name
+/// replaced
+pub fn recording_path(dir: &Path, host: &str, session_id: &str) -> PathBuf {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    let safe = |s: &str| -> String {
+        s.chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '.' { c } else { '_' })
+            .collect()
+    };
+    dir.join(format!("{}-{}-{}.cast", now, safe(host), safe(session_id)))
+}
+
+/// Recordings in `dir`, newest first
+pub fn list_recordings(dir: &Path) -> Result<Vec<RecordingInfo>, String> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let entries = fs::read_dir(dir).map_err(|e| format!("Failed to read recordings: {}", e))?;
+    let mut recordings: Vec<RecordingInfo> = entries
+        .flatten()
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "cast"))
+        .filter_map(|e| {
+            let meta = e.metadata().ok()?;
+            let modified_ms = meta
+                .modified()
+                .ok()?
+                .duration_since(UNIX_EPOCH)
+                .ok()?
+                .as_millis() as u64;
+            Some(RecordingInfo {
+                name: e.file_name().to_string_lossy().to_string(),
+                path: e.path().to_string_lossy().to_string(),
+                size: meta.len(),
+                modified_ms,
+            })
+        })
+        .collect();
+    recordings.sort_by_key(|r| std::cmp::Reverse(r.modified_ms));
+    Ok(recordings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_utf8_is_carried_over() {
+        let mut carry = Vec::new();
+        let bytes = "héllo".as_bytes();
+        assert_eq!(decode_utf8(&mut carry, &bytes[..2]), "h");
+        assert_eq!(carry.len(), 1);
+        assert_eq!(decode_utf8(&mut carry, &bytes[2..]), "éllo");
+        assert!(carry.is_empty());
+        assert_eq!(decode_utf8(&mut carry, &[0xff, b'a']), "\u{fffd}a");
+    }
+
+    #[test]
+    fn test_writes_asciicast_v2() {
+        let path = std::env::temp_dir().join(format!("remotelab-rec-{}.cast", std::process::id()));
+        let mut rec = Recorder::create(&path, 80, 24, Some("lab@gpu1"), true).unwrap();
+        rec.output(b"$ ").unwrap();
+        rec.input(b"ls\r").unwrap();
+        rec.resize(120, 40).unwrap();
+        drop(rec);
+
+        let text = fs::read_to_string(&path).unwrap();
+        let lines: Vec<serde_json::Value> =
+            text.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(lines[0]["version"], 2);
+        assert_eq!(lines[0]["width"], 80);
+        assert_eq!(lines[0]["title"], "lab@gpu1");
+        assert_eq!(lines[1][1], "o");
+        assert_eq!(lines[2][2], "ls\r");
+        assert_eq!(lines[3][2], "120x40");
+        fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_input_is_opt_in_and_file_is_private() {
+        let path = std::env::temp_dir().join(format!("remotelab-rec-noinput-{}.cast", std::process::id()));
+        let mut rec = Recorder::create(&path, 80, 24, None, false).unwrap();
+        rec.output(b"[sudo] password: ").unwrap();
+        rec.input(b"hunter2\r").unwrap();
+        drop(rec);
+
+        let text = fs::read_to_string(&path).unwrap();
+        assert_eq!(text.lines().count(), 2);
+        assert!(!text.contains("hunter2"));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        fs::remove_file(&path).ok();
+    }
+}
@@ -0,0 +1,69 @@
+use std::collections::VecDeque;
+
+/// Raw output kept per session for replay after the webview reloads
+pub const SCROLLBACK_BYTES: usize = 1024 * 1024;
+
+/// Bounded ring buffer of a session's most recent output. Once full, the
+/// oldest bytes are dropped, which may cut an escape sequence at the start
+/// of a replay; xterm.js skips the fragment.
+pub struct Scrollback {
+    buf: VecDeque<u8>,
+    capacity: usize,
+    /// Bytes ever pushed, i.e. the stream offset just past the newest byte
+    total: u64,
+}
+
+impl Scrollback {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            buf: VecDeque::with_capacity(capacity.min(64 * 1024)),
+            capacity,
+            total: 0,
+        }
+    }
+
+    pub fn push(&mut self, data: &[u8]) {
+        self.total += data.len() as u64;
+        let data = &data[data.len().saturating_sub(self.capacity)..];
+        let overflow = (self.buf.len() + data.len()).saturating_sub(self.capacity);
+        self.buf.drain(..overflow);
+        self.buf.extend(data);
+    }
+
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Stream offset of the oldest byte still held
+    pub fn start(&self) -> u64 {
+        self.total - self.buf.len() as u64
+    }
+
+    /// Held output from stream offset `from` on (clamped to what is still
+    /// held), so a client that saw part of the stream only gets the rest
+    pub fn since(&self, from: u64) -> Vec<u8> {
+        let skip = from.saturating_sub(self.start()).min(self.buf.len() as u64) as usize;
+        self.buf.range(skip..).copied().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ring_drops_oldest() {
+        let mut sb = Scrollback::new(8);
+        sb.push(b"hello");
+        sb.push(b" world");
+        assert_eq!(sb.since(0), b"lo world");
+        assert_eq!((sb.start(), sb.total()), (3, 11));
+        assert_eq!(sb.since(9), b"ld");
+        assert!(sb.since(11).is_empty());
+
+        // A single chunk larger than the buffer keeps its tail
+        sb.push(b"0123456789");
+        assert_eq!(sb.since(0), b"23456789");
+        assert_eq!(sb.total(), 21);
+    }
+}
@@ -0,0 +1,230 @@
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::backend::SessionBackend;
+
+/// How long a read blocks before the reader checks whether it was closed
+const READ_TIMEOUT: Duration = Duration::from_millis(200);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Parity {
+    #[default]
+    None,
+    Odd,
+    Even,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FlowControl {
+    #[default]
+    None,
+    /// XON/XOFF
+    Software,
+    /// RTS/CTS
+    Hardware,
+}
+
+/// Line settings for a serial console, 8N1 without flow control by default
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SerialConfig {
+    /// e.g. /dev/ttyUSB0 or COM3
+    pub path: String,
+    pub baud_rate: u32,
+    #[serde(default)]
+    pub parity: Parity,
+    #[serde(default)]
+    pub flow_control: FlowControl,
+    #[serde(default = "default_data_bits")]
+    pub data_bits: u8,
+    #[serde(default = "default_stop_bits")]
+    pub stop_bits: u8,
+}
+
+fn default_data_bits() -> u8 {
+    8
+}
+
+fn default_stop_bits() -> u8 {
+    1
+}
+
+/// A serial port present on this machine
+#[derive(Debug, Clone, Serialize)]
+pub struct SerialPortInfo {
+    pub path: String,
+    /// "usb", "pci", "bluetooth" or "unknown"
+    pub port_type: String,
+    /// USB adapters only, e.g. "FT232R USB UART"
+    pub description: Option<String>,
+}
+
+impl SerialConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.path.trim().is_empty() {
+            return Err("Serial device path is required".to_string());
+        }
+        if self.baud_rate == 0 || self.baud_rate > 4_000_000 {
+            return Err(format!("Invalid baud rate: {}", self.baud_rate));
+        }
+        if !(5..=8).contains(&self.data_bits) {
+            return Err(format!("Invalid data bits: {}", self.data_bits));
+        }
+        if !(1..=2).contains(&self.stop_bits) {
+            return Err(format!("Invalid stop bits: {}", self.stop_bits));
+        }
+        Ok(())
+    }
+
+    fn builder(&self) -> serialport::SerialPortBuilder {
+        let data_bits = match self.data_bits {
+            5 => serialport::DataBits::Five,
+            6 => serialport::DataBits::Six,
+            7 => serialport::DataBits::Seven,
+            _ => serialport::DataBits::Eight,
+        };
+        let stop_bits = match self.stop_bits {
+            2 => serialport::StopBits::Two,
+            _ => serialport::StopBits::One,
+        };
+        let parity = match self.parity {
+            Parity::None => serialport::Parity::None,
+            Parity::Odd => serialport::Parity::Odd,
+            Parity::Even => serialport::Parity::Even,
+        };
+        let flow_control = match self.flow_control {
+            FlowControl::None => serialport::FlowControl::None,
+            FlowControl::Software => serialport::FlowControl::Software,
+            FlowControl::Hardware => serialport::FlowControl::Hardware,
+        };
+        serialport::new(self.path.trim(), self.baud_rate)
+            .data_bits(data_bits)
+            .stop_bits(stop_bits)
+            .parity(parity)
+            .flow_control(flow_control)
+            .timeout(READ_TIMEOUT)
+    }
+}
+
+/// An open serial port, split into the session's writer, a reader for the
+/// output thread and the backend that stops that thread on close
+pub struct SerialPort {
+    pub reader: SerialReader,
+    pub writer: SerialWriter,
+    pub backend: SerialBackend,
+}
+
+pub fn open(config: &SerialConfig) -> Result<SerialPort, String> {
+    config.validate()?;
+    let port = config
+        .builder()
+        .open()
+        .map_err(|e| format!("Failed to open {}: {}", config.path, e))?;
+    let reader_port = port
+        .try_clone()
+        .map_err(|e| format!("Failed to open {}: {}", config.path, e))?;
+    let closed = Arc::new(AtomicBool::new(false));
+    Ok(SerialPort {
+        reader: SerialReader { port: reader_port, closed: closed.clone() },
+        writer: SerialWriter(port),
+        backend: SerialBackend { closed },
+    })
+}
+
+pub fn list_ports() -> Result<Vec<SerialPortInfo>, String> {
+    let ports = serialport::available_ports()
+        .map_err(|e| format!("Failed to list serial ports: {}", e))?;
+    Ok(ports
+        .into_iter()
+        .map(|p| {
+            let (port_type, description) = match p.port_type {
+                serialport::SerialPortType::UsbPort(usb) => ("usb", usb.product),
+                serialport::SerialPortType::PciPort => ("pci", None),
+                serialport::SerialPortType::BluetoothPort => ("bluetooth", None),
+                serialport::SerialPortType::Unknown => ("unknown", None),
+            };
+            SerialPortInfo {
+                path: p.port_name,
+                port_type: port_type.to_string(),
+                description,
+            }
+        })
+        .collect())
+}
+
+pub struct SerialWriter(Box<dyn serialport::SerialPort>);
+
+impl Write for SerialWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.0.flush()
+    }
+}
+
+/// Blocking reader that reports EOF once the session is closed. A serial
+/// line has no EOF of its own, and reads time out while the device is quiet.
+pub struct SerialReader {
+    port: Box<dyn serialport::SerialPort>,
+    closed: Arc<AtomicBool>,
+}
+
+impl Read for SerialReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        loop {
+            if self.closed.load(Ordering::Relaxed) {
+                return Ok(0);
+            }
+            match self.port.read(buf) {
+                Err(e) if e.kind() == std::io::ErrorKind::TimedOut => continue,
+                result => return result,
+            }
+        }
+    }
+}
+
+pub struct SerialBackend {
+    closed: Arc<AtomicBool>,
+}
+
+impl SessionBackend for SerialBackend {
+    /// A serial line has no window size
+    fn resize(&mut self, _cols: u16, _rows: u16) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn close(&mut self) {
+        self.closed.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_defaults_and_validation() {
+        let config: SerialConfig =
+            serde_json::from_str(r#"{"path": "/dev/ttyUSB0", "baud_rate": 115200}"#).unwrap();
+        assert_eq!(config.parity, Parity::None);
+        assert_eq!(config.flow_control, FlowControl::None);
+        assert_eq!((config.data_bits, config.stop_bits), (8, 1));
+        assert!(config.validate().is_ok());
+
+        let hw: SerialConfig = serde_json::from_str(
+            r#"{"path": "COM3", "baud_rate": 9600, "parity": "even", "flow_control": "hardware"}"#,
+        )
+        .unwrap();
+        assert_eq!(hw.flow_control, FlowControl::Hardware);
+
+        assert!(SerialConfig { baud_rate: 0, ..config.clone() }.validate().is_err());
+        assert!(SerialConfig { data_bits: 9, ..config.clone() }.validate().is_err());
+        assert!(SerialConfig { path: " ".into(), ..config }.validate().is_err());
+    }
+}
@@ -0,0 +1,398 @@
+use base64::Engine;
+use futures_util::{SinkExt, StreamExt};
+use rand::Rng;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::StatusCode;
+use tokio_tungstenite::tungstenite::Message;
+
+use super::TerminalManager;
+
+/// How long an invite link stays valid
+pub const INVITE_TTL: Duration = Duration::from_secs(10 * 60);
+/// Output batches queued for an observer; one that falls further behind is
+/// disconnected rather than slowing the session down
+const OBSERVER_QUEUE: usize = 256;
+
+/// A one-time link for one observer
+#[derive(Debug, Clone, Serialize)]
+pub struct ShareInvite {
+    pub session_id: String,
+    /// `ws://address:port/?token=…`
+    pub url: String,
+    /// Unix time in milliseconds
+    pub expires_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ObserverInfo {
+    pub id: String,
+    pub address: String,
+    pub connected_ms: u64,
+    /// May type into the session
+    pub control: bool,
+}
+
+/// A shared session, sent with `terminal-share-{session}` (null once sharing stops)
+#[derive(Debug, Clone, Serialize)]
+pub struct ShareInfo {
+    pub session_id: String,
+    pub address: String,
+    pub port: u16,
+    pub pending_invites: usize,
+    pub observers: Vec<ObserverInfo>,
+}
+
+enum ToObserver {
+    Control(bool),
+    Disconnect,
+}
+
+struct Observer {
+    info: ObserverInfo,
+    tx: mpsc::UnboundedSender<ToObserver>,
+}
+
+struct Share {
+    address: String,
+    port: u16,
+    /// Unused one-time tokens and when they expire
+    invites: HashMap<String, Instant>,
+    observers: Vec<Observer>,
+    listener: tokio::task::JoinHandle<()>,
+}
+
+impl Share {
+    fn info(&self, session_id: &str) -> ShareInfo {
+        ShareInfo {
+            session_id: session_id.to_string(),
+            address: self.address.clone(),
+            port: self.port,
+            pending_invites: self.invites.len(),
+            observers: self.observers.iter().map(|o| o.info.clone()).collect(),
+        }
+    }
+}
+
+/// Live views of terminal sessions for other people, served over WebSocket.
+/// Each shared session gets its own listener; observers connect with a
+/// one-time token and see the session's scrollback, then its live output.
+/// They are view-only until the owner grants control.
+///
+/// Protocol: the server sends a text `{"type":"hello",title,cols,rows,control}`
+/// first, then binary terminal output, and `{"type":"control",granted}` when
+/// control changes. Binary or text frames from an observer with control are
+/// typed into the session; from anyone else they are ignored.
+#[derive(Default)]
+pub struct ShareManager {
+    shares: Mutex<HashMap<String, Share>>,
+}
+
+impl ShareManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an invite for `session_id`, starting to share it on `address`
+    /// if it isn't shared yet
+    pub async fn invite(
+        &self,
+        session_id: &str,
+        address: &str,
+        app: &AppHandle,
+    ) -> Result<ShareInvite, String> {
+        if !app.state::<TerminalManager>().has_session(session_id) {
+            return Err("Session not found".to_string());
+        }
+        let shared_on = self.shares.lock().unwrap().get(session_id).map(|s| s.address.clone());
+        match shared_on {
+            Some(current) if current != address => {
+                return Err(format!(
+                    "Session is already shared on {}; stop sharing it first",
+                    current
+                ));
+            }
+            Some(_) => {}
+            None => {
+                let listener = TcpListener::bind((address, 0))
+                    .await
+                    .map_err(|e| format!("Failed to bind share port on {}: {}", address, e))?;
+                let port = listener
+                    .local_addr()
+                    .map_err(|e| format!("Failed to get addr: {}", e))?
+                    .port();
+                let task = tokio::spawn(serve(listener, session_id.to_string(), app.clone()));
+                log::info!("Sharing session {} on {}:{}", session_id, address, port);
+                let share = Share {
+                    address: address.to_string(),
+                    port,
+                    invites: HashMap::new(),
+                    observers: Vec::new(),
+                    listener: task,
+                };
+                if let Some(raced) = self.shares.lock().unwrap().insert(session_id.to_string(), share) {
+                    raced.listener.abort();
+                }
+            }
+        }
+
+        let token = base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .encode(rand::thread_rng().gen::<[u8; 32]>());
+        let mut shares = self.shares.lock().unwrap();
+        let share = shares.get_mut(session_id).ok_or("Sharing was stopped")?;
+        share.invites.insert(token.clone(), Instant::now() + INVITE_TTL);
+        let invite = ShareInvite {
+            session_id: session_id.to_string(),
+            url: format!("ws://{}:{}/?token={}", share.address, share.port, token),
+            expires_ms: now_ms() + INVITE_TTL.as_millis() as u64,
+        };
+        announce(app, session_id, Some(share.info(session_id)));
+        Ok(invite)
+    }
+
+    pub fn list(&self) -> Vec<ShareInfo> {
+        self.shares
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(sid, share)| share.info(sid))
+            .collect()
+    }
+
+    /// Let an observer type into the session, or take that back
+    pub fn set_control(
+        &self,
+        session_id: &str,
+        observer_id: &str,
+        control: bool,
+        app: &AppHandle,
+    ) -> Result<(), String> {
+        let mut shares = self.shares.lock().unwrap();
+        let share = shares.get_mut(session_id).ok_or("Session is not shared")?;
+        let observer = share
+            .observers
+            .iter_mut()
+            .find(|o| o.info.id == observer_id)
+            .ok_or("Observer not found")?;
+        observer.info.control = control;
+        observer.tx.send(ToObserver::Control(control)).ok();
+        log::info!(
+            "{} control of {} for observer {}",
+            if control { "Granted" } else { "Revoked" },
+            session_id,
+            observer.info.address
+        );
+        announce(app, session_id, Some(share.info(session_id)));
+        Ok(())
+    }
+
+    pub fn disconnect(&self, session_id: &str, observer_id: &str) -> Result<(), String> {
+        let shares = self.shares.lock().unwrap();
+        let share = shares.get(session_id).ok_or("Session is not shared")?;
+        let observer = share
+            .observers
+            .iter()
+            .find(|o| o.info.id == observer_id)
+            .ok_or("Observer not found")?;
+        observer.tx.send(ToObserver::Disconnect).ok();
+        Ok(())
+    }
+
+    /// Stop sharing: close the listener, drop unused invites and disconnect
+    /// every observer. A no-op for a session that isn't shared.
+    pub fn stop(&self, session_id: &str, app: &AppHandle) {
+        let Some(share) = self.shares.lock().unwrap().remove(session_id) else { return };
+        share.listener.abort();
+        for observer in &share.observers {
+            observer.tx.send(ToObserver::Disconnect).ok();
+        }
+        log::info!("Stopped sharing session {}", session_id);
+        announce(app, session_id, None);
+    }
+
+    /// Use up an invite token. Expired tokens are dropped along the way.
+    fn redeem(&self, session_id: &str, token: &str) -> bool {
+        let mut shares = self.shares.lock().unwrap();
+        let Some(share) = shares.get_mut(session_id) else { return false };
+        let now = Instant::now();
+        share.invites.retain(|_, expires| *expires > now);
+        share.invites.remove(token).is_some()
+    }
+
+    fn add_observer(
+        &self,
+        session_id: &str,
+        address: SocketAddr,
+        tx: mpsc::UnboundedSender<ToObserver>,
+        app: &AppHandle,
+    ) -> Result<String, String> {
+        let mut shares = self.shares.lock().unwrap();
+        let share = shares.get_mut(session_id).ok_or("Sharing was stopped")?;
+        let info = ObserverInfo {
+            id: uuid::Uuid::new_v4().to_string(),
+            address: address.to_string(),
+            connected_ms: now_ms(),
+            control: false,
+        };
+        log::info!("Observer {} joined session {}", info.address, session_id);
+        let id = info.id.clone();
+        share.observers.push(Observer { info, tx });
+        announce(app, session_id, Some(share.info(session_id)));
+        Ok(id)
+    }
+
+    fn remove_observer(&self, session_id: &str, observer_id: &str, app: &AppHandle) {
+        let mut shares = self.shares.lock().unwrap();
+        let Some(share) = shares.get_mut(session_id) else { return };
+        share.observers.retain(|o| o.info.id != observer_id);
+        announce(app, session_id, Some(share.info(session_id)));
+    }
+
+    /// Whether the observer currently has control; checked per input frame
+    /// so a revocation takes effect even before the observer hears of it
+    fn has_control(&self, session_id: &str, observer_id: &str) -> bool {
+        self.shares
+            .lock()
+            .unwrap()
+            .get(session_id)
+            .and_then(|s| s.observers.iter().find(|o| o.info.id == observer_id))
+            .is_some_and(|o| o.info.control)
+    }
+}
+
+fn announce(app: &AppHandle, session_id: &str, info: Option<ShareInfo>) {
+    app.emit(&format!("terminal-share-{}", session_id), info).ok();
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// The `token` query parameter of a request URI
+fn query_token(query: &str) -> Option<&str> {
+    query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("token="))
+        .filter(|t| !t.is_empty())
+}
+
+async fn serve(listener: TcpListener, session_id: String, app: AppHandle) {
+    while let Ok((stream, address)) = listener.accept().await {
+        let session_id = session_id.clone();
+        let app = app.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle_observer(stream, address, &session_id, &app).await {
+                log::warn!("Share connection from {} for {}: {}", address, session_id, e);
+            }
+        });
+    }
+}
+
+// The handshake callback's error type is fixed by tungstenite
+#[allow(clippy::result_large_err)]
+async fn handle_observer(
+    stream: TcpStream,
+    address: SocketAddr,
+    session_id: &str,
+    app: &AppHandle,
+) -> Result<(), String> {
+    let shares = app.state::<ShareManager>();
+    let ws_stream = tokio_tungstenite::accept_hdr_async(stream, |req: &Request, resp: Response| {
+        let token = query_token(req.uri().query().unwrap_or(""));
+        if token.is_some_and(|t| shares.redeem(session_id, t)) {
+            return Ok(resp);
+        }
+        let mut denied = ErrorResponse::new(Some("Invalid or expired share link".to_string()));
+        *denied.status_mut() = StatusCode::UNAUTHORIZED;
+        Err(denied)
+    })
+    .await
+    .map_err(|e| format!("WS accept failed: {}", e))?;
+
+    let manager = app.state::<TerminalManager>();
+    let (out_tx, mut out_rx) = mpsc::channel::<Vec<u8>>(OBSERVER_QUEUE);
+    let observed = manager.observe(
+        session_id,
+        Box::new(move |batch| out_tx.try_send(batch.to_vec()).is_ok()),
+    )?;
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let observer_id = shares.add_observer(session_id, address, tx, app)?;
+
+    let (mut ws_write, mut ws_read) = ws_stream.split();
+    let hello = serde_json::json!({
+        "type": "hello",
+        "title": observed.title,
+        "cols": observed.cols,
+        "rows": observed.rows,
+        "control": false,
+    });
+    let greeted = async {
+        ws_write.send(Message::Text(hello.to_string().into())).await?;
+        ws_write.send(Message::Binary(observed.replay.into())).await
+    }
+    .await;
+
+    if greeted.is_ok() {
+        loop {
+            tokio::select! {
+                output = out_rx.recv() => {
+                    // None: the session ended, or this observer fell too far behind
+                    let Some(data) = output else { break };
+                    if ws_write.send(Message::Binary(data.into())).await.is_err() {
+                        break;
+                    }
+                }
+                command = rx.recv() => match command {
+                    Some(ToObserver::Control(granted)) => {
+                        let notice = serde_json::json!({ "type": "control", "granted": granted });
+                        if ws_write.send(Message::Text(notice.to_string().into())).await.is_err() {
+                            break;
+                        }
+                    }
+                    Some(ToObserver::Disconnect) | None => break,
+                },
+                incoming = ws_read.next() => {
+                    let input = match incoming {
+                        Some(Ok(Message::Binary(data))) => data.to_vec(),
+                        Some(Ok(Message::Text(text))) => text.as_bytes().to_vec(),
+                        Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                        Some(Ok(_)) => continue,
+                    };
+                    if shares.has_control(session_id, &observer_id) {
+                        if let Err(e) = manager.write_session(session_id, &input) {
+                            log::warn!("Observer input to {} failed: {}", session_id, e);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    ws_write.send(Message::Close(None)).await.ok();
+    log::info!("Observer {} left session {}", address, session_id);
+    shares.remove_observer(session_id, &observer_id, app);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_token() {
+        assert_eq!(query_token("token=abc"), Some("abc"));
+        assert_eq!(query_token("v=1&token=x-y_z"), Some("x-y_z"));
+        assert_eq!(query_token("token="), None);
+        assert_eq!(query_token("mytoken=abc"), None);
+    }
+}
@@ -0,0 +1,141 @@
+use std::collections::BTreeMap;
+
+use crate::config::{validate, Device};
+
+/// A device's session settings, applied when a terminal session opens
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Startup {
+    pub initial_dir: Option<String>,
+    pub env: BTreeMap<String, String>,
+    /// Run on the remote before the shell starts, e.g. `conda activate lab`
+    pub command: Option<String>,
+    pub forward_agent: bool,
+    pub forward_x11: bool,
+    /// Extra `-o Key=Value` options for the system ssh client
+    pub ssh_options: Vec<String>,
+}
+
+impl Startup {
+    /// From an effective device, so group defaults are already applied
+    pub fn from_device(device: &Device) -> Self {
+        Self {
+            initial_dir: device.initial_dir.clone().filter(|d| !d.trim().is_empty()),
+            env: device.env.clone(),
+            command: device.startup_command.clone().filter(|c| !c.trim().is_empty()),
+            forward_agent: device.forward_agent,
+            forward_x11: device.forward_x11,
+            // Also checked when saving, but a replaced or hand-edited config
+            // file skips that
+            ssh_options: device
+                .ssh_options
+                .iter()
+                .filter(|o| {
+                    let allowed = validate::is_allowed_ssh_option(o);
+                    if !allowed {
+                        log::warn!("Ignoring disallowed ssh option '{}' of {}", o, device.name);
+                    }
+                    allowed
+                })
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Arguments for the system ssh client, before the destination
+    pub fn ssh_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if self.forward_agent {
+            args.push("-A".to_string());
+        }
+        if self.forward_x11 {
+            args.push("-X".to_string());
+        }
+        for option in &self.ssh_options {
+            args.push("-o".to_string());
+            args.push(option.clone());
+        }
+        args
+    }
+
+    /// Remote command that changes directory, exports the environment and
+    /// runs the startup command before running `then`, or a login shell
+    /// without one. The environment is set this way rather than with SSH env
+    /// requests, which most servers only accept for a few `AcceptEnv` names.
+    /// The startup command is part of the remote command rather than typed
+    /// into the terminal, where it could answer a password or host key prompt.
+    pub fn remote_command(&self, then: Option<String>) -> Option<String> {
+        if self.initial_dir.is_none() && self.env.is_empty() && self.command.is_none() {
+            return then;
+        }
+        let mut script = String::new();
+        if let Some(dir) = &self.initial_dir {
+            script.push_str(&format!("cd {}; ", quote_path(dir)));
+        }
+        for (name, value) in &self.env {
+            script.push_str(&format!("export {}={}; ", name, quote(value)));
+        }
+        // On its own line, so a trailing `&` or comment doesn't swallow the rest
+        if let Some(command) = &self.command {
+            script.push_str(command.trim_end());
+            script.push('\n');
+        }
+        script.push_str(&then.unwrap_or_else(|| "exec \"${SHELL:-/bin/sh}\" -l".to_string()));
+        Some(script)
+    }
+}
+
+/// Single-quote `value` for a POSIX shell
+pub(crate) fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Like `quote`, but a leading `~` still expands to the home directory
+fn quote_path(path: &str) -> String {
+    match path.strip_prefix("~/") {
+        Some(rest) => format!("~/{}", quote(rest)),
+        None if path == "~" => "~".to_string(),
+        None => quote(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remote_command_sets_dir_and_env() {
+        let mut startup = Startup::default();
+        assert_eq!(startup.remote_command(None), None);
+        assert_eq!(startup.remote_command(Some("tmux".into())).as_deref(), Some("tmux"));
+
+        startup.initial_dir = Some("~/lab data".into());
+        startup.env.insert("RUN".into(), "it's".into());
+        startup.env.insert("CUDA_VISIBLE_DEVICES".into(), "0,1".into());
+        assert_eq!(
+            startup.remote_command(None).unwrap(),
+            "cd ~/'lab data'; export CUDA_VISIBLE_DEVICES='0,1'; export RUN='it'\\''s'; \
+             exec \"${SHELL:-/bin/sh}\" -l"
+        );
+
+        startup.command = Some("conda activate lab # gpu".into());
+        assert!(startup
+            .remote_command(Some("tmux".into()))
+            .unwrap()
+            .ends_with("export RUN='it'\\''s'; conda activate lab # gpu\ntmux"));
+
+        startup.forward_agent = true;
+        startup.ssh_options = vec!["Compression=yes".into()];
+        assert_eq!(startup.ssh_args(), vec!["-A", "-o", "Compression=yes"]);
+    }
+
+    #[test]
+    fn test_startup_command_runs_before_the_shell() {
+        // Sent with the session request, so whatever the terminal shows
+        // first (a password or host key prompt) never receives it
+        let startup = Startup { command: Some("conda activate lab".into()), ..Default::default() };
+        assert_eq!(
+            startup.remote_command(None).unwrap(),
+            "conda activate lab\nexec \"${SHELL:-/bin/sh}\" -l"
+        );
+    }
+}
@@ -0,0 +1,267 @@
+use regex::Regex;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::Emitter;
+use tauri_plugin_notification::NotificationExt;
+
+use crate::config::{OutputTrigger, TriggerAction, MIN_TRIGGER_COOLDOWN_SECS};
+
+/// Longest partial line held while waiting for its end
+const MAX_LINE: usize = 8 * 1024;
+
+/// Desktop notifications one session may raise per `NOTIFY_WINDOW`, over all
+/// of its triggers. Hits past that still fire their other actions.
+const MAX_NOTIFICATIONS: usize = 5;
+const NOTIFY_WINDOW: Duration = Duration::from_secs(60);
+
+/// A trigger firing, sent with `terminal-trigger` and
+/// `terminal-highlight-{session}`
+#[derive(Debug, Clone, Serialize)]
+pub struct TriggerHit {
+    pub trigger_id: String,
+    pub name: String,
+    /// The output line, without escape sequences
+    pub line: String,
+    /// The part of the line the pattern matched
+    pub matched: String,
+    pub fired_ms: u64,
+    /// Matches swallowed by the cooldown since the previous firing
+    pub suppressed: u32,
+    #[serde(skip)]
+    pub actions: Vec<TriggerAction>,
+}
+
+struct Compiled {
+    trigger: OutputTrigger,
+    regex: Regex,
+    last_fired: Option<Instant>,
+    suppressed: u32,
+}
+
+/// The triggers of one session, matched against its output line by line
+#[derive(Default)]
+pub struct TriggerSet {
+    /// As configured, including disabled ones
+    triggers: Vec<OutputTrigger>,
+    compiled: Vec<Compiled>,
+    /// Output after the last line break
+    partial: Vec<u8>,
+    /// When notifications were raised within the last `NOTIFY_WINDOW`
+    notified: VecDeque<Instant>,
+}
+
+impl TriggerSet {
+    /// Triggers with a bad pattern are skipped; the rest still work
+    pub fn new(triggers: &[OutputTrigger]) -> Self {
+        let mut set = Self { triggers: triggers.to_vec(), ..Self::default() };
+        for trigger in triggers.iter().filter(|t| t.enabled) {
+            match trigger.regex() {
+                Ok(regex) => set.compiled.push(Compiled {
+                    trigger: trigger.clone(),
+                    regex,
+                    last_fired: None,
+                    suppressed: 0,
+                }),
+                Err(e) => log::warn!("Skipping trigger {}: {}", trigger.id, e),
+            }
+        }
+        set
+    }
+
+    /// Replace the triggers. Unlike `new`, any invalid trigger is an error
+    /// and leaves the current ones in place.
+    pub fn replace(&mut self, triggers: &[OutputTrigger]) -> Result<(), String> {
+        for trigger in triggers {
+            trigger.validate()?;
+        }
+        let notified = std::mem::take(&mut self.notified);
+        *self = Self::new(triggers);
+        self.notified = notified;
+        Ok(())
+    }
+
+    pub fn triggers(&self) -> Vec<OutputTrigger> {
+        self.triggers.clone()
+    }
+
+    /// Match complete lines in `data`; carriage returns end a line too, so
+    /// progress output redrawn in place is seen
+    pub fn feed(&mut self, data: &[u8], now: Instant) -> Vec<TriggerHit> {
+        if self.compiled.is_empty() {
+            return Vec::new();
+        }
+        let mut hits = Vec::new();
+        for &byte in data {
+            if byte == b'\n' || byte == b'\r' {
+                self.match_line(now, &mut hits);
+            } else {
+                self.partial.push(byte);
+                if self.partial.len() >= MAX_LINE {
+                    self.match_line(now, &mut hits);
+                }
+            }
+        }
+        hits
+    }
+
+    fn match_line(&mut self, now: Instant, hits: &mut Vec<TriggerHit>) {
+        if self.partial.is_empty() {
+            return;
+        }
+        let line = strip_escapes(&String::from_utf8_lossy(&self.partial));
+        self.partial.clear();
+        for compiled in &mut self.compiled {
+            let Some(found) = compiled.regex.find(&line) else { continue };
+            // Configs from before the minimum may still hold 0
+            let cooldown =
+                Duration::from_secs(compiled.trigger.cooldown_secs.max(MIN_TRIGGER_COOLDOWN_SECS));
+            if compiled.last_fired.is_some_and(|t| now.duration_since(t) < cooldown) {
+                compiled.suppressed += 1;
+                continue;
+            }
+            compiled.last_fired = Some(now);
+            let trigger = &compiled.trigger;
+            let mut actions = trigger.actions.clone();
+            if actions.contains(&TriggerAction::Notify) {
+                while self
+                    .notified
+                    .front()
+                    .is_some_and(|t| now.duration_since(*t) >= NOTIFY_WINDOW)
+                {
+                    self.notified.pop_front();
+                }
+                if self.notified.len() < MAX_NOTIFICATIONS {
+                    self.notified.push_back(now);
+                } else {
+                    actions.retain(|a| *a != TriggerAction::Notify);
+                }
+            }
+            hits.push(TriggerHit {
+                trigger_id: trigger.id.clone(),
+                name: if trigger.name.trim().is_empty() {
+                    trigger.pattern.clone()
+                } else {
+                    trigger.name.clone()
+                },
+                line: line.trim().to_string(),
+                matched: found.as_str().to_string(),
+                fired_ms: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_millis() as u64)
+                    .unwrap_or(0),
+                suppressed: std::mem::take(&mut compiled.suppressed),
+                actions,
+            });
+        }
+    }
+}
+
+/// Carry out a hit's actions for session `session_id`, titled `title`
+pub fn fire(app: &tauri::AppHandle, session_id: &str, title: &str, hit: &TriggerHit) {
+    log::info!("Trigger {} fired in {}: {}", hit.trigger_id, session_id, hit.line);
+    for action in &hit.actions {
+        match action {
+            TriggerAction::Notify => {
+                let body = match hit.suppressed {
+                    0 => format!("{}: {}", title, hit.line),
+                    n => format!("{}: {} (+{} more)", title, hit.line, n),
+                };
+                if let Err(e) = app.notification().builder().title(&hit.name).body(body).show() {
+                    log::warn!("Trigger notification failed: {}", e);
+                }
+            }
+            TriggerAction::Event => {
+                app.emit(
+                    "terminal-trigger",
+                    serde_json::json!({ "session_id": session_id, "hit": hit }),
+                )
+                .ok();
+            }
+            TriggerAction::Highlight => {
+                app.emit(&format!("terminal-highlight-{}", session_id), hit).ok();
+            }
+        }
+    }
+}
+
+/// Remove CSI, OSC and other escape sequences and stray control characters
+fn strip_escapes(line: &str) -> String {
+    static ESCAPES: OnceLock<Regex> = OnceLock::new();
+    let escapes = ESCAPES.get_or_init(|| {
+        Regex::new(r"\x1b\[[0-?]*[ -/]*[@-~]|\x1b\][^\x07\x1b]*(?:\x07|\x1b\\)|\x1b[@-Z\\-_]|[\x00-\x08\x0b-\x1f\x7f]")
+            .expect("valid escape pattern")
+    });
+    escapes.replace_all(line, "").into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trigger(pattern: &str, cooldown_secs: u64) -> OutputTrigger {
+        OutputTrigger {
+            id: pattern.to_string(),
+            name: String::new(),
+            pattern: pattern.to_string(),
+            case_insensitive: false,
+            actions: vec![TriggerAction::Notify],
+            cooldown_secs,
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn test_matches_lines_across_chunks_without_escapes() {
+        let mut set = TriggerSet::new(&[trigger(r"Epoch \d+ done", 1)]);
+        let now = Instant::now();
+        assert!(set.feed(b"\x1b[32mEpoch 10", now).is_empty());
+        let hits = set.feed(b"0 done\x1b[0m\r\nloss 0.1\n", now);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].matched, "Epoch 100 done");
+        assert_eq!(hits[0].line, "Epoch 100 done");
+        assert_eq!(hits[0].name, r"Epoch \d+ done");
+    }
+
+    #[test]
+    fn test_cooldown_counts_suppressed_matches() {
+        let mut set = TriggerSet::new(&[trigger("Segmentation fault", 10), trigger("(", 10)]);
+        assert_eq!(set.compiled.len(), 1);
+
+        let start = Instant::now();
+        let noisy = b"Segmentation fault\n".repeat(5);
+        assert_eq!(set.feed(&noisy, start).len(), 1);
+        let later = set.feed(b"Segmentation fault\n", start + Duration::from_secs(11));
+        assert_eq!(later.len(), 1);
+        assert_eq!(later[0].suppressed, 4);
+        assert!(set.replace(&[trigger("(", 10)]).is_err());
+        assert!(set.replace(&[trigger("fault", 0)]).is_err());
+        assert_eq!(set.triggers().len(), 2);
+    }
+
+    #[test]
+    fn test_notifications_are_capped_per_session() {
+        let patterns: Vec<String> = (0..8).map(|i| format!("error {}", i)).collect();
+        let triggers: Vec<_> = patterns.iter().map(|p| trigger(p, 1)).collect();
+        let mut set = TriggerSet::new(&triggers);
+
+        let start = Instant::now();
+        let output: String = patterns.iter().map(|p| format!("{}\n", p)).collect();
+        let hits = set.feed(output.as_bytes(), start);
+        assert_eq!(hits.len(), 8);
+        let notifying = |hits: &[TriggerHit]| {
+            hits.iter()
+                .filter(|h| h.actions.contains(&TriggerAction::Notify))
+                .count()
+        };
+        assert_eq!(notifying(&hits), MAX_NOTIFICATIONS);
+
+        // Swapping the triggers doesn't reset the window
+        set.replace(&triggers).unwrap();
+        let hits = set.feed(output.as_bytes(), start + Duration::from_secs(2));
+        assert_eq!(notifying(&hits), 0);
+        let hits = set.feed(output.as_bytes(), start + NOTIFY_WINDOW);
+        assert_eq!(notifying(&hits), MAX_NOTIFICATIONS);
+    }
+}
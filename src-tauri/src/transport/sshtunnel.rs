@@ -18,6 +18,10 @@ use tokio::net::TcpStream;
 use tokio::sync::{mpsc, Mutex, RwLock};
 use tokio::time::{interval, Duration};
 
+/// russh 客户端处理器（与终端会话共用 known_hosts 校验）
+#[cfg(feature = "russh")]
+use crate::terminal::native::Client;
+
 /// SSH 隧道传输实现
 /// 
 /// 注意：完整的 SSH 实现需要 libssh2 或 russh
@@ -115,36 +119,40 @@ impl SshTunnelTransport {
     #[cfg(feature = "russh")]
     async fn connect_ssh_tunnel(&mut self) -> Result<(), TransportError> {
         use russh::client::Config;
-        use russh::keys::key::KeyPair;
+        use russh::keys::PrivateKeyWithHashAlg;
         use std::sync::Arc;
 
         // 创建 SSH 配置
         let config = Arc::new(Config::default());
 
-        // 创建连接
-        let addr = format!("{}:{}", self.ssh_config.ssh_host, self.ssh_config.ssh_port);
-        let mut session = russh::client::connect(
-            config,
-            addr.parse().map_err(|e| TransportError::Connection(e.to_string()))?,
-            Client {},
-        )
-        .await
-        .map_err(|e| TransportError::Connection(e.to_string()))?;
+        // 创建连接（主机密钥按 known_hosts 校验）
+        let host = self.ssh_config.ssh_host.clone();
+        let port = self.ssh_config.ssh_port;
+        let mut session = russh::client::connect(config, (host.as_str(), port), Client::new(&host, port))
+            .await
+            .map_err(|e| TransportError::Connection(e.to_string()))?;
 
         // 认证
-        let auth_result = if let Some(ref _key_path) = self.ssh_config.private_key {
+        let auth_result = if let Some(ref key_path) = self.ssh_config.private_key {
             // 使用密钥认证
-            // let key_data = tokio::fs::read(key_path)
-            //     .await
-            //     .map_err(|e| TransportError::Io(e.to_string()))?;
-            // 解析密钥并认证...
-            // session.authenticate_publickey(...).await
-            false
+            let key = russh::keys::load_secret_key(key_path, None)
+                .map_err(|e| TransportError::Connection(e.to_string()))?;
+            let hash_alg = session.best_supported_rsa_hash().await.ok().flatten().flatten();
+            session
+                .authenticate_publickey(
+                    &self.ssh_config.username,
+                    PrivateKeyWithHashAlg::new(Arc::new(key), hash_alg),
+                )
+                .await
+                .map_err(|e| TransportError::Connection(e.to_string()))?
+                .success()
         } else if let Some(ref password) = self.ssh_config.password {
             // 使用密码认证
             session
                 .authenticate_password(&self.ssh_config.username, password)
                 .await
+                .map_err(|e| TransportError::Connection(e.to_string()))?
+                .success()
         } else {
             return Err(TransportError::Connection(
                 "No authentication method provided".to_string(),
@@ -156,7 +164,7 @@ impl SshTunnelTransport {
         }
 
         // 建立端口转发
-        let channel = session
+        let _channel = session
             .channel_open_direct_tcpip(
                 &self.ssh_config.remote_dest,
                 self.config.addr.port(),
@@ -445,24 +453,6 @@ impl Transport for SshTunnelTransport {
     }
 }
 
-/// russh 客户端处理器
-#[cfg(feature = "russh")]
-struct Client;
-
-#[cfg(feature = "russh")]
-impl russh::client::Handler for Client {
-    type Error = russh::Error;
-
-    async fn check_server_key(
-        &mut self,
-        _server_public_key: &russh::keys::PublicKey,
-    ) -> Result<bool, Self::Error> {
-        // 开发环境接受所有密钥
-        // 生产环境应该验证密钥指纹
-        Ok(true)
-    }
-}
-
 /// SSH 连接池
 pub struct SshConnectionPool {
     connections: Vec<Arc<Mutex<SshTunnelTransport>>>,
@@ -0,0 +1,191 @@
+use std::net::{Ipv4Addr, SocketAddrV4, UdpSocket};
+
+/// Port magic packets are sent to (the "discard" port most NICs listen on)
+pub const WOL_PORT: u16 = 9;
+
+/// Parse `aa:bb:cc:dd:ee:ff`, `aa-bb-cc-dd-ee-ff` or `aabbccddeeff`
+pub fn parse_mac(mac: &str) -> Result<[u8; 6], String> {
+    let hex: String = mac
+        .trim()
+        .chars()
+        .filter(|c| !matches!(c, ':' | '-' | '.'))
+        .collect();
+    if hex.len() != 12 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("Invalid MAC address '{}'", mac));
+    }
+    let mut bytes = [0u8; 6];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16)
+            .map_err(|_| format!("Invalid MAC address '{}'", mac))?;
+    }
+    Ok(bytes)
+}
+
+/// Canonical lowercase colon-separated form
+pub fn format_mac(mac: &[u8; 6]) -> String {
+    mac.iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// Six 0xFF bytes followed by the MAC repeated sixteen times
+pub fn magic_packet(mac: &[u8; 6]) -> [u8; 102] {
+    let mut packet = [0xFFu8; 102];
+    for chunk in packet[6..].chunks_mut(6) {
+        chunk.copy_from_slice(mac);
+    }
+    packet
+}
+
+/// Broadcast a magic packet from this machine. With `interface`, the packet
+/// leaves from that interface's address to its subnet broadcast, unless an
+/// explicit `broadcast` address is given.
+pub fn send_local(
+    mac: &[u8; 6],
+    broadcast: Option<Ipv4Addr>,
+    interface: Option<&str>,
+) -> Result<(), String> {
+    let (bind_addr, subnet_broadcast) = match interface {
+        Some(name) => {
+            let (addr, bcast) = interface_ipv4(name)?;
+            (addr, Some(bcast))
+        }
+        None => (Ipv4Addr::UNSPECIFIED, None),
+    };
+    let destination = broadcast
+        .or(subnet_broadcast)
+        .unwrap_or(Ipv4Addr::BROADCAST);
+
+    let socket = UdpSocket::bind(SocketAddrV4::new(bind_addr, 0))
+        .map_err(|e| format!("Failed to open UDP socket: {}", e))?;
+    socket
+        .set_broadcast(true)
+        .map_err(|e| format!("Failed to enable broadcast: {}", e))?;
+    socket
+        .send_to(&magic_packet(mac), SocketAddrV4::new(destination, WOL_PORT))
+        .map_err(|e| format!("Failed to send magic packet: {}", e))?;
+    log::info!(
+        "Sent WoL packet for {} to {}:{}",
+        format_mac(mac),
+        destination,
+        WOL_PORT
+    );
+    Ok(())
+}
+
+/// Shell script that broadcasts a magic packet from a relay host, using
+/// whichever tool it has
+pub fn relay_script(
+    mac: &[u8; 6],
+    broadcast: Option<Ipv4Addr>,
+    interface: Option<&str>,
+) -> String {
+    let mac = format_mac(mac);
+    let bcast = broadcast.unwrap_or(Ipv4Addr::BROADCAST);
+    // etherwake is the only common tool that can pick an interface
+    let etherwake = match interface {
+        Some(iface) => format!(
+            "if command -v etherwake >/dev/null 2>&1; then sudo -n etherwake -i '{}' {} && exit 0; fi\n",
+            iface.replace('\'', ""),
+            mac
+        ),
+        None => String::new(),
+    };
+    format!(
+        r#"{etherwake}if command -v wakeonlan >/dev/null 2>&1; then
+    wakeonlan -i {bcast} -p {port} {mac} && exit 0
+fi
+if command -v python3 >/dev/null 2>&1; then
+    python3 - <<'EOF' && exit 0
+import socket
+mac = bytes.fromhex("{hex}")
+s = socket.socket(socket.AF_INET, socket.SOCK_DGRAM)
+s.setsockopt(socket.SOL_SOCKET, socket.SO_BROADCAST, 1)
+s.sendto(b"\xff" * 6 + mac * 16, ("{bcast}", {port}))
+EOF
+fi
+echo "No way to send a magic packet (install wakeonlan or python3)" >&2
+exit 1
+"#,
+        etherwake = etherwake,
+        bcast = bcast,
+        port = WOL_PORT,
+        mac = mac,
+        hex = mac.replace(':', ""),
+    )
+}
+
+/// IPv4 address and subnet broadcast address of a local interface
+#[cfg(unix)]
+fn interface_ipv4(name: &str) -> Result<(Ipv4Addr, Ipv4Addr), String> {
+    let mut addrs: *mut libc::ifaddrs = std::ptr::null_mut();
+    // SAFETY: getifaddrs allocates a list we walk read-only and free below
+    if unsafe { libc::getifaddrs(&mut addrs) } != 0 {
+        return Err("Failed to list network interfaces".to_string());
+    }
+
+    let mut found = None;
+    let mut cursor = addrs;
+    while !cursor.is_null() {
+        // SAFETY: cursor points into the list returned by getifaddrs
+        let ifa = unsafe { &*cursor };
+        cursor = ifa.ifa_next;
+        if ifa.ifa_addr.is_null() || ifa.ifa_netmask.is_null() {
+            continue;
+        }
+        // SAFETY: ifa_name is a NUL-terminated string owned by the list
+        let ifa_name = unsafe { std::ffi::CStr::from_ptr(ifa.ifa_name) };
+        if ifa_name.to_string_lossy() != name {
+            continue;
+        }
+        // SAFETY: sa_family says whether these are sockaddr_in
+        unsafe {
+            if (*ifa.ifa_addr).sa_family as i32 != libc::AF_INET {
+                continue;
+            }
+            let addr = &*(ifa.ifa_addr as *const libc::sockaddr_in);
+            let mask = &*(ifa.ifa_netmask as *const libc::sockaddr_in);
+            let addr = u32::from_be(addr.sin_addr.s_addr);
+            let mask = u32::from_be(mask.sin_addr.s_addr);
+            found = Some((Ipv4Addr::from(addr), Ipv4Addr::from(addr | !mask)));
+        }
+        break;
+    }
+    // SAFETY: addrs came from getifaddrs and is freed exactly once
+    unsafe { libc::freeifaddrs(addrs) };
+
+    found.ok_or_else(|| format!("Interface '{}' has no IPv4 address", name))
+}
+
+#[cfg(not(unix))]
+fn interface_ipv4(name: &str) -> Result<(Ipv4Addr, Ipv4Addr), String> {
+    Err(format!(
+        "Choosing an interface ('{}') is not supported on this platform; set a broadcast address instead",
+        name
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mac_formats() {
+        let expected = [0x00, 0x1a, 0x2b, 0x3c, 0x4d, 0x5e];
+        assert_eq!(parse_mac("00:1A:2B:3C:4D:5E").unwrap(), expected);
+        assert_eq!(parse_mac("00-1a-2b-3c-4d-5e").unwrap(), expected);
+        assert_eq!(parse_mac("001a2b3c4d5e").unwrap(), expected);
+        assert!(parse_mac("00:1a:2b:3c:4d").is_err());
+        assert!(parse_mac("zz:1a:2b:3c:4d:5e").is_err());
+    }
+
+    #[test]
+    fn test_magic_packet_layout() {
+        let mac = parse_mac("00:1a:2b:3c:4d:5e").unwrap();
+        let packet = magic_packet(&mac);
+        assert_eq!(&packet[..6], &[0xFF; 6]);
+        assert!(packet[6..].chunks(6).all(|c| c == mac));
+        assert!(relay_script(&mac, None, None).contains("001a2b3c4d5e"));
+    }
+}
@@ -1,5 +1,5 @@
 import { useState, useEffect } from "react";
-import { TerminalSquare, ChevronUp, ChevronDown } from "lucide-react";
+import { TerminalSquare, ChevronUp, ChevronDown, Plus } from "lucide-react";
 import Dashboard from "./components/Dashboard";
 import Terminal from "./components/Terminal";
 import RemoteDesktop from "./components/RemoteDesktop";
@@ -7,6 +7,7 @@ import FileManager from "./components/FileManager";
 import Settings from "./components/Settings";
 import PasswordPrompt from "./components/PasswordPrompt";
 import * as api from "./services/api";
+import type { TerminalSource } from "./services/types";
 
 type View = "dashboard" | "settings" | "remote-desktop" | "file-manager";
 
@@ -16,6 +17,8 @@ interface TerminalSession {
   host: string;
   user: string;
   port?: number;
+  deviceId?: string;
+  source?: TerminalSource;
 }
 
 interface DesktopSession {
@@ -23,6 +26,7 @@ interface DesktopSession {
   host: string;
   user: string;
   port?: number;
+  deviceId?: string;
 }
 
 interface FileSession {
@@ -30,6 +34,7 @@ interface FileSession {
   host: string;
   user: string;
   port?: number;
+  deviceId?: string;
 }
 
 function App() {
@@ -45,10 +50,10 @@ function App() {
     api.configIsEncrypted().then((encrypted) => setLocked(encrypted)).catch(() => setLocked(false));
   }, []);
 
-  const openTerminal = (deviceName: string, host: string, user: string, port?: number) => {
+  const openTerminal = (deviceName: string, host: string, user: string, port?: number, deviceId?: string) => {
     // If session to same host exists, just activate it
     const existing = terminalSessions.find(
-      (s) => s.host === host && s.user === user && s.port === port,
+      (s) => !s.source && s.host === host && s.user === user && s.port === port,
     );
     if (existing) {
       setActiveTerminal(existing.id);
@@ -56,7 +61,17 @@ function App() {
       return;
     }
     const id = `${host.replace(/\./g, "_")}-${Date.now()}`;
-    setTerminalSessions((prev) => [...prev, { id, deviceName, host, user, port }]);
+    setTerminalSessions((prev) => [...prev, { id, deviceName, host, user, port, deviceId }]);
+    setActiveTerminal(id);
+    setTerminalExpanded(true);
+  };
+
+  const openLocalShell = () => {
+    const id = `local-${Date.now()}`;
+    setTerminalSessions((prev) => [
+      ...prev,
+      { id, deviceName: "Local shell", host: "localhost", user: "", source: { kind: "local" } },
+    ]);
     setActiveTerminal(id);
     setTerminalExpanded(true);
   };
@@ -74,8 +89,8 @@ function App() {
     });
   };
 
-  const openDesktop = (deviceName: string, host: string, user: string, port?: number) => {
-    setDesktopSession({ deviceName, host, user, port });
+  const openDesktop = (deviceName: string, host: string, user: string, port?: number, deviceId?: string) => {
+    setDesktopSession({ deviceName, host, user, port, deviceId });
     setView("remote-desktop");
   };
 
@@ -84,8 +99,8 @@ function App() {
     setView("dashboard");
   };
 
-  const openFiles = (deviceName: string, host: string, user: string, port?: number) => {
-    setFileSession({ deviceName, host, user, port });
+  const openFiles = (deviceName: string, host: string, user: string, port?: number, deviceId?: string) => {
+    setFileSession({ deviceName, host, user, port, deviceId });
     setView("file-manager");
   };
 
@@ -130,6 +145,7 @@ function App() {
             host={desktopSession.host}
             user={desktopSession.user}
             port={desktopSession.port}
+            deviceId={desktopSession.deviceId}
             onClose={closeDesktop}
           />
         )}
@@ -139,6 +155,7 @@ function App() {
             host={fileSession.host}
             user={fileSession.user}
             port={fileSession.port}
+            deviceId={fileSession.deviceId}
             onClose={closeFiles}
           />
         )}
@@ -191,6 +208,14 @@ function App() {
                 </span>
               </button>
             ))}
+
+            <button
+              onClick={openLocalShell}
+              className="flex items-center text-gray-500 dark:text-gray-400 hover:text-gray-900 dark:hover:text-white px-1.5 py-1 rounded hover:bg-surface-3 transition-colors"
+              title="New local shell"
+            >
+              <Plus className="w-3.5 h-3.5" />
+            </button>
           </div>
 
           {/* Terminal content — only when expanded */}
@@ -206,6 +231,8 @@ function App() {
                     host={session.host}
                     user={session.user}
                     port={session.port}
+                    deviceId={session.deviceId}
+                    source={session.source}
                   />
                 </div>
               ))}
@@ -1,15 +1,16 @@
 import { useState, useEffect, useCallback } from "react";
 import { useTranslation } from "react-i18next";
+import { listen } from "@tauri-apps/api/event";
 import { Plus, RefreshCw, Settings as SettingsIcon } from "lucide-react";
 import VpnToggle from "./VpnToggle";
 import DeviceCard from "./DeviceCard";
-import type { Device } from "../services/types";
+import type { Device, DeviceHealth } from "../services/types";
 import * as api from "../services/api";
 
 interface DashboardProps {
-  onOpenTerminal: (name: string, host: string, user: string, port?: number) => void;
-  onOpenDesktop: (name: string, host: string, user: string, port?: number) => void;
-  onOpenFiles: (name: string, host: string, user: string, port?: number) => void;
+  onOpenTerminal: (name: string, host: string, user: string, port?: number, deviceId?: string) => void;
+  onOpenDesktop: (name: string, host: string, user: string, port?: number, deviceId?: string) => void;
+  onOpenFiles: (name: string, host: string, user: string, port?: number, deviceId?: string) => void;
   onNavigate: (view: string) => void;
 }
 
@@ -42,8 +43,26 @@ export default function Dashboard({ onOpenTerminal, onOpenDesktop, onOpenFiles,
     loadDevices();
   }, [loadDevices]);
 
+  // The backend health monitor pushes online/offline transitions
+  useEffect(() => {
+    const unlisten = listen<DeviceHealth>("device-status", (event) => {
+      const health = event.payload;
+      setDevices((prev) =>
+        prev.map((d) => (d.id === health.device_id ? { ...d, online: health.online } : d)),
+      );
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
   const refresh = async () => {
     setRefreshing(true);
+    try {
+      await api.refreshDeviceStatus();
+    } catch (err) {
+      console.error("Failed to refresh device status:", err);
+    }
     await loadDevices();
     setRefreshing(false);
   };
@@ -1,17 +1,20 @@
+import { useState } from "react";
 import { useTranslation } from "react-i18next";
 import {
   Monitor,
   Terminal as TerminalIcon,
   Trash2,
   FolderOpen,
+  Power,
 } from "lucide-react";
 import type { Device } from "../services/types";
+import * as api from "../services/api";
 
 interface DeviceCardProps {
   device: Device & { online: boolean };
-  onOpenTerminal: (name: string, host: string, user: string, port?: number) => void;
-  onOpenDesktop: (name: string, host: string, user: string, port?: number) => void;
-  onOpenFiles: (name: string, host: string, user: string, port?: number) => void;
+  onOpenTerminal: (name: string, host: string, user: string, port?: number, deviceId?: string) => void;
+  onOpenDesktop: (name: string, host: string, user: string, port?: number, deviceId?: string) => void;
+  onOpenFiles: (name: string, host: string, user: string, port?: number, deviceId?: string) => void;
   onRemove: () => void;
 }
 
@@ -25,6 +28,17 @@ export default function DeviceCard({
   const { t } = useTranslation();
   const sshHost = device.ssh_host || device.vpn_ip;
   const sshPort = device.ssh_port;
+  const [waking, setWaking] = useState(false);
+
+  const handleWake = async () => {
+    setWaking(true);
+    try {
+      await api.wakeDevice(device.id);
+    } catch (err) {
+      console.error("Failed to wake device:", err);
+    }
+    setWaking(false);
+  };
 
   return (
     <div className="bg-surface-2 rounded-lg p-4 border border-surface-3 hover:border-accent/30 transition-colors">
@@ -58,7 +72,7 @@ export default function DeviceCard({
       <div className="flex gap-1.5">
         <button
           onClick={() =>
-            onOpenTerminal(device.name, sshHost, device.ssh_user, sshPort)
+            onOpenTerminal(device.name, sshHost, device.ssh_user, sshPort, device.id)
           }
           className="flex items-center gap-1 px-2.5 py-1.5 bg-surface-3 hover:bg-accent/20 hover:text-accent-hover text-gray-600 dark:text-gray-300 rounded text-xs transition-colors"
           title="SSH Terminal"
@@ -68,7 +82,7 @@ export default function DeviceCard({
         </button>
         <button
           onClick={() =>
-            onOpenDesktop(device.name, sshHost, device.ssh_user, sshPort)
+            onOpenDesktop(device.name, sshHost, device.ssh_user, sshPort, device.id)
           }
           className="flex items-center gap-1 px-2.5 py-1.5 bg-surface-3 hover:bg-accent/20 hover:text-accent-hover text-gray-600 dark:text-gray-300 rounded text-xs transition-colors"
           title="Remote Desktop"
@@ -78,7 +92,7 @@ export default function DeviceCard({
         </button>
         <button
           onClick={() =>
-            onOpenFiles(device.name, sshHost, device.ssh_user, sshPort)
+            onOpenFiles(device.name, sshHost, device.ssh_user, sshPort, device.id)
           }
           className="flex items-center gap-1 px-2.5 py-1.5 bg-surface-3 hover:bg-accent/20 hover:text-accent-hover text-gray-600 dark:text-gray-300 rounded text-xs transition-colors"
           title="File Manager"
@@ -86,6 +100,17 @@ export default function DeviceCard({
           <FolderOpen className="w-3.5 h-3.5" />
           {t('device.files')}
         </button>
+        {device.wol && !device.online && (
+          <button
+            onClick={handleWake}
+            disabled={waking}
+            className="flex items-center gap-1 px-2.5 py-1.5 bg-surface-3 hover:bg-accent/20 hover:text-accent-hover text-gray-600 dark:text-gray-300 rounded text-xs transition-colors disabled:opacity-50"
+            title="Wake-on-LAN"
+          >
+            <Power className="w-3.5 h-3.5" />
+            {waking ? t('device.waking') : t('device.wake')}
+          </button>
+        )}
       </div>
     </div>
   );
@@ -29,6 +29,7 @@ interface FileManagerProps {
   host: string;
   user: string;
   port?: number;
+  deviceId?: string;
   onClose: () => void;
 }
 
@@ -60,6 +61,7 @@ export default function FileManager({
   host,
   user,
   port,
+  deviceId,
   onClose,
 }: FileManagerProps) {
   const { t } = useTranslation();
@@ -86,7 +88,7 @@ export default function FileManager({
       setError(null);
       setSelected(new Set());
       try {
-        const result = await api.sftpList(host, user, port, path);
+        const result = await api.sftpList(host, user, port, path, deviceId);
         setFiles(result);
         setCurrentPath(path);
       } catch (err) {
@@ -95,7 +97,7 @@ export default function FileManager({
         setLoading(false);
       }
     },
-    [host, user, port],
+    [host, user, port, deviceId],
   );
 
   useEffect(() => {
@@ -138,7 +140,7 @@ export default function FileManager({
     } else if (sortBy === "size") {
       cmp = a.size - b.size;
     } else if (sortBy === "modified") {
-      cmp = a.modified.localeCompare(b.modified);
+      cmp = (a.mtime ?? 0) - (b.mtime ?? 0);
     }
     return sortAsc ? cmp : -cmp;
   });
@@ -191,7 +193,7 @@ export default function FileManager({
           : localPath;
       const remoteDest = `${currentPath}/${filename}`;
       try {
-        await api.sftpUpload(host, user, port, String(localPath), remoteDest);
+        await api.sftpUpload(host, user, port, String(localPath), remoteDest, deviceId);
       } catch (err) {
         setError(`Upload failed: ${err}`);
         break;
@@ -215,7 +217,7 @@ export default function FileManager({
 
       setTransferring(true);
       try {
-        await api.sftpDownload(host, user, port, file.path, localPath);
+        await api.sftpDownload(host, user, port, file.path, localPath, deviceId);
       } catch (err) {
         setError(`Download failed: ${err}`);
         break;
@@ -228,7 +230,7 @@ export default function FileManager({
   const handleNewFolder = async () => {
     if (!newFolderName.trim()) return;
     try {
-      await api.sftpMkdir(host, user, port, `${currentPath}/${newFolderName.trim()}`);
+      await api.sftpMkdir(host, user, port, `${currentPath}/${newFolderName.trim()}`, deviceId);
       setShowNewFolder(false);
       setNewFolderName("");
       loadDir(currentPath);
@@ -249,7 +251,7 @@ export default function FileManager({
 
     for (const file of toDelete) {
       try {
-        await api.sftpDelete(host, user, port, file.path);
+        await api.sftpDelete(host, user, port, file.path, deviceId);
       } catch (err) {
         setError(`Delete failed: ${err}`);
         break;
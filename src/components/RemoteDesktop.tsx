@@ -24,6 +24,7 @@ interface RemoteDesktopProps {
   host: string;
   user: string;
   port?: number;
+  deviceId?: string;
   onClose: () => void;
 }
 
@@ -219,6 +220,7 @@ export default function RemoteDesktop({
   host,
   user,
   port,
+  deviceId,
   onClose,
 }: RemoteDesktopProps) {
   const containerRef = useRef<HTMLDivElement>(null);
@@ -263,7 +265,7 @@ export default function RemoteDesktop({
     setIsFirstTime(false);
 
     try {
-      const conn = await api.desktopConnect(host, user, port);
+      const conn = await api.desktopConnect(host, user, port, deviceId);
       setMode(conn.mode as "sunshine" | "vnc");
       setGpuName(conn.gpu_name);
       setStreamUrl(conn.url);
@@ -279,7 +281,7 @@ export default function RemoteDesktop({
       setStatus("error");
       setError(String(err));
     }
-  }, [host, user, port]);
+  }, [host, user, port, deviceId]);
 
   // Listen for progress events
   useEffect(() => {
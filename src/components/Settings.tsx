@@ -75,6 +75,32 @@ export default function Settings({ onNavigate }: SettingsProps) {
     }
   };
 
+  const handleMergeDeviceConfig = async () => {
+    try {
+      setConfigStatus(null);
+      const selected = await open({
+        multiple: false,
+        filters: [{ name: "JSON", extensions: ["json"] }],
+      });
+      if (selected) {
+        const json = await readTextFile(selected);
+        // Conflicting fields keep our values
+        const report = await api.importConfigMerge(json);
+        const count = (status: string) =>
+          report.devices.filter((d) => d.status === status).length;
+        setConfigStatus(
+          t("settings.mergeSuccess", {
+            added: count("added"),
+            updated: count("updated"),
+            conflicts: count("conflict"),
+          }),
+        );
+      }
+    } catch (err) {
+      setConfigStatus(`Import error: ${err}`);
+    }
+  };
+
   const handleImportWgConfig = async () => {
     try {
       const selected = await open({
@@ -276,6 +302,13 @@ export default function Settings({ onNavigate }: SettingsProps) {
                 <Upload className="w-4 h-4" />
                 {t("settings.importConfig")}
               </button>
+              <button
+                onClick={handleMergeDeviceConfig}
+                className="flex items-center gap-1 px-3 py-1.5 bg-surface-3 hover:bg-accent/20 text-gray-600 dark:text-gray-300 rounded text-sm transition-colors"
+              >
+                <Upload className="w-4 h-4" />
+                {t("settings.mergeConfig")}
+              </button>
             </div>
             {configStatus && (
               <p
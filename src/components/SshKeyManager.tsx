@@ -110,6 +110,7 @@ export default function SshKeyManager() {
         host,
         device.ssh_user,
         device.ssh_port,
+        device.id,
       );
       setShowDevicePicker(false);
       setDeployKeyPath(null);
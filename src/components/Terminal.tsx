@@ -1,21 +1,30 @@
-import { useEffect, useRef } from "react";
+import { useEffect, useRef, useState } from "react";
 import { Terminal as XTerm } from "@xterm/xterm";
 import { FitAddon } from "@xterm/addon-fit";
 import { WebglAddon } from "@xterm/addon-webgl";
 import { listen } from "@tauri-apps/api/event";
+import { Channel } from "@tauri-apps/api/core";
 import * as api from "../services/api";
+import type { BroadcastMarker, TerminalSource, TriggerHit } from "../services/types";
 import "@xterm/xterm/css/xterm.css";
 
+/** Ack rendered output every this many bytes */
+const ACK_STEP = 128 * 1024;
+
 interface TerminalProps {
   sessionId: string;
   host: string;
   user: string;
   port?: number;
+  deviceId?: string;
+  /** Local shell or serial console instead of SSH to host */
+  source?: TerminalSource;
 }
 
-export default function Terminal({ sessionId, host, user, port }: TerminalProps) {
+export default function Terminal({ sessionId, host, user, port, deviceId, source }: TerminalProps) {
   const containerRef = useRef<HTMLDivElement>(null);
   const initialized = useRef(false);
+  const [broadcast, setBroadcast] = useState<BroadcastMarker | null>(null);
 
   useEffect(() => {
     if (!containerRef.current || initialized.current) return;
@@ -88,33 +97,108 @@ export default function Terminal({ sessionId, host, user, port }: TerminalProps)
       api.sshResize(sessionId, cols, rows).catch(console.error);
     });
 
-    // Receive PTY output as base64 string → decode → write to xterm
-    const outputUnlisten = listen<string>(
-      `terminal-output-${sessionId}`,
-      (event) => {
-        try {
-          const binary = atob(event.payload);
-          const bytes = new Uint8Array(binary.length);
-          for (let i = 0; i < binary.length; i++) {
-            bytes[i] = binary.charCodeAt(i);
-          }
-          term.write(bytes);
-        } catch (e) {
-          console.error("Failed to decode terminal data:", e);
+    // Output arrives as raw binary batches over a channel. Batches that
+    // come in before the buffered output has been written are held back, and
+    // rendered bytes are acked so the backend can throttle a flood.
+    const output = new Channel<ArrayBuffer>();
+    let pending: Uint8Array[] | null = [];
+    let rendered = 0;
+    let acked = 0;
+    const writeLive = (bytes: Uint8Array) => {
+      term.write(bytes, () => {
+        rendered += bytes.length;
+        if (rendered - acked >= ACK_STEP) {
+          acked = rendered;
+          api.sshAck(sessionId, rendered).catch(() => {});
         }
-      },
-    );
+      });
+    };
+    output.onmessage = (batch) => {
+      const bytes = new Uint8Array(batch);
+      if (pending) {
+        pending.push(bytes);
+      } else {
+        writeLive(bytes);
+      }
+    };
 
     // Session ended
     const exitUnlisten = listen(`terminal-exit-${sessionId}`, () => {
       term.write("\r\n\x1b[90m[Session ended]\x1b[0m\r\n");
     });
 
-    // Connect
-    term.write(`\x1b[90mConnecting to ${user}@${host}...\x1b[0m\r\n`);
-    api.sshOpen(sessionId, host, user, port).catch((err) => {
-      term.write(`\x1b[31mConnection failed: ${err}\x1b[0m\r\n`);
-    });
+    // Persistent sessions reconnect on their own after a dropped connection
+    const reconnectingUnlisten = listen<{ attempt: number; delay_ms: number }>(
+      `terminal-reconnecting-${sessionId}`,
+      (event) => {
+        const secs = Math.round(event.payload.delay_ms / 1000);
+        term.write(`\r\n\x1b[33m[Connection lost, reconnecting in ${secs}s (attempt ${event.payload.attempt})]\x1b[0m\r\n`);
+      },
+    );
+
+    // Highlight trigger: mark the most recent line containing the match.
+    // Output travels separately from the event, so give it a moment to land.
+    const highlightUnlisten = listen<TriggerHit>(
+      `terminal-highlight-${sessionId}`,
+      (event) => {
+        setTimeout(() => {
+          const buffer = term.buffer.active;
+          const cursorLine = buffer.baseY + buffer.cursorY;
+          for (let i = cursorLine; i >= Math.max(0, cursorLine - 200); i--) {
+            if (!buffer.getLine(i)?.translateToString(true).includes(event.payload.matched)) continue;
+            const marker = term.registerMarker(i - cursorLine);
+            if (marker) {
+              term.registerDecoration({ marker, width: term.cols, backgroundColor: "#854d0e" });
+            }
+            break;
+          }
+        }, 50);
+      },
+    );
+
+    // Broadcast group membership, shown as a badge over the terminal
+    const broadcastUnlisten = listen<BroadcastMarker | null>(
+      `terminal-broadcast-${sessionId}`,
+      (event) => setBroadcast(event.payload),
+    );
+
+    // A session that is still alive (e.g. after a reload) is re-subscribed
+    // and its buffered output replayed instead of opening a new one
+    const writeBase64 = (b64: string) => {
+      const binary = atob(b64);
+      const bytes = new Uint8Array(binary.length);
+      for (let i = 0; i < binary.length; i++) {
+        bytes[i] = binary.charCodeAt(i);
+      }
+      term.write(bytes);
+    };
+    api
+      .sshSessions()
+      .then((sessions) => sessions.some((s) => s.session_id === sessionId))
+      .catch(() => false)
+      .then((alive) => (alive ? undefined : openSession()))
+      .then(() => api.sshSubscribe(sessionId, output))
+      .then((replay) => {
+        writeBase64(replay.data);
+        rendered = acked = replay.end;
+        pending?.forEach(writeLive);
+        pending = null;
+      })
+      .catch((err) => {
+        term.write(`\x1b[31mConnection failed: ${err}\x1b[0m\r\n`);
+      });
+
+    function openSession(): Promise<void> {
+      if (source?.kind === "local") {
+        return api.localOpen(sessionId, source.shell);
+      }
+      if (source?.kind === "serial") {
+        term.write(`\x1b[90mOpening ${source.serial.path} at ${source.serial.baud_rate} baud...\x1b[0m\r\n`);
+        return api.serialOpen(sessionId, source.serial);
+      }
+      term.write(`\x1b[90mConnecting to ${user}@${host}...\x1b[0m\r\n`);
+      return api.sshOpen(sessionId, host, user, port, deviceId);
+    }
 
     // Auto-fit on container resize
     const resizeObserver = new ResizeObserver(() => {
@@ -127,8 +211,11 @@ export default function Terminal({ sessionId, host, user, port }: TerminalProps)
     resizeObserver.observe(containerRef.current);
 
     return () => {
-      outputUnlisten.then((fn) => fn());
+      output.onmessage = () => {};
       exitUnlisten.then((fn) => fn());
+      reconnectingUnlisten.then((fn) => fn());
+      broadcastUnlisten.then((fn) => fn());
+      highlightUnlisten.then((fn) => fn());
       resizeObserver.disconnect();
       api.sshClose(sessionId).catch(() => {});
       term.dispose();
@@ -137,6 +224,17 @@ export default function Terminal({ sessionId, host, user, port }: TerminalProps)
   }, [sessionId, host, user]);
 
   return (
-    <div ref={containerRef} className="w-full h-full bg-surface-0 p-1" />
+    <div className="relative w-full h-full">
+      <div ref={containerRef} className="w-full h-full bg-surface-0 p-1" />
+      {broadcast && (
+        <div
+          className={`absolute top-1 right-3 z-10 rounded px-2 py-0.5 text-xs ${
+            broadcast.receiving ? "bg-yellow-400/80 text-gray-900" : "bg-surface-2 text-gray-400"
+          }`}
+        >
+          {broadcast.receiving ? "Broadcast" : "Broadcast paused"}: {broadcast.name}
+        </div>
+      )}
+    </div>
   );
 }
@@ -1,5 +1,5 @@
-import { invoke } from "@tauri-apps/api/core";
-import type { Device, VpnStatus, RemoteFile } from "./types";
+import { invoke, Channel } from "@tauri-apps/api/core";
+import type { Device, VpnStatus, RemoteFile, SshImportCandidate, ConfigBackup, DeviceGroup, GroupDefaults, DeviceHealth, WakeOnLan, MergeReport, MergeSelection, MergeResolutions, AuditEntry, AuditQuery, RecentConnection, Recording, SshBackend, RemoteAttach, RemoteSession, SessionReplay, TerminalSessionInfo, BroadcastGroup, LocalShell, SerialConfig, SerialPortInfo, OutputTrigger, Snippet, ShareInvite, ShareInfo, TransferDirection, TransferJob, TreeOptions, TreePlan, SyncOptions, SyncPlan, RemoteOp, OpResult } from "./types";
 
 // VPN commands
 export async function vpnConnect(configPath: string): Promise<VpnStatus> {
@@ -28,8 +28,39 @@ export async function sshOpen(
   host: string,
   user: string,
   port?: number,
+  deviceId?: string,
+  record?: boolean,
+  backend?: SshBackend,
+  attach?: RemoteAttach,
+  recordInput?: boolean,
 ): Promise<void> {
-  return invoke("ssh_open", { sessionId, host, user, port: port ?? null });
+  return invoke("ssh_open", {
+    sessionId,
+    host,
+    user,
+    port: port ?? null,
+    deviceId: deviceId ?? null,
+    record: record ?? null,
+    backend: backend ?? null,
+    attach: attach ?? null,
+    recordInput: recordInput ?? null,
+  });
+}
+
+export async function sshAttach(sessionId: string, deviceId: string, attach: RemoteAttach): Promise<void> {
+  return invoke("ssh_attach", { sessionId, deviceId, attach });
+}
+
+export async function remoteSessionsList(deviceId: string): Promise<RemoteSession[]> {
+  return invoke("remote_sessions_list", { deviceId });
+}
+
+export async function sshBackendGet(): Promise<SshBackend> {
+  return invoke("ssh_backend_get");
+}
+
+export async function sshBackendSet(backend: SshBackend): Promise<void> {
+  return invoke("ssh_backend_set", { backend });
 }
 
 export async function sshWrite(
@@ -51,13 +82,120 @@ export async function sshClose(sessionId: string): Promise<void> {
   return invoke("ssh_close", { sessionId });
 }
 
+// Local shell and serial console tabs share ssh_write/ssh_resize/ssh_close
+export async function localOpen(sessionId: string, shell?: LocalShell, record?: boolean): Promise<void> {
+  return invoke("local_open", { sessionId, shell: shell ?? null, record: record ?? null });
+}
+
+export async function serialOpen(sessionId: string, serial: SerialConfig, record?: boolean): Promise<void> {
+  return invoke("serial_open", { sessionId, serial, record: record ?? null });
+}
+
+export async function serialPortsList(): Promise<SerialPortInfo[]> {
+  return invoke("serial_ports_list");
+}
+
+/** Stream a session's output to `output` as raw batches; returns what is buffered so far */
+export async function sshSubscribe(sessionId: string, output: Channel<ArrayBuffer>): Promise<SessionReplay> {
+  return invoke("ssh_subscribe", { sessionId, output });
+}
+
+/** Output up to stream offset `offset` has been rendered */
+export async function sshAck(sessionId: string, offset: number): Promise<void> {
+  return invoke("ssh_ack", { sessionId, offset });
+}
+
+export async function sshReplay(sessionId: string, from?: number): Promise<SessionReplay> {
+  return invoke("ssh_replay", { sessionId, from: from ?? null });
+}
+
+export async function sshSessions(): Promise<TerminalSessionInfo[]> {
+  return invoke("ssh_sessions");
+}
+
+// Broadcast input: keystrokes typed into one member go to all of them
+export async function broadcastList(): Promise<BroadcastGroup[]> {
+  return invoke("broadcast_list");
+}
+
+export async function broadcastCreate(name: string, sessionIds: string[]): Promise<BroadcastGroup> {
+  return invoke("broadcast_create", { name, sessionIds });
+}
+
+export async function broadcastJoin(groupId: string, sessionId: string): Promise<void> {
+  return invoke("broadcast_join", { groupId, sessionId });
+}
+
+export async function broadcastLeave(sessionId: string): Promise<void> {
+  return invoke("broadcast_leave", { sessionId });
+}
+
+export async function broadcastExclude(sessionId: string, excluded: boolean): Promise<void> {
+  return invoke("broadcast_exclude", { sessionId, excluded });
+}
+
+export async function broadcastDissolve(groupId: string): Promise<void> {
+  return invoke("broadcast_dissolve", { groupId });
+}
+
+export async function shareInvite(sessionId: string, onVpn?: boolean): Promise<ShareInvite> {
+  return invoke("share_invite", { sessionId, onVpn: onVpn ?? null });
+}
+
+export async function shareList(): Promise<ShareInfo[]> {
+  return invoke("share_list");
+}
+
+export async function shareSetControl(sessionId: string, observerId: string, control: boolean): Promise<void> {
+  return invoke("share_set_control", { sessionId, observerId, control });
+}
+
+export async function shareDisconnect(sessionId: string, observerId: string): Promise<void> {
+  return invoke("share_disconnect", { sessionId, observerId });
+}
+
+export async function shareStop(sessionId: string): Promise<void> {
+  return invoke("share_stop", { sessionId });
+}
+
+export async function sshRecordStart(sessionId: string, recordInput?: boolean): Promise<string> {
+  return invoke("ssh_record_start", { sessionId, recordInput: recordInput ?? null });
+}
+
+export async function sshRecordStop(sessionId: string): Promise<string | null> {
+  return invoke("ssh_record_stop", { sessionId });
+}
+
+export async function sshRecordingsList(): Promise<Recording[]> {
+  return invoke("ssh_recordings_list");
+}
+
+// Output triggers of a running session (device triggers are saved with the device)
+export async function terminalTriggersGet(sessionId: string): Promise<OutputTrigger[]> {
+  return invoke("terminal_triggers_get", { sessionId });
+}
+
+export async function terminalTriggersSet(sessionId: string, triggers: OutputTrigger[]): Promise<void> {
+  return invoke("terminal_triggers_set", { sessionId, triggers });
+}
+
 // Device commands
 interface DeviceWithStatus extends Device {
   online: boolean;
+  health: DeviceHealth | null;
+  inherited: string[];
 }
 
-export async function listDevices(): Promise<DeviceWithStatus[]> {
-  return invoke("list_devices");
+export async function listDevices(tag?: string, group?: string): Promise<DeviceWithStatus[]> {
+  return invoke("list_devices", { tag: tag ?? null, group: group ?? null });
+}
+
+export async function refreshDeviceStatus(): Promise<DeviceHealth[]> {
+  return invoke("refresh_device_status");
+}
+
+export async function setHealthInterval(seconds: number): Promise<void> {
+  return invoke("set_health_interval", { seconds });
 }
 
 export async function addDevice(
@@ -67,6 +205,12 @@ export async function addDevice(
   rustdeskId?: string,
   sshHost?: string,
   sshPort?: number,
+  jumpHosts?: string[],
+  identityFile?: string,
+  group?: string,
+  tags?: string[],
+  wol?: WakeOnLan,
+  recordSessions?: boolean,
 ): Promise<Device> {
   return invoke("add_device", {
     name,
@@ -75,13 +219,72 @@ export async function addDevice(
     rustdeskId: rustdeskId ?? null,
     sshHost: sshHost ?? null,
     sshPort: sshPort ?? null,
+    jumpHosts: jumpHosts ?? null,
+    identityFile: identityFile ?? null,
+    group: group ?? null,
+    tags: tags ?? null,
+    wol: wol ?? null,
+    recordSessions: recordSessions ?? null,
   });
 }
 
+export async function updateDevice(device: Omit<Device, "online">): Promise<Device> {
+  return invoke("update_device", { device });
+}
+
 export async function removeDevice(id: string): Promise<void> {
   return invoke("remove_device", { id });
 }
 
+export async function wakeDevice(
+  deviceId: string,
+  via?: string,
+  waitSecs?: number,
+): Promise<DeviceHealth | null> {
+  return invoke("wake_device", { deviceId, via: via ?? null, waitSecs: waitSecs ?? null });
+}
+
+// Device groups
+export async function listGroups(): Promise<DeviceGroup[]> {
+  return invoke("list_groups");
+}
+
+export async function addGroup(
+  name: string,
+  parent?: string,
+  defaults?: GroupDefaults,
+): Promise<DeviceGroup> {
+  return invoke("add_group", { name, parent: parent ?? null, defaults: defaults ?? null });
+}
+
+export async function updateGroup(group: DeviceGroup): Promise<DeviceGroup> {
+  return invoke("update_group", { group });
+}
+
+export async function removeGroup(id: string): Promise<void> {
+  return invoke("remove_group", { id });
+}
+
+export async function snippetsList(deviceId?: string): Promise<Snippet[]> {
+  return invoke("snippets_list", { deviceId: deviceId ?? null });
+}
+
+export async function snippetSave(snippet: Snippet): Promise<Snippet> {
+  return invoke("snippet_save", { snippet });
+}
+
+export async function snippetDelete(id: string): Promise<void> {
+  return invoke("snippet_delete", { id });
+}
+
+export async function snippetSend(
+  sessionId: string,
+  snippetId: string,
+  params?: Record<string, string>,
+): Promise<void> {
+  return invoke("snippet_send", { sessionId, snippetId, params: params ?? null });
+}
+
 export async function pingDevice(ip: string): Promise<boolean> {
   return invoke("ping_device", { ip });
 }
@@ -95,6 +298,42 @@ export async function importConfig(jsonStr: string): Promise<void> {
   return invoke("import_config", { jsonStr });
 }
 
+export async function importConfigPreview(
+  jsonStr: string,
+  selection?: MergeSelection,
+): Promise<MergeReport> {
+  return invoke("import_config_preview", { jsonStr, selection: selection ?? null });
+}
+
+export async function importConfigMerge(
+  jsonStr: string,
+  selection?: MergeSelection,
+  resolutions?: MergeResolutions,
+): Promise<MergeReport> {
+  return invoke("import_config_merge", {
+    jsonStr,
+    selection: selection ?? null,
+    resolutions: resolutions ?? null,
+  });
+}
+
+// OpenSSH config import/export
+export async function sshConfigPreview(path?: string): Promise<SshImportCandidate[]> {
+  return invoke("ssh_config_preview", { path: path ?? null });
+}
+
+export async function sshConfigImport(
+  aliases: string[],
+  overwrite: boolean,
+  path?: string,
+): Promise<Device[]> {
+  return invoke("ssh_config_import", { path: path ?? null, aliases, overwrite });
+}
+
+export async function sshConfigExport(deviceIds?: string[], path?: string): Promise<string> {
+  return invoke("ssh_config_export", { deviceIds: deviceIds ?? null, path: path ?? null });
+}
+
 // Desktop commands — smart auto-detect + embedded viewer
 export interface DesktopConnection {
   mode: "sunshine" | "vnc";
@@ -115,8 +354,9 @@ export async function desktopConnect(
   host: string,
   user: string,
   port?: number,
+  deviceId?: string,
 ): Promise<DesktopConnection> {
-  return invoke("desktop_connect", { host, user, port: port ?? null });
+  return invoke("desktop_connect", { host, user, port: port ?? null, deviceId: deviceId ?? null });
 }
 
 /** Detect GPU without connecting */
@@ -124,8 +364,9 @@ export async function detectGpu(
   host: string,
   user: string,
   port?: number,
+  deviceId?: string,
 ): Promise<GpuInfo> {
-  return invoke("detect_gpu", { host, user, port: port ?? null });
+  return invoke("detect_gpu", { host, user, port: port ?? null, deviceId: deviceId ?? null });
 }
 
 /** Legacy VNC connect */
@@ -133,8 +374,16 @@ export async function vncConnect(
   host: string,
   user: string,
   vncPort?: number,
+  port?: number,
+  deviceId?: string,
 ): Promise<number> {
-  return invoke("vnc_connect", { host, user, vncPort: vncPort ?? null });
+  return invoke("vnc_connect", {
+    host,
+    user,
+    vncPort: vncPort ?? null,
+    port: port ?? null,
+    deviceId: deviceId ?? null,
+  });
 }
 
 export async function vncDisconnect(): Promise<void> {
@@ -151,8 +400,19 @@ export async function sftpList(
   user: string,
   port: number | undefined,
   path: string,
+  deviceId?: string,
 ): Promise<RemoteFile[]> {
-  return invoke("sftp_list", { host, user, port: port ?? null, path });
+  return invoke("sftp_list", { host, user, port: port ?? null, path, deviceId: deviceId ?? null });
+}
+
+export async function sftpStat(
+  host: string,
+  user: string,
+  port: number | undefined,
+  path: string,
+  deviceId?: string,
+): Promise<RemoteFile> {
+  return invoke("sftp_stat", { host, user, port: port ?? null, path, deviceId: deviceId ?? null });
 }
 
 export async function sftpUpload(
@@ -161,8 +421,16 @@ export async function sftpUpload(
   port: number | undefined,
   localPath: string,
   remotePath: string,
+  deviceId?: string,
 ): Promise<void> {
-  return invoke("sftp_upload", { host, user, port: port ?? null, localPath, remotePath });
+  return invoke("sftp_upload", {
+    host,
+    user,
+    port: port ?? null,
+    localPath,
+    remotePath,
+    deviceId: deviceId ?? null,
+  });
 }
 
 export async function sftpDownload(
@@ -171,8 +439,77 @@ export async function sftpDownload(
   port: number | undefined,
   remotePath: string,
   localPath: string,
+  deviceId?: string,
 ): Promise<void> {
-  return invoke("sftp_download", { host, user, port: port ?? null, remotePath, localPath });
+  return invoke("sftp_download", {
+    host,
+    user,
+    port: port ?? null,
+    remotePath,
+    localPath,
+    deviceId: deviceId ?? null,
+  });
+}
+
+export async function sftpUploadDir(
+  host: string,
+  user: string,
+  port: number | undefined,
+  localPath: string,
+  remotePath: string,
+  options?: TreeOptions,
+  deviceId?: string,
+): Promise<TreePlan> {
+  return invoke("sftp_upload_dir", {
+    host,
+    user,
+    port: port ?? null,
+    localPath,
+    remotePath,
+    options: options ?? null,
+    deviceId: deviceId ?? null,
+  });
+}
+
+export async function sftpDownloadDir(
+  host: string,
+  user: string,
+  port: number | undefined,
+  remotePath: string,
+  localPath: string,
+  options?: TreeOptions,
+  deviceId?: string,
+): Promise<TreePlan> {
+  return invoke("sftp_download_dir", {
+    host,
+    user,
+    port: port ?? null,
+    remotePath,
+    localPath,
+    options: options ?? null,
+    deviceId: deviceId ?? null,
+  });
+}
+
+/** Sync a directory tree; call with `dry_run` first to get the plan */
+export async function sftpSync(
+  host: string,
+  user: string,
+  port: number | undefined,
+  localPath: string,
+  remotePath: string,
+  options: SyncOptions,
+  deviceId?: string,
+): Promise<SyncPlan> {
+  return invoke("sftp_sync", {
+    host,
+    user,
+    port: port ?? null,
+    localPath,
+    remotePath,
+    options,
+    deviceId: deviceId ?? null,
+  });
 }
 
 export async function sftpMkdir(
@@ -180,8 +517,9 @@ export async function sftpMkdir(
   user: string,
   port: number | undefined,
   path: string,
+  deviceId?: string,
 ): Promise<void> {
-  return invoke("sftp_mkdir", { host, user, port: port ?? null, path });
+  return invoke("sftp_mkdir", { host, user, port: port ?? null, path, deviceId: deviceId ?? null });
 }
 
 export async function sftpDelete(
@@ -189,8 +527,83 @@ export async function sftpDelete(
   user: string,
   port: number | undefined,
   path: string,
+  deviceId?: string,
+): Promise<void> {
+  return invoke("sftp_delete", { host, user, port: port ?? null, path, deviceId: deviceId ?? null });
+}
+
+export async function sftpOp(
+  host: string,
+  user: string,
+  port: number | undefined,
+  op: RemoteOp,
+  deviceId?: string,
 ): Promise<void> {
-  return invoke("sftp_delete", { host, user, port: port ?? null, path });
+  return invoke("sftp_op", { host, user, port: port ?? null, op, deviceId: deviceId ?? null });
+}
+
+/** Apply operations over one connection; each gets its own result */
+export async function sftpBatch(
+  host: string,
+  user: string,
+  port: number | undefined,
+  ops: RemoteOp[],
+  deviceId?: string,
+): Promise<OpResult[]> {
+  return invoke("sftp_batch", { host, user, port: port ?? null, ops, deviceId: deviceId ?? null });
+}
+
+// Transfer queue
+export async function transferEnqueue(
+  host: string,
+  user: string,
+  port: number | undefined,
+  direction: TransferDirection,
+  localPath: string,
+  remotePath: string,
+  deviceId?: string,
+): Promise<TransferJob> {
+  return invoke("transfer_enqueue", {
+    host,
+    user,
+    port: port ?? null,
+    direction,
+    localPath,
+    remotePath,
+    deviceId: deviceId ?? null,
+  });
+}
+
+export async function transferList(): Promise<TransferJob[]> {
+  return invoke("transfer_list");
+}
+
+export async function transferPause(id: string): Promise<void> {
+  return invoke("transfer_pause", { id });
+}
+
+export async function transferResume(id: string): Promise<void> {
+  return invoke("transfer_resume", { id });
+}
+
+export async function transferCancel(id: string): Promise<void> {
+  return invoke("transfer_cancel", { id });
+}
+
+export async function transferRemove(id: string): Promise<void> {
+  return invoke("transfer_remove", { id });
+}
+
+export async function transferClearFinished(): Promise<void> {
+  return invoke("transfer_clear_finished");
+}
+
+export async function transferGetParallel(): Promise<number> {
+  return invoke("transfer_get_parallel");
+}
+
+export async function transferSetParallel(maxParallel: number): Promise<void> {
+  return invoke("transfer_set_parallel", { maxParallel });
 }
 
 // SSH Key Management
@@ -211,8 +624,14 @@ export async function sshKeyGenerate(name: string, passphrase: string): Promise<
   return invoke("ssh_key_generate", { name, passphrase });
 }
 
-export async function sshKeyCopyToRemote(keyPath: string, host: string, user: string, port?: number): Promise<void> {
-  return invoke("ssh_key_copy_to_remote", { keyPath, host, user, port: port ?? null });
+export async function sshKeyCopyToRemote(
+  keyPath: string,
+  host: string,
+  user: string,
+  port?: number,
+  deviceId?: string,
+): Promise<void> {
+  return invoke("ssh_key_copy_to_remote", { keyPath, host, user, port: port ?? null, deviceId: deviceId ?? null });
 }
 
 // Config encryption
@@ -231,3 +650,25 @@ export async function setConfigPassword(password: string): Promise<void> {
 export async function removeConfigPassword(): Promise<void> {
   return invoke("remove_config_password");
 }
+
+// Config backups
+export async function configBackupsList(): Promise<ConfigBackup[]> {
+  return invoke("config_backups_list");
+}
+
+export async function configBackupRestore(name: string, password?: string): Promise<void> {
+  return invoke("config_backup_restore", { name, password: password ?? null });
+}
+
+// Audit log
+export async function auditQuery(query?: AuditQuery): Promise<AuditEntry[]> {
+  return invoke("audit_query", { query: query ?? null });
+}
+
+export async function auditExport(format: "json" | "csv", path: string, query?: AuditQuery): Promise<number> {
+  return invoke("audit_export", { format, path, query: query ?? null });
+}
+
+export async function recentConnections(limit?: number): Promise<RecentConnection[]> {
+  return invoke("recent_connections", { limit: limit ?? null });
+}
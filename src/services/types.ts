@@ -6,9 +6,113 @@ export interface Device {
   rustdesk_id?: string;
   ssh_host?: string;
   ssh_port?: number;
+  /** null inherits from the groups; [] connects directly */
+  jump_hosts: string[] | null;
+  identity_file?: string;
+  group?: string;
+  tags: string[];
+  wol?: WakeOnLan;
+  record_sessions: boolean;
+  triggers?: OutputTrigger[];
+  initial_dir?: string;
+  env?: Record<string, string>;
+  startup_command?: string;
+  forward_agent?: boolean;
+  forward_x11?: boolean;
+  ssh_options?: string[];
   online: boolean;
 }
 
+export interface WakeOnLan {
+  mac_address: string;
+  broadcast?: string;
+  interface?: string;
+  relay?: string;
+}
+
+export interface DeviceHealth {
+  device_id: string;
+  online: boolean;
+  latency_ms: number | null;
+  error: string | null;
+  checked_at_ms: number;
+  changed_at_ms: number;
+}
+
+export interface GroupDefaults {
+  ssh_user?: string;
+  ssh_port?: number;
+  jump_hosts?: string[];
+  identity_file?: string;
+}
+
+export interface DeviceGroup {
+  id: string;
+  name: string;
+  parent?: string;
+  defaults: GroupDefaults;
+}
+
+export interface Snippet {
+  id: string;
+  name: string;
+  /** `{{param}}` placeholders are filled in when sending */
+  body: string;
+  /** Only for devices in this group; unset for all sessions */
+  group?: string;
+  /** Press Enter after the text */
+  execute: boolean;
+}
+
+export interface SshImportCandidate {
+  alias: string;
+  device: Omit<Device, "online">;
+  existing_id: string | null;
+  implicit: boolean;
+}
+
+export interface FieldConflict {
+  field: string;
+  ours: unknown;
+  theirs: unknown;
+}
+
+export interface DeviceMerge {
+  incoming_id: string;
+  existing_id: string | null;
+  name: string;
+  status: "added" | "updated" | "conflict" | "unchanged";
+  filled: string[];
+  conflicts: FieldConflict[];
+}
+
+export interface GroupMerge {
+  incoming_id: string;
+  existing_id: string | null;
+  name: string;
+}
+
+export interface MergeReport {
+  devices: DeviceMerge[];
+  groups: GroupMerge[];
+}
+
+export interface MergeSelection {
+  device_ids?: string[];
+  group_ids?: string[];
+}
+
+/** Incoming device id -> field -> which side wins */
+export type MergeResolutions = Record<string, Record<string, "ours" | "theirs">>;
+
+export interface ConfigBackup {
+  name: string;
+  path: string;
+  created_ms: number;
+  size: number;
+  encrypted: boolean;
+}
+
 export interface VpnStatus {
   connected: boolean;
   local_ip?: string;
@@ -19,7 +123,9 @@ export interface VpnStatus {
 }
 
 export interface AppConfig {
+  schema_version: number;
   devices: Device[];
+  groups: DeviceGroup[];
   wg_config_path?: string;
   default_ssh_user: string;
   rustdesk_server?: string;
@@ -33,11 +139,353 @@ export interface SshSession {
   user: string;
 }
 
+export type RemoteFileKind = "file" | "dir" | "symlink" | "other";
+
 export interface RemoteFile {
   name: string;
   path: string;
+  /** Also true for symlinks to directories */
   is_dir: boolean;
   size: number;
   modified: string;
   permissions: string;
+  kind: RemoteFileKind;
+  /** Unix time in seconds */
+  mtime: number | null;
+  /** Permission bits, without the file type */
+  mode: number | null;
+  uid: number | null;
+  gid: number | null;
+  owner: string | null;
+  group: string | null;
+  symlink_target: string | null;
+}
+
+/** A change to remote files; `rename` and `copy` go into `to` if it is a directory */
+export type RemoteOp =
+  | { op: "rename"; from: string; to: string }
+  | { op: "copy"; from: string; to: string }
+  | { op: "chmod"; path: string; mode: number }
+  | { op: "chown"; path: string; uid?: number; gid?: number }
+  | { op: "symlink"; link: string; target: string }
+  | { op: "touch"; path: string }
+  | { op: "mkdir"; path: string }
+  | { op: "delete"; path: string };
+
+/** Outcome of one operation of a batch */
+export interface OpResult {
+  path: string;
+  ok: boolean;
+  error: string | null;
+}
+
+export type TransferDirection = "upload" | "download";
+
+export type TransferStatus = "queued" | "running" | "paused" | "completed" | "failed" | "cancelled";
+
+export interface TransferJob {
+  id: string;
+  direction: TransferDirection;
+  target: { host: string; user: string; port: number | null; device_id: string | null };
+  local_path: string;
+  remote_path: string;
+  status: TransferStatus;
+  transferred: number;
+  /** 0 until the job has started */
+  total: number;
+  error: string | null;
+  attempts: number;
+  /** Source file when the first attempt started */
+  source_size: number | null;
+  source_mtime: number | null;
+  created_ms: number;
+  finished_ms: number | null;
+}
+
+export interface TreeOptions {
+  /** Only files matching one of these are transferred; all when empty */
+  include?: string[];
+  /** e.g. `__pycache__`, `*.ckpt`, `logs/**` */
+  exclude?: string[];
+  /** Only scan and return the plan */
+  dry_run?: boolean;
+}
+
+export interface TreeEntry {
+  /** Relative to the tree root */
+  path: string;
+  kind: RemoteFileKind;
+  size: number;
+  mode: number | null;
+  atime: number | null;
+  mtime: number | null;
+  link_target: string | null;
+}
+
+/** What a directory transfer copies, or would copy for a dry run */
+export interface TreePlan {
+  entries: TreeEntry[];
+  skipped: string[];
+  files: number;
+  dirs: number;
+  symlinks: number;
+  bytes: number;
+  warnings: string[];
+}
+
+export interface SyncOptions {
+  /** "upload" makes the remote tree match the local one, "download" the reverse */
+  direction: TransferDirection;
+  compare?: "size_mtime" | "checksum";
+  /** Remove destination files the source doesn't have; excluded paths are kept */
+  delete?: boolean;
+  include?: string[];
+  exclude?: string[];
+  /** "auto" uses rsync when both ends have it */
+  engine?: "auto" | "rsync" | "builtin";
+  /** Only work out and return the plan */
+  dry_run?: boolean;
+}
+
+export type SyncAction = "create" | "update" | "replace" | "link" | "mkdir" | "delete";
+
+export interface SyncItem {
+  path: string;
+  action: SyncAction;
+  kind: RemoteFileKind;
+  size: number;
+}
+
+export interface SyncPlan {
+  engine: "rsync" | "builtin";
+  direction: TransferDirection;
+  items: SyncItem[];
+  /** Size of the files to copy; rsync may send less */
+  bytes: number;
+  /** Files found identical; only counted by the built-in engine */
+  unchanged: number;
+  warnings: string[];
+}
+
+/** Payload of the `file-transfer-progress` event */
+export interface TransferProgress {
+  id: string;
+  filename: string;
+  direction: TransferDirection;
+  status: TransferStatus;
+  bytes: number;
+  total: number;
+  /** Only a completed job reports 100 */
+  percent: number;
+  bytes_per_sec: number;
+  eta_secs: number | null;
+}
+
+export type AuditAction =
+  | "terminal_session"
+  | "sftp_list"
+  | "sftp_upload"
+  | "sftp_download"
+  | "sftp_mkdir"
+  | "sftp_delete"
+  | "sftp_rename"
+  | "sftp_copy"
+  | "sftp_chmod"
+  | "sftp_chown"
+  | "sftp_symlink"
+  | "sftp_touch"
+  | "desktop_connect"
+  | "vnc_connect"
+  | "key_deploy";
+
+export interface AuditEntry {
+  timestamp_ms: number;
+  action: AuditAction;
+  local_user: string;
+  device_id?: string;
+  host: string;
+  user: string;
+  port?: number;
+  detail?: string;
+  duration_ms?: number;
+  success: boolean;
+  error?: string;
+}
+
+export interface AuditQuery {
+  device_id?: string;
+  host?: string;
+  actions?: AuditAction[];
+  since_ms?: number;
+  until_ms?: number;
+  /** true = only failures, false = only successes */
+  failed?: boolean;
+  limit?: number;
+}
+
+export interface RecentConnection {
+  device_id?: string;
+  host: string;
+  user: string;
+  port?: number;
+  action: AuditAction;
+  last_ms: number;
+}
+
+export interface Recording {
+  name: string;
+  path: string;
+  size: number;
+  modified_ms: number;
+}
+
+export type SshBackend = "auto" | "native" | "process";
+
+/** Payload of `terminal-error-{sessionId}` from the native SSH backend */
+export type SshConnectError =
+  | { kind: "connect"; host: string; message: string }
+  | { kind: "host_key_mismatch"; host: string; port: number }
+  | { kind: "auth_failed"; host: string; user: string; tried: string[] }
+  | { kind: "channel"; message: string };
+
+export type Multiplexer = "tmux" | "screen";
+
+/** Named tmux/screen session to attach to, created if missing */
+export interface RemoteAttach {
+  multiplexer: Multiplexer;
+  name: string;
+}
+
+export interface RemoteSession {
+  multiplexer: Multiplexer;
+  name: string;
+  windows?: number;
+  attached: boolean;
+  created_ms?: number;
+}
+
+export type TerminalKind = "ssh" | "local" | "serial";
+
+export interface TerminalSessionInfo {
+  session_id: string;
+  kind: TerminalKind;
+  title: string;
+  /** Remote host, "localhost" or the serial device */
+  host: string;
+  /** Empty for serial consoles */
+  user: string;
+  device_id?: string;
+  status: "running" | "reconnecting" | "exited";
+  started_ms: number;
+  bytes_received: number;
+  bytes_sent: number;
+  exit_code?: number;
+  recording: boolean;
+  attach?: RemoteAttach;
+}
+
+export interface SessionReplay {
+  /** base64 */
+  data: string;
+  start: number;
+  end: number;
+}
+
+export interface BroadcastGroup {
+  id: string;
+  name: string;
+  members: string[];
+  /** Members that neither send nor receive broadcast input */
+  excluded: string[];
+}
+
+/** One-time link for watching a session over WebSocket */
+export interface ShareInvite {
+  session_id: string;
+  url: string;
+  expires_ms: number;
+}
+
+export interface ShareObserver {
+  id: string;
+  address: string;
+  connected_ms: number;
+  /** May type into the session */
+  control: boolean;
+}
+
+/** Payload of `terminal-share-{session}`; null once sharing stops */
+export interface ShareInfo {
+  session_id: string;
+  address: string;
+  port: number;
+  pending_invites: number;
+  observers: ShareObserver[];
+}
+
+/** Payload of `terminal-broadcast-{session}`; null once the session leaves its group */
+export interface BroadcastMarker {
+  group_id: string;
+  name: string;
+  receiving: boolean;
+}
+
+export interface LocalShell {
+  /** Defaults to $SHELL */
+  shell?: string;
+  /** Defaults to the home directory */
+  cwd?: string;
+}
+
+export type SerialParity = "none" | "odd" | "even";
+export type SerialFlowControl = "none" | "software" | "hardware";
+
+export interface SerialConfig {
+  /** e.g. /dev/ttyUSB0 or COM3 */
+  path: string;
+  baud_rate: number;
+  parity?: SerialParity;
+  flow_control?: SerialFlowControl;
+  /** 5-8, default 8 */
+  data_bits?: number;
+  /** 1 or 2, default 1 */
+  stop_bits?: number;
+}
+
+export interface SerialPortInfo {
+  path: string;
+  port_type: "usb" | "pci" | "bluetooth" | "unknown";
+  description?: string;
+}
+
+/** What a terminal tab opens when it isn't an SSH session */
+export type TerminalSource =
+  | { kind: "local"; shell?: LocalShell }
+  | { kind: "serial"; serial: SerialConfig };
+
+export type TriggerAction = "notify" | "event" | "highlight";
+
+/** Regex matched against each line of terminal output */
+export interface OutputTrigger {
+  id: string;
+  /** Notification title; empty to show the pattern */
+  name?: string;
+  pattern: string;
+  case_insensitive?: boolean;
+  actions: TriggerAction[];
+  /** Default 10, at least 1 */
+  cooldown_secs?: number;
+  /** Default true */
+  enabled?: boolean;
+}
+
+/** Payload of `terminal-highlight-{session}`, and `hit` in `terminal-trigger` */
+export interface TriggerHit {
+  trigger_id: string;
+  name: string;
+  line: string;
+  matched: string;
+  fired_ms: number;
+  /** Matches swallowed by the cooldown since the previous firing */
+  suppressed: number;
 }